@@ -0,0 +1,23 @@
+-- Single-use tokens for email verification and password reset.
+-- The raw token is only ever sent to the user; we keep a SHA-256 hash of its secret part.
+
+CREATE TYPE user_token_purpose AS ENUM (
+    'email_verification',
+    'password_reset'
+);
+
+CREATE TABLE user_tokens (
+    id UUID PRIMARY KEY,
+    email TEXT NOT NULL,
+    purpose user_token_purpose NOT NULL,
+    token_hash TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_user_tokens_email_purpose ON user_tokens (email, purpose);
+
+-- Tokens used to be JWTs stored in plaintext
+DROP TABLE password_reset_tokens;
+ALTER TABLE pending_users DROP COLUMN verification_token;
@@ -0,0 +1,18 @@
+-- Account state managed by admins
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN password_reset_required BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Needed to list unverified users next to verified ones
+ALTER TABLE pending_users ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- Trail of every operation an admin performed on a user account (including impersonation)
+CREATE TABLE admin_actions (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    admin_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    user_id UUID NOT NULL,
+    action TEXT NOT NULL, -- change_role, update_attributes, disable, enable, force_password_reset, impersonate, stop_impersonation
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_admin_actions_user_id ON admin_actions (user_id);
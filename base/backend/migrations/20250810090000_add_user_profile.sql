@@ -0,0 +1,15 @@
+-- Profile data the users manage themselves
+ALTER TABLE users ADD COLUMN preferred_language_id INTEGER REFERENCES languages(id) ON DELETE SET NULL;
+ALTER TABLE users ADD COLUMN avatar TEXT; -- public path of the uploaded image
+
+-- Changing the email is confirmed through a link sent to the new address
+ALTER TYPE user_token_purpose ADD VALUE 'email_change';
+
+CREATE TABLE pending_email_changes (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    new_email TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Listings of deleted accounts can be kept anonymized (see ACCOUNT_DELETION_POLICY)
+ALTER TABLE listings ALTER COLUMN user_id DROP NOT NULL;
@@ -0,0 +1,20 @@
+-- GDPR data exports requested by users, built in the background
+CREATE TYPE data_export_status AS ENUM (
+    'pending',
+    'running',
+    'ready',
+    'failed'
+);
+
+CREATE TABLE data_exports (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status data_export_status NOT NULL DEFAULT 'pending',
+    file_path TEXT, -- inside EXPORTS_DIR, never served publicly
+    error TEXT,
+    expires_at TIMESTAMPTZ, -- the archive can be downloaded until then
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_data_exports_user_id ON data_exports (user_id);
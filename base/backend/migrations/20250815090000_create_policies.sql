@@ -0,0 +1,22 @@
+-- Declarative ABAC policies (see shared::policy::PolicyDocument)
+CREATE TABLE policies (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    document JSONB NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Every running backend listens on this channel and reloads its policy engine
+CREATE FUNCTION notify_policies_changed() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('policies_changed', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER policies_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON policies
+FOR EACH STATEMENT EXECUTE FUNCTION notify_policies_changed();
@@ -0,0 +1,2 @@
+-- Stored policies are evaluated by position (then name), which matters for first_applicable
+ALTER TABLE policies ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Typed attributes per resource type (see shared::attribute_schema::AttributeSchema)
+CREATE TABLE attribute_schemas (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    resource_type TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL
+        CHECK (kind IN ('string', 'integer', 'decimal', 'bool', 'date_time', 'list', 'map')),
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    default_value JSONB,
+    allowed_values JSONB NOT NULL DEFAULT '[]',
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (resource_type, name)
+);
@@ -0,0 +1,81 @@
+-- Roles stored in the database (see shared::role::Role).
+-- `users.role` stays the built-in role of every user, the roles of the same name
+-- carry its permissions and more roles can be assigned on top in `user_roles`.
+CREATE TABLE roles (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    -- `resource_type:action`, `*` matches any resource type or action
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- A role has the permissions of its parents
+CREATE TABLE role_parents (
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    parent_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, parent_id),
+    CHECK (role_id <> parent_id)
+);
+
+CREATE TABLE user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, role_id)
+);
+
+INSERT INTO roles (name, description, permissions) VALUES
+    ('User', 'Every registered user', '{}'),
+    ('Admin', 'Manages the users', '{user:manage,attribute_schema:view}'),
+    ('SuperAdmin', 'Can do everything', '{*}');
+
+INSERT INTO role_parents (role_id, parent_id)
+SELECT child.id, parent.id
+FROM roles child, roles parent
+WHERE (child.name, parent.name) IN (('Admin', 'User'), ('SuperAdmin', 'Admin'));
+
+-- The built-in role and the assigned roles of a user
+CREATE FUNCTION user_role_names(uid UUID) RETURNS TEXT[] AS $$
+    SELECT ARRAY(
+        SELECT CASE role
+            WHEN 'super_admin' THEN 'SuperAdmin'
+            WHEN 'admin' THEN 'Admin'
+            ELSE 'User'
+        END
+        FROM users
+        WHERE id = uid
+        UNION
+        SELECT r.name
+        FROM user_roles ur
+        JOIN roles r ON r.id = ur.role_id
+        WHERE ur.user_id = uid
+    )
+$$ LANGUAGE sql STABLE;
+
+-- The roles and every role they inherit from
+CREATE FUNCTION effective_roles(names TEXT[]) RETURNS TEXT[] AS $$
+    WITH RECURSIVE effective(id) AS (
+        SELECT id FROM roles WHERE name = ANY(names)
+        UNION
+        SELECT p.parent_id
+        FROM role_parents p
+        JOIN effective e ON e.id = p.role_id
+    )
+    SELECT ARRAY(
+        SELECT r.name
+        FROM roles r
+        JOIN effective e ON e.id = r.id
+        ORDER BY r.name
+    )
+$$ LANGUAGE sql STABLE;
+
+-- The permissions of the roles, inherited ones included
+CREATE FUNCTION role_permissions(names TEXT[]) RETURNS TEXT[] AS $$
+    SELECT ARRAY(
+        SELECT DISTINCT permission
+        FROM roles r, unnest(r.permissions) AS permission
+        WHERE r.name = ANY(effective_roles(names))
+        ORDER BY permission
+    )
+$$ LANGUAGE sql STABLE;
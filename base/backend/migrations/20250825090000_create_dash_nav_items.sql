@@ -0,0 +1,37 @@
+-- The dashboard menu (see shared::dashboard::DashNavItem)
+CREATE TABLE dash_nav_items (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    parent_id INTEGER REFERENCES dash_nav_items(id) ON DELETE CASCADE,
+    -- The label when there is none in the language of the user
+    name TEXT NOT NULL,
+    icon TEXT NOT NULL,
+    route TEXT NOT NULL UNIQUE,
+    -- `resource_type:action` one of the roles of the user must grant
+    required_permission TEXT,
+    -- `resource_type:action` the policies must allow
+    required_action TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (parent_id <> id)
+);
+
+-- Labels of the nav items per language
+CREATE TABLE dash_nav_items_names (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL,
+    language_id INTEGER NOT NULL REFERENCES languages(id) ON DELETE CASCADE,
+    nav_item_id INTEGER NOT NULL REFERENCES dash_nav_items(id) ON DELETE CASCADE,
+    UNIQUE(language_id, nav_item_id)
+);
+
+-- The menu that used to be hard-coded
+INSERT INTO dash_nav_items (name, icon, route, required_permission, required_action, position) VALUES
+    ('Categories', 'cat', '/dashboard/categories', 'category:write', NULL, 10),
+    ('Languages', 'languages', '/dashboard/languages', 'language:write', NULL, 20),
+    ('Fields', 'fields', '/dashboard/fields', 'field:write', NULL, 30),
+    ('Users', 'users', '/dashboard/users', 'user:manage', NULL, 40),
+    ('Policies', 'policies', '/dashboard/policies', 'policy:manage', NULL, 50),
+    ('Roles', 'roles', '/dashboard/roles', 'role:manage', NULL, 60),
+    ('Menu', 'menu', '/dashboard/nav-items', 'dash_nav_item:manage', NULL, 70),
+    ('Listings', 'list', '/dashboard/settings', NULL, 'listing:view', 80);
@@ -0,0 +1,24 @@
+-- Every mutation with who did it and what changed (see shared::audit::AuditEntry)
+CREATE TABLE audit_log (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Kept when the actor is deleted
+    actor_email TEXT,
+    action TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    before JSONB,
+    after JSONB,
+    -- The changed keys, `{"name": {"before": .., "after": ..}}`
+    diff JSONB NOT NULL DEFAULT '{}',
+    ip TEXT,
+    user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_audit_log_resource ON audit_log (resource_type, resource_id);
+CREATE INDEX idx_audit_log_actor_id ON audit_log (actor_id);
+CREATE INDEX idx_audit_log_created_at ON audit_log (created_at);
+
+INSERT INTO dash_nav_items (name, icon, route, required_permission, position) VALUES
+    ('Audit log', 'history', '/dashboard/audit-log', 'audit_log:view', 75);
@@ -0,0 +1,40 @@
+-- Errors and panics are grouped by fingerprint (see shared::error_log::ErrorGroup)
+CREATE TYPE error_status AS ENUM (
+    'open',
+    'resolved',
+    'ignored'
+);
+
+ALTER TABLE errors
+    ADD COLUMN fingerprint TEXT,
+    ADD COLUMN occurrences INT NOT NULL DEFAULT 1,
+    ADD COLUMN status error_status NOT NULL DEFAULT 'open',
+    ADD COLUMN last_seen_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN resolved_at TIMESTAMPTZ,
+    ADD COLUMN resolved_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+-- Rows from before the grouping stay apart
+UPDATE errors SET fingerprint = 'legacy-' || id, last_seen_at = created_at;
+
+ALTER TABLE errors
+    ALTER COLUMN fingerprint SET NOT NULL,
+    ADD CONSTRAINT errors_fingerprint_key UNIQUE (fingerprint);
+
+CREATE INDEX idx_errors_status_last_seen_at ON errors (status, last_seen_at);
+
+-- Every time an error happened, with the request it happened in
+CREATE TABLE error_occurrences (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    error_id INT NOT NULL REFERENCES errors(id) ON DELETE CASCADE,
+    request_id UUID,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    method TEXT,
+    route TEXT,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_error_occurrences_error_id ON error_occurrences (error_id, created_at);
+
+INSERT INTO dash_nav_items (name, icon, route, required_permission, position) VALUES
+    ('Errors', 'bug', '/dashboard/errors', 'error:manage', 77);
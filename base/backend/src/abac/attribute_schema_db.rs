@@ -0,0 +1,163 @@
+use crate::audit::{add_audit_entry, snapshot, AuditActor, AuditRecord};
+use crate::error::Result;
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use serde_json::Value;
+use shared::attribute_schema::{AttributeKind, AttributeSchema, AttributeSchemaPayload};
+use sqlx::{query, query_as, PgPool, Postgres, Transaction};
+
+struct AttributeSchemaRow {
+    id: i32,
+    resource_type: String,
+    name: String,
+    kind: String,
+    required: bool,
+    default_value: Option<Value>,
+    allowed_values: Value,
+    description: Option<String>,
+}
+
+/// The kind is stored as text, like it is written in JSON
+fn into_schemas(rows: Vec<AttributeSchemaRow>) -> Result<Vec<AttributeSchema>> {
+    rows.into_iter()
+        .map(|row| {
+            let kind: AttributeKind = serde_json::from_value(Value::String(row.kind))
+                .map_err(|err| SamError::Err(err.to_string()))?;
+            let allowed_values = serde_json::from_value(row.allowed_values).unwrap_or_default();
+            Ok(AttributeSchema {
+                id: row.id,
+                resource_type: row.resource_type,
+                name: row.name,
+                kind,
+                required: row.required,
+                default_value: row.default_value,
+                allowed_values,
+                description: row.description,
+            })
+        })
+        .collect()
+}
+
+#[catch_error]
+pub async fn list_attribute_schemas(
+    pool: &PgPool,
+    resource_type: Option<&str>,
+) -> Result<Vec<AttributeSchema>> {
+    let rows: Vec<AttributeSchemaRow> = query_as!(
+        AttributeSchemaRow,
+        r#"
+        SELECT id, resource_type, name, kind, required, default_value, allowed_values, description
+        FROM attribute_schemas
+        WHERE $1::text IS NULL OR resource_type = $1
+        ORDER BY resource_type, name
+        "#,
+        resource_type
+    )
+    .fetch_all(pool)
+    .await?;
+    into_schemas(rows)
+}
+
+#[catch_error]
+pub async fn add_attribute_schema(
+    pool: &PgPool,
+    actor: &AuditActor,
+    payload: &AttributeSchemaPayload,
+) -> Result<i32> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let row = query!(
+        r#"
+        INSERT INTO attribute_schemas
+            (resource_type, name, kind, required, default_value, allowed_values, description)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+        payload.resource_type,
+        payload.name,
+        payload.kind.to_string(),
+        payload.required,
+        payload.default_value,
+        Value::Array(payload.allowed_values.clone()),
+        payload.description
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let after = snapshot(&mut tx, "attribute_schemas", &row.id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("create", "attribute_schema", row.id).after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(row.id)
+}
+
+#[catch_error]
+pub async fn update_attribute_schema(
+    pool: &PgPool,
+    actor: &AuditActor,
+    id: i32,
+    payload: &AttributeSchemaPayload,
+) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "attribute_schemas", &id.to_string()).await?;
+    let result = query!(
+        r#"
+        UPDATE attribute_schemas
+        SET resource_type = $1, name = $2, kind = $3, required = $4, default_value = $5,
+            allowed_values = $6, description = $7
+        WHERE id = $8
+        "#,
+        payload.resource_type,
+        payload.name,
+        payload.kind.to_string(),
+        payload.required,
+        payload.default_value,
+        Value::Array(payload.allowed_values.clone()),
+        payload.description,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Attribute schema".to_string()));
+    }
+    let after = snapshot(&mut tx, "attribute_schemas", &id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("update", "attribute_schema", id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn delete_attribute_schema(pool: &PgPool, actor: &AuditActor, id: i32) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "attribute_schemas", &id.to_string()).await?;
+    let result = query!(
+        r#"
+        DELETE FROM attribute_schemas
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Attribute schema".to_string()));
+    }
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("delete", "attribute_schema", id).before(before),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
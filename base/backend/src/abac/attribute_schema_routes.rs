@@ -0,0 +1,109 @@
+// Routes for the attribute schemas, read by the attribute and policy editors
+// GET	/attribute-schemas?resource_type=user	List the schemas, of one resource type when given
+// POST	/attribute-schemas	Create a schema (super admins only)
+// PUT	/attribute-schemas/{id}	Update a schema (super admins only)
+// DELETE	/attribute-schemas/{id}	Delete a schema (super admins only)
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, Query, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sam_error::SamError;
+use serde_json::json;
+use shared::attribute_schema::{AttributeSchemaFilter, AttributeSchemaPayload};
+
+use super::{
+    attribute_schema_db::{
+        add_attribute_schema, delete_attribute_schema, list_attribute_schemas,
+        update_attribute_schema,
+    },
+    authorize, Authorize,
+};
+use crate::{
+    audit::AuditActor,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn attribute_schema_routes(state: AppState) -> Router<AppState> {
+    let write_routes = Router::new()
+        .route("/attribute-schemas", post(add_attribute_schema_handler))
+        .route(
+            "/attribute-schemas/{id}",
+            put(update_attribute_schema_handler).delete(delete_attribute_schema_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "attribute_schema", "manage"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
+    Router::new()
+        .route("/attribute-schemas", get(list_attribute_schemas_handler))
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "attribute_schema", "view"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .merge(write_routes)
+        .with_state(state)
+}
+
+fn validate(payload: &AttributeSchemaPayload) -> Result<()> {
+    payload.validate().map_err(SamError::InvalidAttributes)
+}
+
+async fn list_attribute_schemas_handler(
+    State(state): State<AppState>,
+    Query(query): Query<AttributeSchemaFilter>,
+) -> Result<Response> {
+    let schemas = list_attribute_schemas(&state.pool, query.resource_type.as_deref()).await?;
+    let res = UserResponse::with_json(schemas).into_response();
+    Ok(res)
+}
+
+async fn add_attribute_schema_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    payload: Result<Json<AttributeSchemaPayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    validate(&payload)?;
+    let id = add_attribute_schema(&state.pool, &actor, &payload).await?;
+    let res = UserResponse::with_json_and_code(json!({ "id": id }), 201).into_response();
+    Ok(res)
+}
+
+async fn update_attribute_schema_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+    payload: Result<Json<AttributeSchemaPayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    validate(&payload)?;
+    update_attribute_schema(&state.pool, &actor, id, &payload).await?;
+    let res = UserResponse::with_success("Attribute schema updated").into_response();
+    Ok(res)
+}
+
+async fn delete_attribute_schema_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    delete_attribute_schema(&state.pool, &actor, id).await?;
+    let res = UserResponse::with_success("Attribute schema deleted").into_response();
+    Ok(res)
+}
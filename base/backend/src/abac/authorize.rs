@@ -0,0 +1,220 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{
+        ConnectInfo, FromRequestParts, OptionalFromRequestParts, RawPathParams, Request, State,
+    },
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+use sam_error::SamError;
+use serde_json::json;
+use shared::{
+    error_code,
+    policy::Explanation,
+    user::{UserInfo, UserResponse},
+    Action, AttributeValue, Context, Resource,
+};
+use time::OffsetDateTime;
+
+use super::{denial_reason, PolicyEngine, ResourceColumns, SqlFilter};
+use crate::{error::Result, AppState};
+
+/// # Authorize requests with the policy engine
+/// Must run after `auth_middleware` so the user is in the request extensions.
+///
+/// ```ignore
+/// async fn handler(authorizer: Authorizer, Path(id): Path<Uuid>) -> Result<Response> {
+///     let listing = fetch_listing(id).await?;
+///     let resource = Resource::new("listing", id.to_string())
+///         .with_attribute("owner_id", AttributeValue::String(listing.user_id.to_string()));
+///     authorizer.authorize(&resource, "edit")?;
+///     ...
+/// }
+/// ```
+pub struct Authorizer {
+    user: Arc<UserInfo>,
+    context: Context,
+    engine: Arc<PolicyEngine>,
+}
+
+impl FromRequestParts<AppState> for Authorizer {
+    type Rejection = SamError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self> {
+        let user = parts
+            .extensions
+            .get::<Arc<UserInfo>>()
+            .cloned()
+            .ok_or(SamError::NotAuthorized)?;
+        Ok(Self {
+            user,
+            context: request_context(parts, state.config.trust_proxy_headers),
+            engine: state.policy_engine.clone(),
+        })
+    }
+}
+
+/// `None` for visitors, the route needs `optional_auth_middleware` to know the user
+impl OptionalFromRequestParts<AppState> for Authorizer {
+    type Rejection = SamError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Option<Self>> {
+        if parts.extensions.get::<Arc<UserInfo>>().is_none() {
+            return Ok(None);
+        }
+        let authorizer =
+            <Self as FromRequestParts<AppState>>::from_request_parts(parts, state).await?;
+        Ok(Some(authorizer))
+    }
+}
+
+impl Authorizer {
+    pub fn user(&self) -> &UserInfo {
+        &self.user
+    }
+
+    /// WHERE fragment keeping the rows the user may perform the action on,
+    /// its placeholders start at `first_param`
+    pub fn filter(&self, columns: &ResourceColumns, action: &str, first_param: usize) -> SqlFilter {
+        self.engine.filter(
+            &self.user,
+            &Action::new(action),
+            &self.context,
+            columns,
+            first_param,
+        )
+    }
+
+    pub fn explain(&self, resource: &Resource, action: &str) -> Explanation {
+        self.engine
+            .explain(&self.user, resource, &Action::new(action), &self.context)
+    }
+
+    /// Returns `SamError::PolicyDenied` with the reason when the engine denies the action.
+    /// Development builds answer with the whole explanation to help writing policies.
+    pub fn authorize(&self, resource: &Resource, action: &str) -> Result<()> {
+        let action = Action::new(action);
+        let explanation = self
+            .engine
+            .explain(&self.user, resource, &action, &self.context);
+        if explanation.allowed {
+            return Ok(());
+        }
+
+        let reason = denial_reason(&explanation, resource, &action);
+        let body = if cfg!(debug_assertions) {
+            let response = UserResponse::with_error_json_and_code(
+                json!({ "message": reason, "explanation": explanation }),
+                403,
+            )
+            .code(error_code::POLICY_DENIED);
+            serde_json::to_string(&response).ok()
+        } else {
+            None
+        };
+        Err(SamError::PolicyDenied { reason, body })
+    }
+
+    pub fn is_allowed(&self, resource: &Resource, action: &str) -> bool {
+        self.explain(resource, action).allowed
+    }
+}
+
+/// # Route layer checking an action on a resource type
+/// The resource id is taken from the `{id}` path parameter when there is one.
+///
+/// ```ignore
+/// .route_layer(middleware::from_fn_with_state(
+///     Authorize::new(&state, "category", "write"),
+///     authorize,
+/// ))
+/// .route_layer(middleware::from_fn_with_state(state.clone(), auth_middleware))
+/// ```
+#[derive(Clone)]
+pub struct Authorize {
+    state: AppState,
+    resource_type: &'static str,
+    action: &'static str,
+}
+
+impl Authorize {
+    pub fn new(state: &AppState, resource_type: &'static str, action: &'static str) -> Self {
+        Self {
+            state: state.clone(),
+            resource_type,
+            action,
+        }
+    }
+}
+
+pub async fn authorize(
+    State(rule): State<Authorize>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let (mut parts, body) = req.into_parts();
+    let authorizer =
+        <Authorizer as FromRequestParts<AppState>>::from_request_parts(&mut parts, &rule.state)
+            .await?;
+
+    let id = RawPathParams::from_request_parts(&mut parts, &())
+        .await
+        .ok()
+        .and_then(|params| {
+            params
+                .iter()
+                .find(|(key, _)| *key == "id")
+                .map(|(_, value)| value.to_string())
+        })
+        .unwrap_or_default();
+    authorizer.authorize(&Resource::new(rule.resource_type, id), rule.action)?;
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+fn request_context(parts: &Parts, trust_proxy_headers: bool) -> Context {
+    Context::new(client_ip(parts, trust_proxy_headers))
+        .with_attribute(
+            "method",
+            AttributeValue::String(parts.method.as_str().to_string()),
+        )
+        .with_attribute("path", AttributeValue::String(parts.uri.path().to_string()))
+        .with_attribute("time", AttributeValue::DateTime(OffsetDateTime::now_utc()))
+}
+
+/// # The real address of the client
+/// Needs the app to be served with `into_make_service_with_connect_info::<SocketAddr>()`.
+/// `X-Forwarded-For` and `X-Real-IP` can be set by anyone, they are only used with
+/// `trust_proxy_headers` (the `TRUST_PROXY_HEADERS` setting, when behind a reverse proxy).
+pub fn client_ip(parts: &Parts, trust_proxy_headers: bool) -> String {
+    if trust_proxy_headers {
+        // Our proxy appends the address it got the request from, the entries before it
+        // come from the client and can be forged, so only the last one is trusted
+        let forwarded = parts
+            .headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .or_else(|| {
+                parts
+                    .headers
+                    .get("x-real-ip")
+                    .and_then(|value| value.to_str().ok())
+            })
+            .and_then(|value| value.trim().parse::<IpAddr>().ok());
+        if let Some(ip) = forwarded {
+            return ip.to_string();
+        }
+    }
+
+    parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
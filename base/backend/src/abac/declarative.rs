@@ -0,0 +1,214 @@
+use std::cmp::Ordering;
+
+use rust_decimal::Decimal;
+use shared::{
+    policy::{parse_clock, Condition, Effect, Operand, Policy, PolicyDocument, PolicyPayload},
+    user::UserInfo,
+    Action, AttributeValue, Context, Resource,
+};
+use time::{OffsetDateTime, UtcOffset};
+
+use super::{Evaluation, PartialRequest, PartialResult, PolicyEvaluator, SqlBuilder};
+
+/// # A policy loaded from the `policies` table
+/// See `shared::policy::PolicyDocument` for the format.
+pub struct DeclarativePolicy {
+    name: String,
+    document: PolicyDocument,
+}
+
+impl From<Policy> for DeclarativePolicy {
+    fn from(policy: Policy) -> Self {
+        Self {
+            name: policy.name,
+            document: policy.document,
+        }
+    }
+}
+
+/// A policy that isn't saved yet, see the simulator
+impl From<PolicyPayload> for DeclarativePolicy {
+    fn from(payload: PolicyPayload) -> Self {
+        Self {
+            name: payload.name,
+            document: payload.document,
+        }
+    }
+}
+
+impl DeclarativePolicy {
+    fn targets(&self, user: &UserInfo, resource_type: &str, action: &Action) -> bool {
+        let document = &self.document;
+        (document.resource_type == "*" || document.resource_type == resource_type)
+            && (document.actions.is_empty()
+                || document.actions.iter().any(|name| name == &action.name))
+            && (document.roles.is_empty() || document.roles.iter().any(|role| user.has_role(role)))
+    }
+}
+
+impl PolicyEvaluator for DeclarativePolicy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn evaluate(
+        &self,
+        user: &UserInfo,
+        resource: &Resource,
+        action: &Action,
+        context: &Context,
+    ) -> Evaluation {
+        if !self.targets(user, &resource.resource_type, action) {
+            return Evaluation::not_applicable("doesn't target the request");
+        }
+        let request = Request {
+            user,
+            resource,
+            action,
+            context,
+        };
+        if !request.check(&self.document.condition) {
+            return Evaluation::not_applicable("the condition doesn't hold");
+        }
+        match self.document.effect {
+            Effect::Permit => Evaluation::permit("the condition holds"),
+            Effect::Deny => Evaluation::deny("the condition holds"),
+        }
+    }
+
+    fn partial_evaluate(&self, request: &PartialRequest, sql: &mut SqlBuilder) -> PartialResult {
+        if !self.targets(request.user, request.columns.resource_type, request.action) {
+            return PartialResult::not_applicable();
+        }
+        let when = request.condition(&self.document.condition, sql);
+        PartialResult::effect(self.document.effect, when)
+    }
+}
+
+struct Request<'a> {
+    user: &'a UserInfo,
+    resource: &'a Resource,
+    action: &'a Action,
+    context: &'a Context,
+}
+
+impl Request<'_> {
+    /// Missing attributes make the condition fail, a policy never applies by accident
+    fn check(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::All { conditions } => conditions.iter().all(|c| self.check(c)),
+            Condition::Any { conditions } => conditions.iter().any(|c| self.check(c)),
+            Condition::Not { condition } => !self.check(condition),
+            Condition::Eq { left, right } => self.compare(left, right, |o| o == Ordering::Equal),
+            Condition::Ne { left, right } => self.compare(left, right, |o| o != Ordering::Equal),
+            Condition::Lt { left, right } => self.compare(left, right, |o| o == Ordering::Less),
+            Condition::Le { left, right } => self.compare(left, right, |o| o != Ordering::Greater),
+            Condition::Gt { left, right } => self.compare(left, right, |o| o == Ordering::Greater),
+            Condition::Ge { left, right } => self.compare(left, right, |o| o != Ordering::Less),
+            Condition::In { value, set } => match (self.resolve(value), self.resolve(set)) {
+                (Some(value), Some(AttributeValue::List(set))) => set
+                    .iter()
+                    .any(|item| compare_values(&value, item) == Some(Ordering::Equal)),
+                _ => false,
+            },
+            Condition::TimeWindow { from, to, days } => {
+                in_time_window(self.context, from, to, days)
+            }
+            Condition::Owner { attribute } => matches!(
+                self.resource.attributes.get(attribute),
+                Some(AttributeValue::String(owner_id)) if owner_id == &self.user.id
+            ),
+        }
+    }
+
+    fn compare(&self, left: &Operand, right: &Operand, accept: impl Fn(Ordering) -> bool) -> bool {
+        match (self.resolve(left), self.resolve(right)) {
+            (Some(left), Some(right)) => compare_values(&left, &right).is_some_and(accept),
+            _ => false,
+        }
+    }
+
+    fn resolve(&self, operand: &Operand) -> Option<AttributeValue> {
+        match operand {
+            Operand::Resource(name) => match name.as_str() {
+                "id" => Some(AttributeValue::String(self.resource.id.clone())),
+                "type" => Some(AttributeValue::String(self.resource.resource_type.clone())),
+                _ => self.resource.attributes.get(name).cloned(),
+            },
+            _ => resolve_known(operand, self.user, self.action, self.context),
+        }
+    }
+}
+
+/// Resolves every operand but the resource ones, which are the only unknowns of a list query
+pub(super) fn resolve_known(
+    operand: &Operand,
+    user: &UserInfo,
+    action: &Action,
+    context: &Context,
+) -> Option<AttributeValue> {
+    let string = |value: &str| Some(AttributeValue::String(value.to_string()));
+    match operand {
+        Operand::User(name) => match name.as_str() {
+            "id" => string(&user.id),
+            "email" => string(&user.email),
+            "role" => string(&format!("{:?}", user.role)),
+            "roles" => Some(strings(&user.roles)),
+            "permissions" => Some(strings(&user.permissions)),
+            _ => user.attributes.0.get(name).cloned(),
+        },
+        Operand::Action(name) => match name.as_str() {
+            "name" => string(&action.name),
+            _ => action.attributes.get(name).cloned(),
+        },
+        Operand::Context(name) => match name.as_str() {
+            "ip" => string(&context.ip_address),
+            _ => context.attributes.get(name).cloned(),
+        },
+        Operand::Resource(_) => None,
+        Operand::Value(value) => Some(AttributeValue::from(value.clone())),
+    }
+}
+
+fn strings(values: &[String]) -> AttributeValue {
+    AttributeValue::List(values.iter().cloned().map(AttributeValue::String).collect())
+}
+
+pub(super) fn in_time_window(context: &Context, from: &str, to: &str, days: &[u8]) -> bool {
+    let (Some(from), Some(to)) = (parse_clock(from), parse_clock(to)) else {
+        return false;
+    };
+    let now = match context.attributes.get("time") {
+        Some(AttributeValue::DateTime(time)) => *time,
+        _ => OffsetDateTime::now_utc(),
+    }
+    .to_offset(UtcOffset::UTC);
+
+    if !days.is_empty() && !days.contains(&now.weekday().number_from_monday()) {
+        return false;
+    }
+    let time = now.time();
+    if from <= to {
+        from <= time && time < to
+    } else {
+        // The window goes over midnight, e.g. 22:00 to 06:00
+        time >= from || time < to
+    }
+}
+
+/// Numbers compare across integers and decimals, other values only with their own kind.
+/// Null compares with nothing, like a missing value.
+pub(super) fn compare_values(left: &AttributeValue, right: &AttributeValue) -> Option<Ordering> {
+    match (left, right) {
+        (AttributeValue::Integer(l), AttributeValue::Integer(r)) => Some(l.cmp(r)),
+        (AttributeValue::Integer(l), AttributeValue::Decimal(r)) => Some(Decimal::from(*l).cmp(r)),
+        (AttributeValue::Decimal(l), AttributeValue::Integer(r)) => Some(l.cmp(&Decimal::from(*r))),
+        (AttributeValue::Decimal(l), AttributeValue::Decimal(r)) => Some(l.cmp(r)),
+        (AttributeValue::String(l), AttributeValue::String(r)) => Some(l.cmp(r)),
+        (AttributeValue::Bool(l), AttributeValue::Bool(r)) => Some(l.cmp(r)),
+        (AttributeValue::DateTime(l), AttributeValue::DateTime(r)) => Some(l.cmp(r)),
+        (AttributeValue::List(l), AttributeValue::List(r)) if l == r => Some(Ordering::Equal),
+        (AttributeValue::Map(l), AttributeValue::Map(r)) if l == r => Some(Ordering::Equal),
+        _ => None,
+    }
+}
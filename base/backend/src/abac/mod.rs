@@ -1 +1,20 @@
+mod attribute_schema_db;
+mod attribute_schema_routes;
+mod authorize;
+mod declarative;
+mod partial;
 mod policy;
+mod policy_db;
+mod policy_routes;
+mod reload;
+mod simulator;
+
+pub use attribute_schema_db::list_attribute_schemas;
+pub use attribute_schema_routes::attribute_schema_routes;
+pub use authorize::{authorize, client_ip, Authorize, Authorizer};
+pub use declarative::DeclarativePolicy;
+pub use partial::*;
+pub use policy::*;
+pub use policy_routes::policy_routes;
+pub use reload::{reload_policies, watch_policies};
+pub use simulator::{parse_fixture, run_fixture, simulate};
@@ -0,0 +1,377 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use shared::{
+    policy::{Condition, Decision, Effect, Operand},
+    user::UserInfo,
+    Action, AttributeValue, Context,
+};
+use sqlx::{postgres::PgArguments, query::Query, Postgres};
+use time::format_description::well_known::Rfc3339;
+
+use super::declarative::{compare_values, in_time_window, resolve_known};
+
+/// # Columns of a resource type
+/// Maps the resource attributes used by policies to SQL expressions of a list query.
+/// Attributes without a column are missing on every row, as if the resource didn't carry them.
+///
+/// ```ignore
+/// ResourceColumns::new("listing")
+///     .column("id", "id")
+///     .column("owner_id", "user_id")
+/// ```
+pub struct ResourceColumns {
+    pub resource_type: &'static str,
+    columns: HashMap<&'static str, &'static str>,
+}
+
+impl ResourceColumns {
+    pub fn new(resource_type: &'static str) -> Self {
+        Self {
+            resource_type,
+            columns: HashMap::new(),
+        }
+    }
+
+    pub fn column(mut self, attribute: &'static str, sql: &'static str) -> Self {
+        self.columns.insert(attribute, sql);
+        self
+    }
+
+    pub fn get(&self, attribute: &str) -> Option<&'static str> {
+        self.columns.get(attribute).copied()
+    }
+}
+
+/// A predicate over the rows, constants are simplified as the filter is built
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    True,
+    False,
+    Sql(String),
+}
+
+impl Filter {
+    pub fn and(self, other: Filter) -> Filter {
+        match (self, other) {
+            (Filter::False, _) | (_, Filter::False) => Filter::False,
+            (Filter::True, filter) | (filter, Filter::True) => filter,
+            (Filter::Sql(left), Filter::Sql(right)) => {
+                Filter::Sql(format!("({} AND {})", left, right))
+            }
+        }
+    }
+
+    pub fn or(self, other: Filter) -> Filter {
+        match (self, other) {
+            (Filter::True, _) | (_, Filter::True) => Filter::True,
+            (Filter::False, filter) | (filter, Filter::False) => filter,
+            (Filter::Sql(left), Filter::Sql(right)) => {
+                Filter::Sql(format!("({} OR {})", left, right))
+            }
+        }
+    }
+
+    pub fn not(self) -> Filter {
+        match self {
+            Filter::True => Filter::False,
+            Filter::False => Filter::True,
+            Filter::Sql(sql) => Filter::Sql(format!("(NOT {})", sql)),
+        }
+    }
+
+    fn from_bool(value: bool) -> Filter {
+        if value {
+            Filter::True
+        } else {
+            Filter::False
+        }
+    }
+
+    /// Fragments never evaluate to NULL, a missing value fails the comparison like at runtime
+    fn predicate(sql: String) -> Filter {
+        Filter::Sql(format!("COALESCE({}, FALSE)", sql))
+    }
+}
+
+/// Parameters are bound as text and cast to their type in the fragment
+#[derive(Debug, Clone)]
+pub enum SqlParam {
+    Text(String),
+    TextArray(Vec<String>),
+}
+
+/// # Build a WHERE fragment
+/// Numbers the placeholders from `first_param` so the fragment can follow the query's own parameters.
+pub struct SqlBuilder {
+    first_param: usize,
+    params: Vec<SqlParam>,
+}
+
+impl SqlBuilder {
+    pub fn new(first_param: usize) -> Self {
+        Self {
+            first_param,
+            params: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, param: SqlParam) -> String {
+        self.params.push(param);
+        format!("${}", self.first_param + self.params.len() - 1)
+    }
+
+    /// `column <op> value`, the column is compared as text with strings
+    pub fn compare(&mut self, column: &str, op: &str, value: &AttributeValue) -> Filter {
+        match sql_type(value) {
+            Some("text") => {
+                let placeholder = self.push(SqlParam::Text(sql_text(value)));
+                Filter::predicate(format!("({})::text {} {}", column, op, placeholder))
+            }
+            Some(ty) => {
+                let placeholder = self.push(SqlParam::Text(sql_text(value)));
+                Filter::predicate(format!("{} {} {}::{}", column, op, placeholder, ty))
+            }
+            None => Filter::False,
+        }
+    }
+
+    /// `column` is one of the values, all the values must have the same SQL type
+    pub fn one_of(&mut self, column: &str, values: &[AttributeValue]) -> Filter {
+        let Some(ty) = values.first().and_then(sql_type) else {
+            return Filter::False;
+        };
+        if values.iter().any(|value| sql_type(value) != Some(ty)) {
+            return Filter::False;
+        }
+        let placeholder = self.push(SqlParam::TextArray(values.iter().map(sql_text).collect()));
+        if ty == "text" {
+            Filter::predicate(format!("({})::text = ANY({})", column, placeholder))
+        } else {
+            Filter::predicate(format!("{} = ANY({}::{}[])", column, placeholder, ty))
+        }
+    }
+
+    /// The array column contains the value
+    pub fn contains(&mut self, column: &str, value: &AttributeValue) -> Filter {
+        match sql_type(value) {
+            Some(ty) => {
+                let placeholder = self.push(SqlParam::Text(sql_text(value)));
+                Filter::predicate(format!("{}::{} = ANY({})", placeholder, ty, column))
+            }
+            None => Filter::False,
+        }
+    }
+
+    pub fn finish(self, filter: Filter) -> SqlFilter {
+        let clause = match filter {
+            Filter::True => "TRUE".to_string(),
+            Filter::False => "FALSE".to_string(),
+            Filter::Sql(sql) => sql,
+        };
+        SqlFilter {
+            clause,
+            params: self.params,
+        }
+    }
+}
+
+fn sql_type(value: &AttributeValue) -> Option<&'static str> {
+    match value {
+        AttributeValue::String(_) => Some("text"),
+        AttributeValue::Integer(_) | AttributeValue::Decimal(_) => Some("numeric"),
+        AttributeValue::Bool(_) => Some("boolean"),
+        AttributeValue::DateTime(_) => Some("timestamptz"),
+        AttributeValue::Null | AttributeValue::List(_) | AttributeValue::Map(_) => None,
+    }
+}
+
+fn sql_text(value: &AttributeValue) -> String {
+    match value {
+        AttributeValue::String(value) => value.clone(),
+        AttributeValue::Integer(value) => value.to_string(),
+        AttributeValue::Decimal(value) => value.to_string(),
+        AttributeValue::Bool(value) => value.to_string(),
+        AttributeValue::DateTime(value) => value.format(&Rfc3339).unwrap_or_default(),
+        AttributeValue::Null | AttributeValue::List(_) | AttributeValue::Map(_) => String::new(),
+    }
+}
+
+/// # A WHERE fragment with its parameters
+///
+/// ```ignore
+/// let sql = format!("SELECT id FROM listings WHERE {}", filter.clause);
+/// let rows = filter.bind(sqlx::query(&sql)).fetch_all(pool).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SqlFilter {
+    pub clause: String,
+    pub params: Vec<SqlParam>,
+}
+
+impl SqlFilter {
+    pub fn bind<'q>(
+        self,
+        mut query: Query<'q, Postgres, PgArguments>,
+    ) -> Query<'q, Postgres, PgArguments> {
+        for param in self.params {
+            query = match param {
+                SqlParam::Text(value) => query.bind(value),
+                SqlParam::TextArray(values) => query.bind(values),
+            };
+        }
+        query
+    }
+}
+
+/// What is known of a list request: everything but the rows
+pub struct PartialRequest<'a> {
+    pub user: &'a UserInfo,
+    pub action: &'a Action,
+    pub context: &'a Context,
+    pub columns: &'a ResourceColumns,
+}
+
+/// # The decision of a policy on every row
+/// Permit where `permit` holds, deny where `deny` holds and not applicable elsewhere.
+/// The two never hold on the same row.
+pub struct PartialResult {
+    pub permit: Filter,
+    pub deny: Filter,
+}
+
+impl PartialResult {
+    pub fn not_applicable() -> Self {
+        Self {
+            permit: Filter::False,
+            deny: Filter::False,
+        }
+    }
+
+    pub fn effect(effect: Effect, when: Filter) -> Self {
+        match effect {
+            Effect::Permit => Self {
+                permit: when,
+                deny: Filter::False,
+            },
+            Effect::Deny => Self {
+                permit: Filter::False,
+                deny: when,
+            },
+        }
+    }
+}
+
+/// Policies that don't look at the resource attributes decide the same for every row
+impl From<Decision> for PartialResult {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Permit => Self::effect(Effect::Permit, Filter::True),
+            Decision::Deny => Self::effect(Effect::Deny, Filter::True),
+            Decision::NotApplicable => Self::not_applicable(),
+        }
+    }
+}
+
+/// A resolved operand, resource attributes are columns
+enum Term {
+    Value(AttributeValue),
+    Column(&'static str),
+}
+
+impl PartialRequest<'_> {
+    fn term(&self, operand: &Operand) -> Option<Term> {
+        match operand {
+            Operand::Resource(name) if name == "type" => Some(Term::Value(AttributeValue::String(
+                self.columns.resource_type.to_string(),
+            ))),
+            Operand::Resource(name) => self.columns.get(name).map(Term::Column),
+            _ => resolve_known(operand, self.user, self.action, self.context).map(Term::Value),
+        }
+    }
+
+    /// # Translate a condition into a filter
+    /// Mirrors the runtime evaluation of `DeclarativePolicy` row by row.
+    pub fn condition(&self, condition: &Condition, sql: &mut SqlBuilder) -> Filter {
+        match condition {
+            Condition::All { conditions } => conditions
+                .iter()
+                .fold(Filter::True, |filter, c| filter.and(self.condition(c, sql))),
+            Condition::Any { conditions } => conditions
+                .iter()
+                .fold(Filter::False, |filter, c| filter.or(self.condition(c, sql))),
+            Condition::Not { condition } => self.condition(condition, sql).not(),
+            Condition::Eq { left, right } => self.compare(left, "=", right, sql),
+            Condition::Ne { left, right } => self.compare(left, "<>", right, sql),
+            Condition::Lt { left, right } => self.compare(left, "<", right, sql),
+            Condition::Le { left, right } => self.compare(left, "<=", right, sql),
+            Condition::Gt { left, right } => self.compare(left, ">", right, sql),
+            Condition::Ge { left, right } => self.compare(left, ">=", right, sql),
+            Condition::In { value, set } => match (self.term(value), self.term(set)) {
+                (Some(Term::Value(value)), Some(Term::Value(AttributeValue::List(set)))) => {
+                    Filter::from_bool(
+                        set.iter()
+                            .any(|item| compare_values(&value, item) == Some(Ordering::Equal)),
+                    )
+                }
+                (Some(Term::Column(column)), Some(Term::Value(AttributeValue::List(set)))) => {
+                    sql.one_of(column, &set)
+                }
+                (Some(Term::Value(value)), Some(Term::Column(column))) => {
+                    sql.contains(column, &value)
+                }
+                _ => Filter::False,
+            },
+            Condition::TimeWindow { from, to, days } => {
+                Filter::from_bool(in_time_window(self.context, from, to, days))
+            }
+            Condition::Owner { attribute } => match self.columns.get(attribute) {
+                Some(column) => {
+                    sql.compare(column, "=", &AttributeValue::String(self.user.id.clone()))
+                }
+                None => Filter::False,
+            },
+        }
+    }
+
+    fn compare(&self, left: &Operand, op: &str, right: &Operand, sql: &mut SqlBuilder) -> Filter {
+        match (self.term(left), self.term(right)) {
+            (Some(Term::Value(left)), Some(Term::Value(right))) => {
+                let ordering = compare_values(&left, &right);
+                Filter::from_bool(ordering.is_some_and(|o| matches_op(op, o)))
+            }
+            (Some(Term::Column(column)), Some(Term::Value(value))) => {
+                sql.compare(column, op, &value)
+            }
+            (Some(Term::Value(value)), Some(Term::Column(column))) => {
+                sql.compare(column, flip(op), &value)
+            }
+            (Some(Term::Column(left)), Some(Term::Column(right))) => {
+                Filter::predicate(format!("{} {} {}", left, op, right))
+            }
+            _ => Filter::False,
+        }
+    }
+}
+
+fn matches_op(op: &str, ordering: Ordering) -> bool {
+    match op {
+        "=" => ordering == Ordering::Equal,
+        "<>" => ordering != Ordering::Equal,
+        "<" => ordering == Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        ">=" => ordering != Ordering::Less,
+        _ => false,
+    }
+}
+
+/// `value op column` written as `column op value`
+fn flip(op: &str) -> &str {
+    match op {
+        "<" => ">",
+        "<=" => ">=",
+        ">" => "<",
+        ">=" => "<=",
+        op => op,
+    }
+}
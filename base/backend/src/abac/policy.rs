@@ -1,65 +1,579 @@
-use shared::user::User;
+use std::{collections::HashSet, sync::RwLock};
 
+use shared::user::UserInfo;
+
+use shared::policy::{CombiningAlgorithm, Decision, Effect, Explanation, PolicyResult};
 use shared::{Action, AttributeValue, Context, Resource};
 
+use super::{
+    DeclarativePolicy, Filter, PartialRequest, PartialResult, ResourceColumns, SqlBuilder,
+    SqlFilter,
+};
+
 pub trait PolicyEvaluator {
+    /// Shown in the explanation of a decision
+    fn name(&self) -> &str;
+
+    /// Policies that don't target the request return `Decision::NotApplicable`
     fn evaluate(
         &self,
-        user: &User,
+        user: &UserInfo,
         resource: &Resource,
         action: &Action,
         context: &Context,
-    ) -> bool;
+    ) -> Evaluation;
+
+    /// Decides for every row of a list query at once, see `PolicyEngine::filter`
+    fn partial_evaluate(&self, request: &PartialRequest, sql: &mut SqlBuilder) -> PartialResult;
+}
+
+/// The decision of a single policy and why it was taken
+pub struct Evaluation {
+    pub decision: Decision,
+    pub reason: String,
+}
+
+impl Evaluation {
+    pub fn permit(reason: impl Into<String>) -> Self {
+        Self {
+            decision: Decision::Permit,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self {
+            decision: Decision::Deny,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn not_applicable(reason: impl Into<String>) -> Self {
+        Self {
+            decision: Decision::NotApplicable,
+            reason: reason.into(),
+        }
+    }
 }
 
-// Example policy: Users can edit their own posts
+// Users can edit and delete what they own,
+// users with the permission (e.g. `listing:edit`) can edit and delete anything.
+// Applies to resources with an `owner_id` attribute.
 pub struct OwnershipPolicy;
 
 impl PolicyEvaluator for OwnershipPolicy {
+    fn name(&self) -> &str {
+        "ownership"
+    }
+
+    fn evaluate(
+        &self,
+        user: &UserInfo,
+        resource: &Resource,
+        action: &Action,
+        _context: &Context,
+    ) -> Evaluation {
+        if !matches!(action.name.as_str(), "edit" | "delete") {
+            return Evaluation::not_applicable("only edit and delete are checked");
+        }
+        let Some(owner_id) = resource.attributes.get("owner_id") else {
+            return Evaluation::not_applicable("the resource has no owner_id");
+        };
+        if user.has_permission(&resource.resource_type, &action.name) {
+            return Evaluation::permit(format!(
+                "the user may {} any {}",
+                action.name, resource.resource_type
+            ));
+        }
+        match owner_id {
+            AttributeValue::String(owner_id) if owner_id == &user.id => {
+                Evaluation::permit("the user owns the resource")
+            }
+            _ => Evaluation::deny("the user doesn't own the resource"),
+        }
+    }
+
+    fn partial_evaluate(&self, request: &PartialRequest, sql: &mut SqlBuilder) -> PartialResult {
+        let Some(column) = request.columns.get("owner_id") else {
+            return PartialResult::not_applicable();
+        };
+        if !matches!(request.action.name.as_str(), "edit" | "delete") {
+            return PartialResult::not_applicable();
+        }
+        let has_owner = Filter::Sql(format!("{} IS NOT NULL", column));
+        if request
+            .user
+            .has_permission(request.columns.resource_type, &request.action.name)
+        {
+            return PartialResult::effect(Effect::Permit, has_owner);
+        }
+        let owns = sql.compare(
+            column,
+            "=",
+            &AttributeValue::String(request.user.id.clone()),
+        );
+        PartialResult {
+            permit: owns.clone(),
+            deny: has_owner.and(owns.not()),
+        }
+    }
+}
+
+// Resource types and actions that need a permission, e.g. `category:write`.
+// The action `*` protects every action of the resource type.
+// Permissions come from the roles of the user and the roles those inherit from.
+pub struct PermissionPolicy {
+    protected: HashSet<(String, String)>,
+}
+
+impl PermissionPolicy {
+    pub fn new() -> Self {
+        Self {
+            protected: HashSet::new(),
+        }
+    }
+
+    pub fn require(mut self, resource_type: &str, action: &str) -> Self {
+        self.protected
+            .insert((resource_type.to_string(), action.to_string()));
+        self
+    }
+
+    fn is_protected(&self, resource_type: &str, action: &str) -> bool {
+        [action, "*"].iter().any(|action| {
+            self.protected
+                .contains(&(resource_type.to_string(), action.to_string()))
+        })
+    }
+}
+
+impl PolicyEvaluator for PermissionPolicy {
+    fn name(&self) -> &str {
+        "permission"
+    }
+
     fn evaluate(
         &self,
-        user: &User,
+        user: &UserInfo,
         resource: &Resource,
         action: &Action,
         _context: &Context,
-    ) -> bool {
-        // if action.name == "edit" && resource.resource_type == "post" {
-        //     if let (Some(AttributeValue::Number(user_id)), Some(AttributeValue::Number(owner_id))) = (
-        //         user.attributes.get("id"),
-        //         resource.attributes.get("owner_id"),
-        //     ) {
-        //         return user_id == owner_id;
-        //     }
-        // }
-        false
+    ) -> Evaluation {
+        let permission = format!("{}:{}", resource.resource_type, action.name);
+        if !self.is_protected(&resource.resource_type, &action.name) {
+            return Evaluation::not_applicable(format!("{} needs no permission", permission));
+        }
+        if user.has_permission(&resource.resource_type, &action.name) {
+            Evaluation::permit(format!("a role of the user grants {}", permission))
+        } else {
+            Evaluation::deny(format!(
+                "none of the roles {:?} grants {}",
+                user.roles, permission
+            ))
+        }
+    }
+
+    fn partial_evaluate(&self, request: &PartialRequest, _sql: &mut SqlBuilder) -> PartialResult {
+        let resource = Resource::new(request.columns.resource_type, "");
+        self.evaluate(request.user, &resource, request.action, request.context)
+            .decision
+            .into()
     }
 }
 
-// Policy engine that combines multiple policies
+// Resources carrying a `required_roles` list are only visible to those roles,
+// roles inheriting from them included.
+pub struct RequiredRolesPolicy;
+
+impl PolicyEvaluator for RequiredRolesPolicy {
+    fn name(&self) -> &str {
+        "required_roles"
+    }
+
+    fn evaluate(
+        &self,
+        user: &UserInfo,
+        resource: &Resource,
+        _action: &Action,
+        _context: &Context,
+    ) -> Evaluation {
+        match resource.attributes.get("required_roles") {
+            Some(AttributeValue::List(roles)) => {
+                let role = roles
+                    .iter()
+                    .filter_map(AttributeValue::as_string)
+                    .find(|role| user.has_role(role));
+                match role {
+                    Some(role) => {
+                        Evaluation::permit(format!("the {} role is in required_roles", role))
+                    }
+                    None => Evaluation::deny(format!(
+                        "none of the roles {:?} is in required_roles",
+                        user.roles
+                    )),
+                }
+            }
+            _ => Evaluation::not_applicable("the resource has no required_roles"),
+        }
+    }
+
+    fn partial_evaluate(&self, request: &PartialRequest, sql: &mut SqlBuilder) -> PartialResult {
+        let Some(column) = request.columns.get("required_roles") else {
+            return PartialResult::not_applicable();
+        };
+        let has_roles = Filter::Sql(format!("{} IS NOT NULL", column));
+        let required = request
+            .user
+            .roles
+            .iter()
+            .fold(Filter::False, |filter, role| {
+                filter.or(sql.contains(column, &AttributeValue::String(role.clone())))
+            });
+        PartialResult {
+            permit: has_roles.clone().and(required.clone()),
+            deny: has_roles.and(required.not()),
+        }
+    }
+}
+
+// Policy engine that combines multiple policies.
+// The built-in policies are fixed, the declarative ones are replaced when the `policies` table changes.
 pub struct PolicyEngine {
     policies: Vec<Box<dyn PolicyEvaluator + Send + Sync>>,
+    stored: RwLock<Vec<DeclarativePolicy>>,
+    algorithm: CombiningAlgorithm,
 }
 
 impl PolicyEngine {
     pub fn new() -> Self {
         Self {
             policies: Vec::new(),
+            stored: RwLock::new(Vec::new()),
+            algorithm: CombiningAlgorithm::default(),
         }
     }
 
+    /// The policies the application runs with
+    pub fn with_default_policies() -> Self {
+        let mut engine = Self::new();
+        // The permissions themselves are given to the roles in the `roles` table
+        engine.add_policy(
+            PermissionPolicy::new()
+                .require("category", "write")
+                .require("language", "write")
+                .require("field", "write")
+                .require("user", "*")
+                .require("policy", "*")
+                .require("attribute_schema", "*")
+                .require("role", "*")
+                .require("dash_nav_item", "*")
+                .require("audit_log", "*")
+                .require("error", "*"),
+        );
+        engine.add_policy(RequiredRolesPolicy);
+        engine.add_policy(OwnershipPolicy);
+        engine
+    }
+
+    pub fn with_algorithm(mut self, algorithm: CombiningAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
     pub fn add_policy<P: PolicyEvaluator + Send + Sync + 'static>(&mut self, policy: P) {
         self.policies.push(Box::new(policy));
     }
 
-    pub fn authorize(
+    /// Replaces the declarative policies (loaded from the `policies` table)
+    pub fn set_stored_policies(&self, policies: Vec<DeclarativePolicy>) {
+        *self.stored.write().unwrap_or_else(|err| err.into_inner()) = policies;
+    }
+
+    /// # Evaluate every policy and combine their decisions
+    /// Requests no policy applies to are allowed.
+    pub fn explain(
         &self,
-        user: &User,
+        user: &UserInfo,
         resource: &Resource,
         action: &Action,
         context: &Context,
-    ) -> bool {
-        self.policies
+    ) -> Explanation {
+        self.explain_with(None, self.algorithm, user, resource, action, context)
+    }
+
+    pub fn algorithm(&self) -> CombiningAlgorithm {
+        self.algorithm
+    }
+
+    /// # Explain a request against other declarative policies
+    /// The built-in policies still come first, `candidates` takes the place of the loaded ones
+    /// when given. Used by the simulator to try policies before saving them.
+    pub fn explain_with(
+        &self,
+        candidates: Option<&[DeclarativePolicy]>,
+        algorithm: CombiningAlgorithm,
+        user: &UserInfo,
+        resource: &Resource,
+        action: &Action,
+        context: &Context,
+    ) -> Explanation {
+        let loaded = self.stored.read().unwrap_or_else(|err| err.into_inner());
+        let stored = candidates.unwrap_or(&loaded);
+        let results: Vec<PolicyResult> = self
+            .policies
+            .iter()
+            .map(|policy| policy.as_ref() as &dyn PolicyEvaluator)
+            .chain(stored.iter().map(|policy| policy as &dyn PolicyEvaluator))
+            .map(|policy| {
+                let Evaluation { decision, reason } =
+                    policy.evaluate(user, resource, action, context);
+                PolicyResult {
+                    policy: policy.name().to_string(),
+                    decision,
+                    reason,
+                }
+            })
+            .collect();
+
+        let decisive = combine(algorithm, &results);
+        let decision = decisive.map_or(Decision::NotApplicable, |result| result.decision);
+        Explanation {
+            allowed: decision != Decision::Deny,
+            decision,
+            algorithm,
+            decided_by: decisive.map(|result| result.policy.clone()),
+            results,
+        }
+    }
+
+    /// # Filter the rows of a list query
+    /// Partially evaluates the policies with everything known but the resource attributes,
+    /// the rows passing the filter are the ones `explain` would allow.
+    /// The placeholders start at `first_param`.
+    pub fn filter(
+        &self,
+        user: &UserInfo,
+        action: &Action,
+        context: &Context,
+        columns: &ResourceColumns,
+        first_param: usize,
+    ) -> SqlFilter {
+        let mut sql = SqlBuilder::new(first_param);
+        let request = PartialRequest {
+            user,
+            action,
+            context,
+            columns,
+        };
+        let stored = self.stored.read().unwrap_or_else(|err| err.into_inner());
+        let results: Vec<PartialResult> = self
+            .policies
             .iter()
-            .all(|policy| policy.evaluate(user, resource, action, context))
+            .map(|policy| policy.as_ref() as &dyn PolicyEvaluator)
+            .chain(stored.iter().map(|policy| policy as &dyn PolicyEvaluator))
+            .map(|policy| policy.partial_evaluate(&request, &mut sql))
+            .collect();
+
+        let any = |filters: Vec<Filter>| filters.into_iter().fold(Filter::False, Filter::or);
+        let permits = || results.iter().map(|result| result.permit.clone()).collect();
+        let denies = || results.iter().map(|result| result.deny.clone()).collect();
+        // Rows no policy applies to are allowed, like in `explain`
+        let allowed = match self.algorithm {
+            CombiningAlgorithm::DenyOverrides => any(denies()).not(),
+            CombiningAlgorithm::PermitOverrides => any(permits()).or(any(denies()).not()),
+            CombiningAlgorithm::FirstApplicable => {
+                results.iter().rev().fold(Filter::True, |rest, result| {
+                    result
+                        .permit
+                        .clone()
+                        .or(result.deny.clone().not().and(rest))
+                })
+            }
+        };
+        sql.finish(allowed)
+    }
+}
+
+/// The result that decides for the algorithm, `None` when no policy applies
+fn combine(algorithm: CombiningAlgorithm, results: &[PolicyResult]) -> Option<&PolicyResult> {
+    let first = |decision: Decision| results.iter().find(|result| result.decision == decision);
+    match algorithm {
+        CombiningAlgorithm::DenyOverrides => {
+            first(Decision::Deny).or_else(|| first(Decision::Permit))
+        }
+        CombiningAlgorithm::PermitOverrides => {
+            first(Decision::Permit).or_else(|| first(Decision::Deny))
+        }
+        CombiningAlgorithm::FirstApplicable => results
+            .iter()
+            .find(|result| result.decision != Decision::NotApplicable),
+    }
+}
+
+/// Short reason of a denial, used as the 403 message
+pub fn denial_reason(explanation: &Explanation, resource: &Resource, action: &Action) -> String {
+    let decisive = explanation.results.iter().find(|result| {
+        result.decision == Decision::Deny && Some(&result.policy) == explanation.decided_by.as_ref()
+    });
+    match decisive {
+        Some(result) => format!(
+            "the {} policy denies {} on {}: {}",
+            result.policy, action.name, resource.resource_type, result.reason
+        ),
+        None => format!("{} on {} is denied", action.name, resource.resource_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::user::UserRole;
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    /// A policy that always takes the same decision
+    struct Fixed(&'static str, Decision);
+
+    impl PolicyEvaluator for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn evaluate(&self, _: &UserInfo, _: &Resource, _: &Action, _: &Context) -> Evaluation {
+            Evaluation {
+                decision: self.1,
+                reason: "fixed".to_string(),
+            }
+        }
+
+        fn partial_evaluate(&self, _: &PartialRequest, _: &mut SqlBuilder) -> PartialResult {
+            match self.1 {
+                Decision::Permit => PartialResult {
+                    permit: Filter::True,
+                    deny: Filter::False,
+                },
+                Decision::Deny => PartialResult {
+                    permit: Filter::False,
+                    deny: Filter::True,
+                },
+                Decision::NotApplicable => PartialResult::not_applicable(),
+            }
+        }
+    }
+
+    fn user() -> UserInfo {
+        UserInfo {
+            id: "1".to_string(),
+            email: "user@example.com".to_string(),
+            role: UserRole::User,
+            attributes: Default::default(),
+            disabled: false,
+            password_reset_required: false,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    fn engine(
+        algorithm: CombiningAlgorithm,
+        decisions: &[(&'static str, Decision)],
+    ) -> PolicyEngine {
+        let mut engine = PolicyEngine::new().with_algorithm(algorithm);
+        for &(name, decision) in decisions {
+            engine.add_policy(Fixed(name, decision));
+        }
+        engine
+    }
+
+    fn explain(engine: &PolicyEngine) -> Explanation {
+        engine.explain(
+            &user(),
+            &Resource::new("listing", "1"),
+            &Action::new("edit"),
+            &Context::new("127.0.0.1"),
+        )
+    }
+
+    const MIXED: [(&str, Decision); 4] = [
+        ("none", Decision::NotApplicable),
+        ("permit", Decision::Permit),
+        ("deny", Decision::Deny),
+        ("other_permit", Decision::Permit),
+    ];
+
+    #[test]
+    fn deny_overrides_any_permit() {
+        let explanation = explain(&engine(CombiningAlgorithm::DenyOverrides, &MIXED));
+        assert_eq!(explanation.decision, Decision::Deny);
+        assert!(!explanation.allowed);
+        assert_eq!(explanation.decided_by.as_deref(), Some("deny"));
+        assert_eq!(explanation.results.len(), 4);
+    }
+
+    #[test]
+    fn permit_overrides_any_deny() {
+        let explanation = explain(&engine(CombiningAlgorithm::PermitOverrides, &MIXED));
+        assert_eq!(explanation.decision, Decision::Permit);
+        assert!(explanation.allowed);
+        assert_eq!(explanation.decided_by.as_deref(), Some("permit"));
+    }
+
+    #[test]
+    fn first_applicable_follows_the_order() {
+        let explanation = explain(&engine(CombiningAlgorithm::FirstApplicable, &MIXED));
+        assert_eq!(explanation.decided_by.as_deref(), Some("permit"));
+
+        let denied_first = [MIXED[0], MIXED[2], MIXED[1]];
+        let explanation = explain(&engine(CombiningAlgorithm::FirstApplicable, &denied_first));
+        assert_eq!(explanation.decision, Decision::Deny);
+        assert_eq!(explanation.decided_by.as_deref(), Some("deny"));
+    }
+
+    #[test]
+    fn nothing_applicable_is_allowed() {
+        for algorithm in [
+            CombiningAlgorithm::DenyOverrides,
+            CombiningAlgorithm::PermitOverrides,
+            CombiningAlgorithm::FirstApplicable,
+        ] {
+            let explanation = explain(&engine(algorithm, &[MIXED[0]]));
+            assert_eq!(explanation.decision, Decision::NotApplicable);
+            assert!(explanation.allowed);
+            assert_eq!(explanation.decided_by, None);
+        }
+    }
+
+    #[test]
+    fn the_filter_allows_what_explain_allows() {
+        let decisions = [Decision::Permit, Decision::Deny, Decision::NotApplicable];
+        let columns = ResourceColumns::new("listing");
+        for algorithm in [
+            CombiningAlgorithm::DenyOverrides,
+            CombiningAlgorithm::PermitOverrides,
+            CombiningAlgorithm::FirstApplicable,
+        ] {
+            for first in decisions {
+                for second in decisions {
+                    let engine = engine(algorithm, &[("first", first), ("second", second)]);
+                    let filter = engine.filter(
+                        &user(),
+                        &Action::new("edit"),
+                        &Context::new("127.0.0.1"),
+                        &columns,
+                        1,
+                    );
+                    let allowed = explain(&engine).allowed;
+                    assert_eq!(
+                        filter.clause,
+                        if allowed { "TRUE" } else { "FALSE" },
+                        "{:?} of {:?} and {:?}",
+                        algorithm,
+                        first,
+                        second
+                    );
+                }
+            }
+        }
     }
 }
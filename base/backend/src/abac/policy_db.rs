@@ -0,0 +1,174 @@
+use crate::audit::{add_audit_entry, snapshot, AuditActor, AuditRecord};
+use crate::error::Result;
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use serde_json::Value;
+use shared::policy::{Policy, PolicyDocument, PolicyPayload};
+use sqlx::{query, query_as, PgPool, Postgres, Transaction};
+use time::OffsetDateTime;
+
+struct PolicyRow {
+    id: i32,
+    name: String,
+    description: Option<String>,
+    document: Value,
+    active: bool,
+    position: i32,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+}
+
+/// Rows edited by hand can hold a document the engine doesn't understand,
+/// those are logged and left out instead of breaking every request.
+fn into_policies(rows: Vec<PolicyRow>) -> Vec<Policy> {
+    rows.into_iter()
+        .filter_map(
+            |row| match serde_json::from_value::<PolicyDocument>(row.document) {
+                Ok(document) => Some(Policy {
+                    id: row.id,
+                    name: row.name,
+                    description: row.description,
+                    document,
+                    active: row.active,
+                    position: row.position,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                }),
+                Err(err) => {
+                    tracing::error!("Policy {} ({}) is invalid: {}", row.name, row.id, err);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+fn document_value(document: &PolicyDocument) -> Result<Value> {
+    serde_json::to_value(document).map_err(|err| SamError::Err(err.to_string()))
+}
+
+#[catch_error]
+pub async fn list_policies(pool: &PgPool) -> Result<Vec<Policy>> {
+    let rows: Vec<PolicyRow> = query_as!(
+        PolicyRow,
+        r#"
+        SELECT id, name, description, document, active, position, created_at, updated_at
+        FROM policies
+        ORDER BY position, name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(into_policies(rows))
+}
+
+#[catch_error]
+pub async fn list_active_policies(pool: &PgPool) -> Result<Vec<Policy>> {
+    let rows: Vec<PolicyRow> = query_as!(
+        PolicyRow,
+        r#"
+        SELECT id, name, description, document, active, position, created_at, updated_at
+        FROM policies
+        WHERE active
+        ORDER BY position, name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(into_policies(rows))
+}
+
+#[catch_error]
+pub async fn add_policy(pool: &PgPool, actor: &AuditActor, payload: &PolicyPayload) -> Result<i32> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let row = query!(
+        r#"
+        INSERT INTO policies (name, description, document, active, position)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        payload.name,
+        payload.description,
+        document_value(&payload.document)?,
+        payload.active,
+        payload.position
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let after = snapshot(&mut tx, "policies", &row.id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("create", "policy", row.id).after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(row.id)
+}
+
+#[catch_error]
+pub async fn update_policy(
+    pool: &PgPool,
+    actor: &AuditActor,
+    id: i32,
+    payload: &PolicyPayload,
+) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "policies", &id.to_string()).await?;
+    let result = query!(
+        r#"
+        UPDATE policies
+        SET name = $1, description = $2, document = $3, active = $4, position = $5,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $6
+        "#,
+        payload.name,
+        payload.description,
+        document_value(&payload.document)?,
+        payload.active,
+        payload.position,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Policy".to_string()));
+    }
+    let after = snapshot(&mut tx, "policies", &id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("update", "policy", id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn delete_policy(pool: &PgPool, actor: &AuditActor, id: i32) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "policies", &id.to_string()).await?;
+    let result = query!(
+        r#"
+        DELETE FROM policies
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Policy".to_string()));
+    }
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("delete", "policy", id).before(before),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
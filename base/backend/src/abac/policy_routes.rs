@@ -0,0 +1,141 @@
+// Routes for managing the declarative policies (super admins only)
+// GET	/policies	List all policies, active or not
+// POST	/policies	Create a policy
+// PUT	/policies/{id}	Update a policy
+// DELETE	/policies/{id}	Delete a policy
+// POST	/policies/simulate	Explain a request without performing it
+// POST	/policies/test	Run a YAML or JSON fixture of expected decisions
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    http::{header, HeaderMap},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use sam_error::SamError;
+use serde_json::json;
+use shared::policy::{PolicyPayload, SimulationRequest};
+
+use super::{
+    authorize, parse_fixture,
+    policy_db::{add_policy, delete_policy, list_policies, update_policy},
+    reload::reload_policies,
+    run_fixture, simulate, Authorize,
+};
+use crate::{
+    audit::AuditActor,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn policy_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/policies",
+            get(list_policies_handler).post(add_policy_handler),
+        )
+        .route(
+            "/policies/{id}",
+            put(update_policy_handler).delete(delete_policy_handler),
+        )
+        .route("/policies/simulate", post(simulate_handler))
+        .route("/policies/test", post(test_policies_handler))
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "policy", "manage"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
+
+fn validate(payload: &PolicyPayload) -> Result<()> {
+    if payload.name.trim().is_empty() {
+        return Err(SamError::InvalidPolicy(
+            "the name can't be empty".to_string(),
+        ));
+    }
+    payload.document.validate().map_err(SamError::InvalidPolicy)
+}
+
+/// Every backend reloads on the database notification,
+/// reloading here too makes the change visible as soon as the response is sent.
+async fn reload(state: &AppState) -> Result<()> {
+    reload_policies(&state.pool, &state.policy_engine).await
+}
+
+async fn list_policies_handler(State(state): State<AppState>) -> Result<Response> {
+    let policies = list_policies(&state.pool).await?;
+    let res = UserResponse::with_json(policies).into_response();
+    Ok(res)
+}
+
+async fn add_policy_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    payload: Result<Json<PolicyPayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    validate(&payload)?;
+    let id = add_policy(&state.pool, &actor, &payload).await?;
+    reload(&state).await?;
+    let res = UserResponse::with_json_and_code(json!({ "id": id }), 201).into_response();
+    Ok(res)
+}
+
+async fn update_policy_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+    payload: Result<Json<PolicyPayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    validate(&payload)?;
+    update_policy(&state.pool, &actor, id, &payload).await?;
+    reload(&state).await?;
+    let res = UserResponse::with_success("Policy updated").into_response();
+    Ok(res)
+}
+
+async fn delete_policy_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    delete_policy(&state.pool, &actor, id).await?;
+    reload(&state).await?;
+    let res = UserResponse::with_success("Policy deleted").into_response();
+    Ok(res)
+}
+
+async fn simulate_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<SimulationRequest>, JsonRejection>,
+) -> Result<Response> {
+    let request = payload?.0;
+    let explanation = simulate(&state, request).await?;
+    let res = UserResponse::with_json(explanation).into_response();
+    Ok(res)
+}
+
+/// The body is a fixture, YAML with a `yaml` content type and JSON otherwise
+async fn test_policies_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let fixture = parse_fixture(content_type, &body)?;
+    let report = run_fixture(&state, fixture).await?;
+    let res = UserResponse::with_json(report).into_response();
+    Ok(res)
+}
@@ -0,0 +1,42 @@
+use std::{sync::Arc, time::Duration};
+
+use sqlx::{postgres::PgListener, PgPool};
+
+use super::{policy_db::list_active_policies, DeclarativePolicy, PolicyEngine};
+use crate::error::Result;
+
+/// Notified by a trigger on every change of the `policies` table
+const POLICIES_CHANNEL: &str = "policies_changed";
+
+/// Loads the active policies of the `policies` table into the engine
+pub async fn reload_policies(pool: &PgPool, engine: &PolicyEngine) -> Result<()> {
+    let policies = list_active_policies(pool).await?;
+    tracing::info!("Loaded {} policies", policies.len());
+    engine.set_stored_policies(policies.into_iter().map(DeclarativePolicy::from).collect());
+    Ok(())
+}
+
+/// # Keep the engine in sync with the `policies` table
+/// Runs forever, meant to be spawned once at startup.
+/// Every backend listens to the notifications, so a change applies everywhere without a restart.
+pub async fn watch_policies(pool: Arc<PgPool>, engine: Arc<PolicyEngine>) {
+    loop {
+        if let Err(err) = listen(&pool, &engine).await {
+            tracing::error!("Policy listener failed: {}", err);
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+async fn listen(pool: &PgPool, engine: &PolicyEngine) -> Result<()> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(POLICIES_CHANNEL).await?;
+
+    // Reload once we listen so a change made in between isn't missed
+    reload_policies(pool, engine).await?;
+    loop {
+        // `None` means the connection was lost, changes may have been missed while reconnecting
+        let _notification = listener.try_recv().await?;
+        reload_policies(pool, engine).await?;
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use sam_error::SamError;
+use serde_json::Value;
+use shared::{
+    policy::{
+        CaseResult, CombiningAlgorithm, Expectation, Explanation, FixtureReport, PolicyCase,
+        PolicyFixture, PolicyPayload, SimulatedContext, SimulatedResource, SimulatedUser,
+        SimulationRequest,
+    },
+    user::{UserInfo, UserRole},
+    Action, AttributeMap, AttributeValue, Context, Resource,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{DeclarativePolicy, PolicyEngine};
+use crate::{error::Result, role::role_db::resolve_roles, user::fetch_user_by_id, AppState};
+
+/// # Evaluate a request without performing it
+/// Uses the candidate policies of the request instead of the stored ones when given.
+pub async fn simulate(state: &AppState, request: SimulationRequest) -> Result<Explanation> {
+    let candidates = request.policies.map(candidate_policies).transpose()?;
+    let algorithm = request
+        .algorithm
+        .unwrap_or_else(|| state.policy_engine.algorithm());
+    let user = simulated_user(state, &request.user).await?;
+
+    Ok(state.policy_engine.explain_with(
+        candidates.as_deref(),
+        algorithm,
+        &user,
+        &simulated_resource(request.resource),
+        &Action::new(request.action),
+        &simulated_context(request.context),
+    ))
+}
+
+/// # Check every case of a fixture
+/// A failing case doesn't stop the run, the report lists them all.
+pub async fn run_fixture(state: &AppState, fixture: PolicyFixture) -> Result<FixtureReport> {
+    let candidates = fixture.policies.map(candidate_policies).transpose()?;
+    let algorithm = fixture
+        .algorithm
+        .unwrap_or_else(|| state.policy_engine.algorithm());
+
+    let mut results = Vec::with_capacity(fixture.cases.len());
+    for case in fixture.cases {
+        let user = simulated_user(state, &case.user).await?;
+        results.push(check_case(
+            &state.policy_engine,
+            candidates.as_deref(),
+            algorithm,
+            &user,
+            case,
+        ));
+    }
+    Ok(fixture_report(results))
+}
+
+/// Compares the decision of the engine with the expected one, `user` is the resolved `case.user`
+fn check_case(
+    engine: &PolicyEngine,
+    candidates: Option<&[DeclarativePolicy]>,
+    algorithm: CombiningAlgorithm,
+    user: &UserInfo,
+    case: PolicyCase,
+) -> CaseResult {
+    let PolicyCase {
+        name,
+        resource,
+        action,
+        context,
+        expect,
+        ..
+    } = case;
+    let explanation = engine.explain_with(
+        candidates,
+        algorithm,
+        user,
+        &simulated_resource(resource),
+        &Action::new(action),
+        &simulated_context(context),
+    );
+    let passed = match expect {
+        Expectation::Allow => explanation.allowed,
+        Expectation::Deny => !explanation.allowed,
+    };
+    CaseResult {
+        name,
+        expected: expect,
+        passed,
+        explanation,
+    }
+}
+
+fn fixture_report(results: Vec<CaseResult>) -> FixtureReport {
+    let passed = results.iter().filter(|result| result.passed).count();
+    FixtureReport {
+        passed,
+        failed: results.len() - passed,
+        results,
+    }
+}
+
+/// Fixtures are YAML when the content type says so, JSON otherwise
+pub fn parse_fixture(content_type: &str, body: &str) -> Result<PolicyFixture> {
+    let fixture = if content_type.contains("yaml") {
+        serde_yaml::from_str(body).map_err(|err| err.to_string())
+    } else {
+        serde_json::from_str(body).map_err(|err| err.to_string())
+    };
+    fixture.map_err(|err| SamError::InvalidPolicy(format!("the fixture can't be read: {}", err)))
+}
+
+/// Inactive policies are left out and the rest is ordered like the stored ones
+fn candidate_policies(mut payloads: Vec<PolicyPayload>) -> Result<Vec<DeclarativePolicy>> {
+    for payload in &payloads {
+        payload
+            .document
+            .validate()
+            .map_err(|err| SamError::InvalidPolicy(format!("{}: {}", payload.name, err)))?;
+    }
+    payloads.retain(|payload| payload.active);
+    payloads.sort_by(|a, b| (a.position, &a.name).cmp(&(b.position, &b.name)));
+    Ok(payloads.into_iter().map(DeclarativePolicy::from).collect())
+}
+
+async fn simulated_user(state: &AppState, user: &SimulatedUser) -> Result<UserInfo> {
+    let Some(role) = user.role.clone() else {
+        let id = user.id.as_deref().ok_or(SamError::InvalidPolicy(
+            "the simulated user needs an id or a role".to_string(),
+        ))?;
+        let id = Uuid::parse_str(id)
+            .map_err(|_| SamError::InvalidPolicy(format!("{} is not a user id", id)))?;
+        // The lookup answers a failed login when the user doesn't exist
+        return fetch_user_by_id(&state.pool, id)
+            .await
+            .map_err(|err| match err {
+                SamError::LoginFailed => SamError::NotFound("User".to_string()),
+                err => err,
+            });
+    };
+
+    // The built-in role and the extra ones, with everything they inherit
+    let mut names = vec![role.name().to_string()];
+    names.extend(user.roles.iter().cloned());
+    let (roles, permissions) = resolve_roles(&state.pool, &names).await?;
+    Ok(role_user(user, role, roles, permissions))
+}
+
+/// A user that only exists for the simulation
+fn role_user(
+    user: &SimulatedUser,
+    role: UserRole,
+    roles: Vec<String>,
+    permissions: Vec<String>,
+) -> UserInfo {
+    UserInfo {
+        id: user.id.clone().unwrap_or_default(),
+        email: user.email.clone().unwrap_or_default(),
+        role,
+        attributes: AttributeMap(attributes(user.attributes.clone())),
+        disabled: false,
+        password_reset_required: false,
+        created_at: OffsetDateTime::now_utc(),
+        roles,
+        permissions,
+    }
+}
+
+fn simulated_resource(resource: SimulatedResource) -> Resource {
+    Resource {
+        id: resource.id,
+        resource_type: resource.resource_type,
+        attributes: attributes(resource.attributes),
+    }
+}
+
+fn simulated_context(context: SimulatedContext) -> Context {
+    let mut attributes = attributes(context.attributes);
+    attributes
+        .entry("time".to_string())
+        .or_insert_with(|| AttributeValue::DateTime(OffsetDateTime::now_utc()));
+    Context {
+        ip_address: context.ip.unwrap_or_default(),
+        attributes,
+    }
+}
+
+fn attributes(value: Value) -> HashMap<String, AttributeValue> {
+    match value {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| (key, AttributeValue::from(value)))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::policy::Decision;
+
+    use super::*;
+
+    const YAML: &str = include_str!("../../fixtures/policies/owners-only.yaml");
+    const JSON: &str = include_str!("../../fixtures/policies/owners-only.json");
+
+    /// `run_fixture` without the database, the users only have their own role
+    fn run(fixture: PolicyFixture) -> FixtureReport {
+        let candidates = fixture
+            .policies
+            .map(candidate_policies)
+            .transpose()
+            .unwrap();
+        let algorithm = fixture.algorithm.unwrap_or_default();
+        let engine = PolicyEngine::new();
+        let results = fixture
+            .cases
+            .into_iter()
+            .map(|case| {
+                let role = case.user.role.clone().expect("the cases have a role");
+                let mut roles = vec![role.name().to_string()];
+                roles.extend(case.user.roles.iter().cloned());
+                let user = role_user(&case.user, role, roles, Vec::new());
+                check_case(&engine, candidates.as_deref(), algorithm, &user, case)
+            })
+            .collect();
+        fixture_report(results)
+    }
+
+    #[test]
+    fn yaml_and_json_fixtures_are_the_same() {
+        let yaml = parse_fixture("application/yaml", YAML).unwrap();
+        let json = parse_fixture("application/json", JSON).unwrap();
+        assert_eq!(yaml.cases.len(), 3);
+        assert_eq!(yaml.policies.as_ref().map(Vec::len), Some(1));
+        assert_eq!(
+            serde_json::to_value(&yaml).unwrap(),
+            serde_json::to_value(&json).unwrap()
+        );
+    }
+
+    #[test]
+    fn json_is_the_default_format() {
+        assert!(parse_fixture("", JSON).is_ok());
+        assert!(parse_fixture("text/plain", YAML).is_err());
+    }
+
+    #[test]
+    fn malformed_fixtures_are_invalid_policies() {
+        for (content_type, body) in [
+            ("application/json", "{ \"cases\": "),
+            (
+                "application/x-yaml",
+                "cases: [ { name: missing the rest } ]",
+            ),
+            ("application/yaml", "policies: ["),
+        ] {
+            match parse_fixture(content_type, body) {
+                Err(SamError::InvalidPolicy(message)) => {
+                    assert!(
+                        message.starts_with("the fixture can't be read"),
+                        "{}",
+                        message
+                    )
+                }
+                other => panic!("{} was read as {:?}", body, other),
+            }
+        }
+    }
+
+    #[test]
+    fn every_case_of_the_sample_passes() {
+        let report = run(parse_fixture("application/yaml", YAML).unwrap());
+        assert_eq!((report.passed, report.failed), (3, 0));
+        let decisions: Vec<_> = report
+            .results
+            .iter()
+            .map(|result| result.explanation.decision)
+            .collect();
+        assert_eq!(
+            decisions,
+            [
+                Decision::NotApplicable,
+                Decision::Deny,
+                Decision::NotApplicable
+            ]
+        );
+    }
+
+    #[test]
+    fn the_report_lists_expected_and_actual_decisions() {
+        let mut fixture = parse_fixture("application/json", JSON).unwrap();
+        // The admin is allowed, so expecting a deny fails
+        fixture.cases[2].expect = Expectation::Deny;
+        let report = run(fixture);
+
+        assert_eq!((report.passed, report.failed), (2, 1));
+        assert_eq!(report.results.len(), 3);
+        let failed = &report.results[2];
+        assert_eq!(failed.name, "admins edit every listing");
+        assert_eq!(failed.expected, Expectation::Deny);
+        assert!(!failed.passed);
+        assert!(failed.explanation.allowed);
+
+        let denied = &report.results[1];
+        assert!(denied.passed);
+        assert!(!denied.explanation.allowed);
+        assert_eq!(
+            denied.explanation.decided_by.as_deref(),
+            Some("owners-only")
+        );
+    }
+
+    #[test]
+    fn inactive_policies_are_left_out() {
+        let mut fixture = parse_fixture("application/yaml", YAML).unwrap();
+        if let Some(policies) = fixture.policies.as_mut() {
+            policies[0].active = false;
+        }
+        let report = run(fixture);
+        assert_eq!((report.passed, report.failed), (2, 1));
+        assert!(!report.results[1].passed);
+    }
+}
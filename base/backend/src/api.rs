@@ -0,0 +1,102 @@
+// The backend side of the endpoints declared in shared::api
+// Router::endpoint(E, handler) routes the handler at the method and path of E, the
+// Params<E> and Payload<E> extractors and reply::<E> tie the handler to its types.
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, Path, Query, Request},
+    handler::Handler,
+    http::request::Parts,
+    response::{IntoResponse, Response},
+    routing::{on, MethodFilter},
+    Json, Router,
+};
+use sam_error::SamError;
+use serde::de::DeserializeOwned;
+use shared::api::{Endpoint, Method};
+
+use crate::{
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+};
+
+pub trait EndpointRouter<S> {
+    /// Routes `handler` at the method and path of `E`
+    fn endpoint<E, H, T>(self, endpoint: E, handler: H) -> Self
+    where
+        E: Endpoint,
+        H: Handler<T, S>,
+        T: 'static;
+}
+
+impl<S: Clone + Send + Sync + 'static> EndpointRouter<S> for Router<S> {
+    fn endpoint<E, H, T>(self, _endpoint: E, handler: H) -> Self
+    where
+        E: Endpoint,
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        let filter = match E::METHOD {
+            Method::Get => MethodFilter::GET,
+            Method::Post => MethodFilter::POST,
+            Method::Put => MethodFilter::PUT,
+            Method::Delete => MethodFilter::DELETE,
+        };
+        self.route(E::PATH, on(filter, handler))
+    }
+}
+
+/// The `{..}` segments of the path of `E`
+pub struct Params<E: Endpoint>(pub E::Params);
+
+impl<E, S> FromRequestParts<S> for Params<E>
+where
+    E: Endpoint,
+    E::Params: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(params) = Path::<E::Params>::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(Self(params))
+    }
+}
+
+/// The request of `E`, from the query string or the JSON body depending on the method
+pub struct Payload<E: Endpoint>(pub E::Request);
+
+impl<E, S> FromRequest<S> for Payload<E>
+where
+    E: Endpoint,
+    E::Request: Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if E::METHOD.has_body() {
+            let Json(request) = Json::<E::Request>::from_request(req, state)
+                .await
+                .map_err(|rejection| SamError::from(rejection).into_response())?;
+            Ok(Self(request))
+        } else {
+            let (mut parts, _) = req.into_parts();
+            let Query(request) = Query::<E::Request>::from_request_parts(&mut parts, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            Ok(Self(request))
+        }
+    }
+}
+
+/// Answers with the response of `E`, a `String` is sent as the success message
+pub fn reply<E: Endpoint>(response: E::Response) -> Result<Response> {
+    let res = match serde_json::to_value(response) {
+        Ok(serde_json::Value::String(msg)) => UserResponse::with_success(msg),
+        Ok(json) => UserResponse::with_json(json),
+        Err(e) => return Err(SamError::Err(e.to_string())),
+    };
+    Ok(res.into_response())
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use sam_error::SamError;
+use shared::user::UserInfo;
+use uuid::Uuid;
+
+use crate::{abac::client_ip, AppState};
+
+/// # Who performs a mutation
+/// The logged in user (if any) with the address and the user agent of the request,
+/// written to the `audit_log` with every mutation.
+#[derive(Debug, Clone)]
+pub struct AuditActor {
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub ip: String,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppState> for AuditActor {
+    type Rejection = SamError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = parts.extensions.get::<Arc<UserInfo>>();
+        Ok(Self {
+            user_id: user.and_then(|user| Uuid::parse_str(&user.id).ok()),
+            email: user.map(|user| user.email.clone()),
+            ip: client_ip(parts, state.config.trust_proxy_headers),
+            user_agent: parts
+                .headers
+                .get(axum::http::header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    error::Result,
+    list_query::{fetch_page, ListColumns},
+};
+use sam_proc_macros::catch_error;
+use serde_json::Value;
+use shared::{
+    audit::{json_diff, AuditEntry, AuditFilter},
+    list::{ListQuery, Page},
+};
+use sqlx::{postgres::PgRow, query, query_scalar, PgPool, Postgres, Transaction};
+
+use super::AuditActor;
+
+/// # A mutation to record
+/// ```ignore
+/// let before = snapshot(&mut tx, "languages", &id.to_string()).await?;
+/// // update the language
+/// let after = snapshot(&mut tx, "languages", &id.to_string()).await?;
+/// add_audit_entry(
+///     &mut tx,
+///     actor,
+///     AuditRecord::new("update", "language", id).before(before).after(after),
+/// )
+/// .await?;
+/// ```
+pub struct AuditRecord {
+    action: String,
+    resource_type: String,
+    resource_id: String,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl AuditRecord {
+    pub fn new(action: &str, resource_type: &str, resource_id: impl ToString) -> Self {
+        Self {
+            action: action.to_string(),
+            resource_type: resource_type.to_string(),
+            resource_id: resource_id.to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn before(mut self, before: Option<Value>) -> Self {
+        self.before = before;
+        self
+    }
+
+    pub fn after(mut self, after: Option<Value>) -> Self {
+        self.after = after;
+        self
+    }
+}
+
+/// Written in the transaction of the mutation, so it's only kept when the mutation is
+pub async fn add_audit_entry(
+    tx: &mut Transaction<'static, Postgres>,
+    actor: &AuditActor,
+    record: AuditRecord,
+) -> Result<()> {
+    let diff = json_diff(record.before.as_ref(), record.after.as_ref());
+    query!(
+        r#"
+        INSERT INTO audit_log
+            (actor_id, actor_email, action, resource_type, resource_id, before, after, diff, ip, user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#,
+        actor.user_id,
+        actor.email,
+        record.action,
+        record.resource_type,
+        record.resource_id,
+        record.before,
+        record.after,
+        diff,
+        actor.ip,
+        actor.user_agent
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// # A row as JSON
+/// `table` is one of ours, never user input. Passwords are left out.
+pub async fn snapshot(
+    tx: &mut Transaction<'static, Postgres>,
+    table: &'static str,
+    id: &str,
+) -> Result<Option<Value>> {
+    let sql = format!(
+        "SELECT to_jsonb(t) - 'password' FROM {} t WHERE t.id::text = $1",
+        table
+    );
+    let row: Option<Value> = query_scalar(&sql)
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await?;
+    Ok(row)
+}
+
+/// The audit log fields a list can be sorted and filtered on
+pub fn audit_log_columns() -> ListColumns {
+    ListColumns::new("id", "id", "bigint")
+        .column("created_at", "created_at", "timestamptz")
+        .column("action", "action", "text")
+        .column("resource_type", "resource_type", "text")
+        .column("resource_id", "resource_id", "text")
+        .filter_column("created_on", "created_at::date", "date")
+        .filter_column("actor_email", "actor_email", "text")
+        .filter_column("actor_id", "actor_id", "uuid")
+}
+
+pub async fn list_audit_log(pool: &PgPool, list: ListQuery) -> Result<Page<AuditEntry>> {
+    fetch_page(pool, "audit_log", &audit_log_columns(), &list, None).await
+}
+
+/// Every entry matching the filter (no paging), as text columns for `rows_to_dataframe`
+#[catch_error]
+pub async fn export_audit_log_rows(pool: &PgPool, filter: &AuditFilter) -> Result<Vec<PgRow>> {
+    let rows: Vec<PgRow> = query(
+        r#"
+        SELECT
+            id::text, created_at::text, actor_id::text, actor_email, action, resource_type,
+            resource_id, diff::text, before::text, after::text, ip, user_agent
+        FROM audit_log
+        WHERE ($1::text IS NULL OR actor_email ILIKE '%' || $1 || '%')
+            AND ($2::text IS NULL OR action = $2)
+            AND ($3::text IS NULL OR resource_type = $3)
+            AND ($4::text IS NULL OR resource_id = $4)
+            AND ($5::text IS NULL OR created_at >= $5::date)
+            AND ($6::text IS NULL OR created_at < $6::date + 1)
+        ORDER BY created_at DESC, id DESC
+        "#,
+    )
+    .bind(non_empty(&filter.actor))
+    .bind(non_empty(&filter.action))
+    .bind(non_empty(&filter.resource_type))
+    .bind(non_empty(&filter.resource_id))
+    .bind(non_empty(&filter.from))
+    .bind(non_empty(&filter.to))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_ref()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,81 @@
+// Routes for reading the audit log
+// GET	/audit-log	Page through the entries, a ListQuery
+// GET	/audit-log/export	Download every matching entry, ?format=csv (default) or json
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use sam_error::SamError;
+use sam_util::{df_to_csv, df_to_json, rows_to_dataframe};
+use serde::Deserialize;
+use shared::audit::{AuditFilter, ListAuditLog};
+
+use super::audit_db::{export_audit_log_rows, list_audit_log};
+use crate::{
+    abac::{authorize, Authorize},
+    api::reply,
+    error::Result,
+    list_query::ListParams,
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn audit_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/audit-log", get(list_audit_log_handler))
+        .route("/audit-log/export", get(export_audit_log_handler))
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "audit_log", "view"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
+
+async fn list_audit_log_handler(
+    State(state): State<AppState>,
+    ListParams(list): ListParams,
+) -> Result<Response> {
+    let page = list_audit_log(&state.pool, list).await?;
+    reply::<ListAuditLog>(page)
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    format: Option<String>,
+}
+
+async fn export_audit_log_handler(
+    State(state): State<AppState>,
+    Query(filter): Query<AuditFilter>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response> {
+    let rows = export_audit_log_rows(&state.pool, &filter).await?;
+    let mut df = rows_to_dataframe(rows).await?;
+    let (content_type, extension, body) = match params.format.as_deref() {
+        Some("json") => (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&df_to_json(&mut df)?)
+                .map_err(|err| SamError::Err(err.to_string()))?,
+        ),
+        None | Some("csv") => ("text/csv", "csv", df_to_csv(&mut df)?),
+        Some(format) => return Err(SamError::Err(format!("Unknown export format {}", format))),
+    };
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"audit-log.{}\"", extension),
+        ),
+    ];
+    Ok((headers, body).into_response())
+}
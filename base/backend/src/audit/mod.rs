@@ -0,0 +1,6 @@
+mod actor;
+pub mod audit_db;
+mod audit_routes;
+pub use actor::AuditActor;
+pub use audit_db::{add_audit_entry, snapshot, AuditRecord};
+pub use audit_routes::audit_routes;
@@ -1,19 +1,27 @@
 // use super::Category;
+use crate::abac::{ResourceColumns, SqlFilter};
+use crate::audit::{add_audit_entry, snapshot, AuditActor, AuditRecord};
 use crate::error::Result;
+use crate::list_query::{fetch_page, ListColumns};
 use polars::prelude::{lit, IntoLazy};
 use polars::{frame::DataFrame, prelude::col};
 use sam_proc_macros::catch_error;
 use sam_util::rows_to_dataframe;
+use serde_json::json;
+use shared::list::{ListQuery, Page};
 use shared::{Category, CategoryName};
 use sqlx::{query, query_as, PgPool};
 
 #[catch_error]
 pub async fn add_category_with_names(
     pool: &PgPool,
+    actor: &AuditActor,
     category: Category,
     names: Vec<CategoryName>,
 ) -> Result<()> {
     let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let record = AuditRecord::new("create", "category", &category.id)
+        .after(Some(json!({ "category": category, "names": names })));
 
     // Insert category
     add_category(&mut tx, category).await?;
@@ -23,6 +31,54 @@ pub async fn add_category_with_names(
         add_category_name(&mut tx, name).await?;
     }
 
+    add_audit_entry(&mut tx, actor, record).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Sets the parent of the category and replaces its names
+#[catch_error]
+pub async fn update_category_with_names(
+    pool: &PgPool,
+    actor: &AuditActor,
+    category: Category,
+    names: Vec<CategoryName>,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "categories", &category.id).await?;
+    let record = AuditRecord::new("update", "category", &category.id)
+        .before(before)
+        .after(Some(json!({ "category": category, "names": names })));
+
+    let result = query!(
+        r#"
+        UPDATE categories
+        SET parent_id = $2
+        WHERE id = $1
+        "#,
+        category.id,
+        category.parent_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    query!(
+        r#"
+        DELETE FROM categories_names
+        WHERE category_id = $1
+        "#,
+        category.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    for name in names {
+        add_category_name(&mut tx, name).await?;
+    }
+
+    add_audit_entry(&mut tx, actor, record).await?;
     tx.commit().await?;
     Ok(())
 }
@@ -65,11 +121,25 @@ async fn add_category_name<'a>(
     Ok(())
 }
 
-#[catch_error]
-pub async fn list_categories(pool: &PgPool) -> Result<Vec<sqlx::postgres::PgRow>> {
-    let rows: Vec<sqlx::postgres::PgRow> =
-        query(r#"SELECT * FROM categories"#).fetch_all(pool).await?;
-    Ok(rows)
+/// The category attributes policies can filter on
+pub fn category_columns() -> ResourceColumns {
+    ResourceColumns::new("category")
+        .column("id", "id")
+        .column("parent_id", "parent_id")
+}
+
+/// The category fields a list can be sorted and filtered on
+pub fn category_list_columns() -> ListColumns {
+    ListColumns::new("id", "id", "text").filter_column("parent_id", "parent_id", "text")
+}
+
+/// `filter` keeps the categories the user may view, visitors get them all
+pub async fn list_categories(
+    pool: &PgPool,
+    list: ListQuery,
+    filter: Option<SqlFilter>,
+) -> Result<Page<Category>> {
+    fetch_page(pool, "categories", &category_list_columns(), &list, filter).await
 }
 
 #[catch_error]
@@ -132,7 +202,9 @@ pub async fn get_category(pool: &PgPool, category_id: String) -> Result<Category
 // }
 
 #[catch_error]
-pub async fn delete_category(pool: &PgPool, category_id: String) -> Result<()> {
+pub async fn delete_category(pool: &PgPool, actor: &AuditActor, category_id: String) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "categories", &category_id).await?;
     query!(
         r#"
         DELETE FROM categories 
@@ -140,8 +212,15 @@ pub async fn delete_category(pool: &PgPool, category_id: String) -> Result<()> {
         "#,
         category_id,
     )
-    .execute(pool)
+    .execute(&mut *tx)
+    .await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("delete", "category", category_id).before(before),
+    )
     .await?;
+    tx.commit().await?;
     Ok(())
 }
 
@@ -162,15 +241,22 @@ pub async fn delete_category(pool: &PgPool, category_id: String) -> Result<()> {
 //     Ok(())
 // }
 
-#[catch_error]
-pub async fn list_categories_names(pool: &PgPool) -> Result<Vec<CategoryName>> {
-    let categories: Vec<CategoryName> = query_as!(
-        CategoryName,
-        r#"
-        SELECT * FROM categories_names 
-        "#
+/// The category name fields a list can be sorted and filtered on
+pub fn category_name_columns() -> ListColumns {
+    ListColumns::new("id", "id", "integer")
+        .column("name", "name", "text")
+        .column("language_id", "language_id", "integer")
+        .column("category_id", "category_id", "text")
+        .column("slug", "slug", "text")
+}
+
+pub async fn list_categories_names(pool: &PgPool, list: ListQuery) -> Result<Page<CategoryName>> {
+    fetch_page(
+        pool,
+        "categories_names",
+        &category_name_columns(),
+        &list,
+        None,
     )
-    .fetch_all(pool)
-    .await?;
-    Ok(categories)
+    .await
 }
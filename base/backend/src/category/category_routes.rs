@@ -8,30 +8,43 @@
 use std::collections::HashMap;
 
 use super::category_db::*;
+use crate::abac::{authorize, Authorize, Authorizer};
+use crate::api::{reply, EndpointRouter, Params, Payload};
+use crate::audit::AuditActor;
 use crate::error::Result;
-use crate::{response::*, AppState};
-use axum::{
-    extract::{rejection::JsonRejection, Path, Query, State},
-    response::{IntoResponse, Response},
-    routing::{delete, get, post},
-    Json, Router,
-};
-use polars::prelude::{col, lit, DataType, IntoLazy};
-use sam_error::SamError;
-use sam_util::rows_to_dataframe;
+use crate::list_query::ListParams;
+use crate::user::{auth_middleware, optional_auth_middleware};
+use crate::AppState;
+use axum::{extract::State, middleware, response::Response, Router};
+use polars::prelude::DataType;
 use serde_json::{json, to_value};
-use shared::{Category, CategoryName, CategoryWithNames};
+use shared::{
+    AddCategory, DeleteCategory, GetCategory, ListCategories, ListCategoryNames, UpdateCategory,
+};
 
 pub fn category_routes(state: AppState) -> Router<AppState> {
+    let write_routes = Router::new()
+        .endpoint(AddCategory, add_category_with_names_handler)
+        .endpoint(UpdateCategory, update_category_with_names_handler)
+        .endpoint(DeleteCategory, delete_category_handler)
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "category", "write"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
     Router::new()
-        .route(
-            "/categories",
-            get(list_categories_handler).post(add_category_with_names_handler),
-        )
-        .route(
-            "/categories/{id}",
-            delete(delete_category_handler).get(get_category_handler),
-        )
+        .endpoint(ListCategories, list_categories_handler)
+        .endpoint(ListCategoryNames, list_categories_names_handler)
+        .endpoint(GetCategory, get_category_handler)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            optional_auth_middleware,
+        ))
+        .merge(write_routes)
 }
 
 // async fn list_categories_handler(State(state): State<AppState>) -> Result<Response> {
@@ -80,29 +93,22 @@ pub fn category_routes(state: AppState) -> Router<AppState> {
 //     Ok(res)
 // }
 
-async fn list_categories_handler(State(state): State<AppState>) -> Result<Response> {
-    let mut rows = list_categories(&state.pool).await?;
-    let df = rows_to_dataframe(rows).await?;
-    let mut filtered = df
-        .lazy()
-        .filter(col("id").str().contains(lit("a".to_string()), false))
-        .select([col("id")])
-        .collect()
-        .map_err(|e| sam_error::SamError::Err(e.to_string()))?;
-
-    let records = sam_util::df_to_json(&mut filtered).map_err(|e| SamError::Err(e.to_string()))?;
-
-    let res = UserResponse::with_json(records).into_response();
-    Ok(res)
+async fn list_categories_handler(
+    State(state): State<AppState>,
+    authorizer: Option<Authorizer>,
+    ListParams(list): ListParams,
+) -> Result<Response> {
+    let filter = authorizer.map(|authorizer| authorizer.filter(&category_columns(), "view", 1));
+    let page = list_categories(&state.pool, list, filter).await?;
+    reply::<ListCategories>(page)
 }
 
 async fn get_category_handler(
     State(state): State<AppState>,
-    Path(id): Path<String>,
+    Params(id): Params<GetCategory>,
 ) -> Result<Response> {
-    let category: Category = get_category(&state.pool, id).await?;
-    let res = UserResponse::with_json(category).into_response();
-    Ok(res)
+    let category = get_category(&state.pool, id).await?;
+    reply::<GetCategory>(category)
 }
 
 // async fn add_category_handler(
@@ -116,29 +122,49 @@ async fn get_category_handler(
 
 async fn add_category_with_names_handler(
     State(state): State<AppState>,
-    Json(category_with_names): Json<CategoryWithNames>,
+    actor: AuditActor,
+    Payload(category_with_names): Payload<AddCategory>,
 ) -> Result<Response> {
     add_category_with_names(
         &state.pool,
+        &actor,
         category_with_names.category,
         category_with_names.names,
     )
     .await?;
-    let res = UserResponse::with_success("Category and Names Added Successfully").into_response();
-    Ok(res)
+    reply::<AddCategory>("Category and Names Added Successfully".to_string())
+}
+
+async fn update_category_with_names_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Params(id): Params<UpdateCategory>,
+    Payload(category_with_names): Payload<UpdateCategory>,
+) -> Result<Response> {
+    // The category of the path, whatever the body says
+    let mut category = category_with_names.category;
+    category.id = id;
+    let mut names = category_with_names.names;
+    for name in &mut names {
+        name.category_id = category.id.clone();
+    }
+    update_category_with_names(&state.pool, &actor, category, names).await?;
+    reply::<UpdateCategory>("Category and Names Updated Successfully".to_string())
 }
 
 async fn delete_category_handler(
     State(state): State<AppState>,
-    Path(id): Path<String>,
+    actor: AuditActor,
+    Params(id): Params<DeleteCategory>,
 ) -> Result<Response> {
-    delete_category(&state.pool, id).await?;
-    let res = UserResponse::with_success("Category Deleted Successfully").into_response();
-    Ok(res)
+    delete_category(&state.pool, &actor, id).await?;
+    reply::<DeleteCategory>("Category Deleted Successfully".to_string())
 }
 
-async fn list_categories_names_handler(State(state): State<AppState>) -> Result<Response> {
-    let categories_names: Vec<CategoryName> = list_categories_names(&state.pool).await?;
-    let res = UserResponse::with_json(categories_names).into_response();
-    Ok(res)
+async fn list_categories_names_handler(
+    State(state): State<AppState>,
+    ListParams(list): ListParams,
+) -> Result<Response> {
+    let page = list_categories_names(&state.pool, list).await?;
+    reply::<ListCategoryNames>(page)
 }
@@ -0,0 +1,216 @@
+use std::{fmt, str::FromStr};
+
+use axum::http::{
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, ORIGIN, SET_COOKIE},
+    HeaderValue, Method,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::Config;
+
+/// # An origin allowed to call the API from a browser
+/// `https://app.example.com`, or `https://*.example.com` for every subdomain of
+/// `example.com` (but not `example.com` itself). Without a port the default one of the
+/// scheme is meant, `https://example.com` and `https://example.com:443` are the same.
+///
+/// ```ignore
+/// let pattern: OriginPattern = "https://*.example.com".parse().unwrap();
+/// assert!(pattern.matches("https://shop.example.com"));
+/// assert!(!pattern.matches("https://example.com"));
+/// assert!(!pattern.matches("http://shop.example.com"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginPattern {
+    scheme: String,
+    host: HostPattern,
+    port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    Exact(String),
+    /// The domain under the `*.`
+    Subdomains(String),
+}
+
+impl OriginPattern {
+    /// Whether the `Origin` header `origin` is allowed
+    pub fn matches(&self, origin: &str) -> bool {
+        let Ok((scheme, host, port)) = split_origin(origin) else {
+            return false;
+        };
+        if scheme != self.scheme || port != self.port || host.contains('*') {
+            return false;
+        }
+        match &self.host {
+            HostPattern::Exact(exact) => host == *exact,
+            HostPattern::Subdomains(domain) => host
+                .strip_suffix(domain.as_str())
+                .and_then(|subdomain| subdomain.strip_suffix('.'))
+                .is_some_and(|subdomain| !subdomain.is_empty()),
+        }
+    }
+}
+
+impl FromStr for OriginPattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (scheme, host, port) = split_origin(value)?;
+        let host = match host.strip_prefix("*.") {
+            Some(domain) => HostPattern::Subdomains(domain.to_string()),
+            None => HostPattern::Exact(host),
+        };
+        match &host {
+            HostPattern::Exact(host) | HostPattern::Subdomains(host)
+                if host.is_empty() || host.contains('*') =>
+            {
+                Err(format!(
+                    "{}: only a leading *. is allowed, e.g. https://*.example.com",
+                    value
+                ))
+            }
+            _ => Ok(Self { scheme, host, port }),
+        }
+    }
+}
+
+impl fmt::Display for OriginPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://", self.scheme)?;
+        match &self.host {
+            HostPattern::Exact(host) => write!(f, "{}", host)?,
+            HostPattern::Subdomains(domain) => write!(f, "*.{}", domain)?,
+        }
+        if self.port != default_port(&self.scheme) {
+            write!(f, ":{}", self.port)?;
+        }
+        Ok(())
+    }
+}
+
+/// The lower cased scheme and host and the port of `scheme://host[:port]`
+fn split_origin(value: &str) -> Result<(String, String, u16), String> {
+    let (scheme, authority) = value
+        .trim()
+        .split_once("://")
+        .ok_or_else(|| format!("{}: an origin looks like https://example.com", value))?;
+    let scheme = scheme.to_ascii_lowercase();
+    if scheme != "http" && scheme != "https" {
+        return Err(format!("{}: the scheme must be http or https", value));
+    }
+    let authority = authority.strip_suffix('/').unwrap_or(authority);
+    if authority.contains(['/', '?', '#', '@']) {
+        return Err(format!("{}: an origin has no path, query or user", value));
+    }
+    // The port is after the last `:`, unless it is inside an IPv6 address
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => {
+            let port = port
+                .parse()
+                .map_err(|_| format!("{}: invalid port {}", value, port))?;
+            (host, port)
+        }
+        _ => (authority, default_port(&scheme)),
+    };
+    Ok((scheme, host.to_ascii_lowercase(), port))
+}
+
+fn default_port(scheme: &str) -> u16 {
+    match scheme {
+        "http" => 80,
+        _ => 443,
+    }
+}
+
+/// # The CORS rules of the API
+/// Only the `cors_origins` of the config may call it from a browser, with their cookies.
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    let origins = config.cors_origins.clone();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(
+            move |origin: &HeaderValue, _| match origin.to_str() {
+                Ok(origin) => origins.iter().any(|pattern| pattern.matches(origin)),
+                Err(_) => false,
+            },
+        ))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers([ORIGIN, CONTENT_TYPE, ACCEPT, AUTHORIZATION])
+        .allow_credentials(true)
+        .expose_headers([SET_COOKIE])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(value: &str) -> OriginPattern {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn exact_origins_match_themselves() {
+        let pattern = pattern("https://App.Example.com/");
+        assert!(pattern.matches("https://app.example.com"));
+        assert!(pattern.matches("https://app.example.com:443"));
+        assert!(!pattern.matches("http://app.example.com"));
+        assert!(!pattern.matches("https://app.example.com:8443"));
+        assert!(!pattern.matches("https://shop.app.example.com"));
+        assert!(!pattern.matches("null"));
+    }
+
+    #[test]
+    fn wildcards_match_subdomains_only() {
+        let pattern = pattern("https://*.example.com");
+        assert!(pattern.matches("https://shop.example.com"));
+        assert!(pattern.matches("https://a.b.example.com"));
+        assert!(!pattern.matches("https://example.com"));
+        assert!(!pattern.matches("https://.example.com"));
+        assert!(!pattern.matches("https://evilexample.com"));
+        assert!(!pattern.matches("https://example.com.evil.com"));
+        assert!(!pattern.matches("https://*.example.com"));
+    }
+
+    #[test]
+    fn ports_and_ipv6_hosts() {
+        let localhost = pattern("http://localhost:8080");
+        assert!(localhost.matches("http://localhost:8080"));
+        assert!(!localhost.matches("http://localhost"));
+        assert!(pattern("http://[::1]").matches("http://[::1]:80"));
+        assert!(pattern("http://[::1]:9000").matches("http://[::1]:9000"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        for value in [
+            "example.com",
+            "ftp://example.com",
+            "https://example.com/path",
+            "https://user@example.com",
+            "https://example.com:http",
+            "https://shop.*.example.com",
+            "https://*.",
+            "https://",
+        ] {
+            assert!(value.parse::<OriginPattern>().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn patterns_display_without_default_port() {
+        assert_eq!(
+            pattern("HTTPS://*.Example.com:443").to_string(),
+            "https://*.example.com"
+        );
+        assert_eq!(
+            pattern("http://localhost:8080").to_string(),
+            "http://localhost:8080"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod nav_item_db;
+mod nav_item_routes;
+mod nav_menu;
+pub use nav_item_routes::nav_item_routes;
+pub use nav_menu::visible_nav_items;
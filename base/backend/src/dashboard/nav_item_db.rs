@@ -0,0 +1,258 @@
+use crate::audit::{add_audit_entry, AuditActor, AuditRecord};
+use crate::error::Result;
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use serde_json::Value;
+use shared::dashboard::{DashNavItem, DashNavItemName, DashNavItemPayload};
+use sqlx::{query, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Every nav item with its labels, ordered by position
+#[catch_error]
+pub async fn list_nav_items(pool: &PgPool) -> Result<Vec<DashNavItem>> {
+    let names = query!(
+        r#"
+        SELECT nav_item_id, language_id, name
+        FROM dash_nav_items_names
+        ORDER BY language_id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let items = query!(
+        r#"
+        SELECT id, parent_id, name, icon, route, required_permission, required_action, position
+        FROM dash_nav_items
+        ORDER BY position, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| DashNavItem {
+        id: row.id,
+        parent_id: row.parent_id,
+        name: row.name,
+        icon: row.icon,
+        route: row.route,
+        required_permission: row.required_permission,
+        required_action: row.required_action,
+        position: row.position,
+        names: names
+            .iter()
+            .filter(|name| name.nav_item_id == row.id)
+            .map(|name| DashNavItemName {
+                language_id: name.language_id,
+                name: name.name.clone(),
+            })
+            .collect(),
+    })
+    .collect();
+    Ok(items)
+}
+
+/// # The language of the labels
+/// The requested language code first, then the preferred language of the user.
+#[catch_error]
+pub async fn nav_language(pool: &PgPool, user_id: Uuid, code: Option<&str>) -> Result<Option<i32>> {
+    let row = query!(
+        r#"
+        SELECT COALESCE(
+            (SELECT id FROM languages WHERE code = $1),
+            (SELECT preferred_language_id FROM users WHERE id = $2)
+        ) as language_id
+        "#,
+        code,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.language_id)
+}
+
+#[catch_error]
+pub async fn add_nav_item(
+    pool: &PgPool,
+    actor: &AuditActor,
+    payload: &DashNavItemPayload,
+) -> Result<i32> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let row = query!(
+        r#"
+        INSERT INTO dash_nav_items (parent_id, name, icon, route, required_permission, required_action, position)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+        payload.parent_id,
+        payload.name,
+        payload.icon,
+        payload.route,
+        payload.required_permission,
+        payload.required_action,
+        payload.position
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    set_names(&mut tx, row.id, &payload.names).await?;
+    let after = nav_item_snapshot(&mut tx, row.id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("create", "dash_nav_item", row.id).after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(row.id)
+}
+
+/// An item can't be moved under itself or one of its children
+#[catch_error]
+pub async fn update_nav_item(
+    pool: &PgPool,
+    actor: &AuditActor,
+    id: i32,
+    payload: &DashNavItemPayload,
+) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = nav_item_snapshot(&mut tx, id).await?;
+    if let Some(parent_id) = payload.parent_id {
+        let cycle = query!(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT id, parent_id FROM dash_nav_items WHERE id = $1
+                UNION
+                SELECT d.id, d.parent_id FROM dash_nav_items d JOIN ancestors a ON d.id = a.parent_id
+            )
+            SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2) as "cycle!"
+            "#,
+            parent_id,
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if cycle.cycle {
+            return Err(SamError::InvalidNavItem(
+                "an item can't be nested under itself".to_string(),
+            ));
+        }
+    }
+
+    let result = query!(
+        r#"
+        UPDATE dash_nav_items
+        SET parent_id = $1, name = $2, icon = $3, route = $4, required_permission = $5,
+            required_action = $6, position = $7, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $8
+        "#,
+        payload.parent_id,
+        payload.name,
+        payload.icon,
+        payload.route,
+        payload.required_permission,
+        payload.required_action,
+        payload.position,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Nav item".to_string()));
+    }
+    set_names(&mut tx, id, &payload.names).await?;
+    let after = nav_item_snapshot(&mut tx, id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("update", "dash_nav_item", id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The children are deleted with their parent
+#[catch_error]
+pub async fn delete_nav_item(pool: &PgPool, actor: &AuditActor, id: i32) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = nav_item_snapshot(&mut tx, id).await?;
+    let result = query!(
+        r#"
+        DELETE FROM dash_nav_items
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Nav item".to_string()));
+    }
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("delete", "dash_nav_item", id).before(before),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The item with its labels by language id
+async fn nav_item_snapshot(
+    tx: &mut Transaction<'static, Postgres>,
+    id: i32,
+) -> Result<Option<Value>> {
+    let row = query!(
+        r#"
+        SELECT to_jsonb(d) || jsonb_build_object(
+            'names',
+            (
+                SELECT COALESCE(jsonb_object_agg(n.language_id, n.name), '{}')
+                FROM dash_nav_items_names n
+                WHERE n.nav_item_id = d.id
+            )
+        ) as "item!"
+        FROM dash_nav_items d
+        WHERE d.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row.map(|row| row.item))
+}
+
+/// Replaces the labels of an item
+async fn set_names(
+    tx: &mut Transaction<'static, Postgres>,
+    nav_item_id: i32,
+    names: &[DashNavItemName],
+) -> Result<()> {
+    query!(
+        r#"
+        DELETE FROM dash_nav_items_names
+        WHERE nav_item_id = $1
+        "#,
+        nav_item_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    for name in names.iter().filter(|name| !name.name.trim().is_empty()) {
+        query!(
+            r#"
+            INSERT INTO dash_nav_items_names (name, language_id, nav_item_id)
+            VALUES ($1, $2, $3)
+            "#,
+            name.name,
+            name.language_id,
+            nav_item_id
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
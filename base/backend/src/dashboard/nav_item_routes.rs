@@ -0,0 +1,90 @@
+// Routes for editing the dashboard menu
+// GET	/nav-items	List every nav item with its labels
+// POST	/nav-items	Create a nav item
+// PUT	/nav-items/{id}	Update a nav item
+// DELETE	/nav-items/{id}	Delete a nav item and its children
+//
+// The menu of the logged in user is served by GET /users/dashboard/nav-items
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use sam_error::SamError;
+use serde_json::json;
+use shared::dashboard::DashNavItemPayload;
+
+use super::nav_item_db::{add_nav_item, delete_nav_item, list_nav_items, update_nav_item};
+use crate::{
+    abac::{authorize, Authorize},
+    audit::AuditActor,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn nav_item_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/nav-items",
+            get(list_nav_items_handler).post(add_nav_item_handler),
+        )
+        .route(
+            "/nav-items/{id}",
+            put(update_nav_item_handler).delete(delete_nav_item_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "dash_nav_item", "manage"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
+
+async fn list_nav_items_handler(State(state): State<AppState>) -> Result<Response> {
+    let items = list_nav_items(&state.pool).await?;
+    let res = UserResponse::with_json(items).into_response();
+    Ok(res)
+}
+
+async fn add_nav_item_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    payload: Result<Json<DashNavItemPayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    payload.validate().map_err(SamError::InvalidNavItem)?;
+    let id = add_nav_item(&state.pool, &actor, &payload).await?;
+    let res = UserResponse::with_json_and_code(json!({ "id": id }), 201).into_response();
+    Ok(res)
+}
+
+async fn update_nav_item_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+    payload: Result<Json<DashNavItemPayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    payload.validate().map_err(SamError::InvalidNavItem)?;
+    update_nav_item(&state.pool, &actor, id, &payload).await?;
+    let res = UserResponse::with_success("Nav item updated").into_response();
+    Ok(res)
+}
+
+async fn delete_nav_item_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    delete_nav_item(&state.pool, &actor, id).await?;
+    let res = UserResponse::with_success("Nav item deleted").into_response();
+    Ok(res)
+}
@@ -0,0 +1,64 @@
+use shared::{
+    dashboard::{DashNavItem, DashNavItemInfo},
+    Resource,
+};
+
+use crate::abac::Authorizer;
+
+/// # The menu of a user
+/// Keeps the items the user passes both checks of, nested under their parent.
+/// A hidden parent hides its children.
+pub fn visible_nav_items(
+    items: &[DashNavItem],
+    language_id: Option<i32>,
+    authorizer: &Authorizer,
+) -> Vec<DashNavItemInfo> {
+    children(items, None, language_id, authorizer)
+}
+
+fn children(
+    items: &[DashNavItem],
+    parent_id: Option<i32>,
+    language_id: Option<i32>,
+    authorizer: &Authorizer,
+) -> Vec<DashNavItemInfo> {
+    items
+        .iter()
+        .filter(|item| item.parent_id == parent_id && is_visible(item, authorizer))
+        .map(|item| DashNavItemInfo {
+            name: label(item, language_id),
+            icon: item.icon.clone(),
+            route: item.route.clone(),
+            sub_items: children(items, Some(item.id), language_id, authorizer),
+        })
+        .collect()
+}
+
+fn is_visible(item: &DashNavItem, authorizer: &Authorizer) -> bool {
+    let has_permission = item
+        .required_permission
+        .as_deref()
+        .is_none_or(|permission| {
+            split(permission).is_some_and(|(resource_type, action)| {
+                authorizer.user().has_permission(resource_type, action)
+            })
+        });
+    // The policies decide like for the page itself
+    let is_allowed = item.required_action.as_deref().is_none_or(|action| {
+        split(action).is_some_and(|(resource_type, action)| {
+            authorizer.is_allowed(&Resource::new(resource_type, ""), action)
+        })
+    });
+    has_permission && is_allowed
+}
+
+fn split(value: &str) -> Option<(&str, &str)> {
+    value.split_once(':')
+}
+
+fn label(item: &DashNavItem, language_id: Option<i32>) -> String {
+    item.names
+        .iter()
+        .find(|name| Some(name.language_id) == language_id)
+        .map_or_else(|| item.name.clone(), |name| name.name.clone())
+}
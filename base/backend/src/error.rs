@@ -1,4 +1,4 @@
-use sam_error::SamError;
+use sam_error::{report_error, ErrorDetails, ErrorReport, SamError};
 pub type Result<T, E = SamError> = std::result::Result<T, E>;
 
 use sqlx::{Error as SqlxError, PgPool};
@@ -14,7 +14,10 @@ use axum::{
 };
 // use thiserror::Error;
 
+use shared::error_code;
+
 use crate::{
+    error_log::RequestContext,
     response::{IntoUserResponse, UserResponse},
     AppState,
 };
@@ -86,11 +89,25 @@ type BoxError = Box<dyn Any + Send + 'static>;
 //     }
 // }
 
+/// # Every error answers with a `UserResponse`
+/// With the code of the error and the id of the request (added by `IntoUserResponse`).
+/// A `SamError` answering with a 5xx is reported, like the errors of `any_with_log!`.
 pub async fn error_middleware(request: Request, next: Next) -> Response {
     let response = next.run(request).await;
     let status_code = response.status().as_u16();
     // Check if the response is an error
     if response.status().is_client_error() || response.status().is_server_error() {
+        // A SamError says what it is, no need to read the body
+        if let Some(details) = response.extensions().get::<ErrorDetails>().cloned() {
+            if response.status().is_server_error() {
+                report_server_error(&details);
+            }
+            return UserResponse::with_error_and_code(details.message, status_code)
+                .code(details.code)
+                .field_errors(details.field_errors)
+                .into_response();
+        }
+
         // Extract the body from the response
         let (_, body) = response.into_parts();
 
@@ -103,11 +120,14 @@ pub async fn error_middleware(request: Request, next: Next) -> Response {
                     let message = String::from_utf8(body_bytes.to_vec())
                         .unwrap_or_else(|_| "Unknown error".to_string());
 
-                    UserResponse::with_error_and_code(message.as_str(), status_code).into_response()
+                    UserResponse::with_error_and_code(message.as_str(), status_code)
+                        .code(error_code::from_status(status_code))
+                        .into_response()
                 }
             }
         } else {
             UserResponse::with_error_and_code("Failed to read response body", status_code)
+                .code(error_code::from_status(status_code))
                 .into_response()
         }
     } else {
@@ -115,6 +135,189 @@ pub async fn error_middleware(request: Request, next: Next) -> Response {
     }
 }
 
-pub async fn handle_error(error: Box<dyn Any + Send>, pool: &PgPool) -> Response {
-    Response::new("hello samoo...".into())
+/// # Report a 5xx `SamError`, e.g. a database error returned with `?`
+/// The handler is unknown here so the error is grouped by its code and route.
+/// `SamError::Any` is skipped, `any_with_log!` reported it where it happened.
+fn report_server_error(details: &ErrorDetails) {
+    if details.code == SamError::Any.code() {
+        return;
+    }
+    let route = RequestContext::current()
+        .map(|context| format!("{} {}", context.method, context.route))
+        .unwrap_or_else(|| "unknown".to_string());
+    error!("{} on {}: {}", details.code, route, details.message);
+    report_error(ErrorReport {
+        module: details.code.to_string(),
+        file: route,
+        line: 0,
+        message: details.message.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::{
+        body::Body,
+        extract::FromRequest,
+        http::{header::CONTENT_TYPE, Request},
+        middleware,
+        routing::get,
+        Json, Router,
+    };
+    use sam_error::{set_error_reporter, FieldErrors};
+    use tower::ServiceExt;
+
+    use crate::error_log::request_context_middleware;
+
+    use super::*;
+
+    async fn json_rejection() -> JsonRejection {
+        let request = Request::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from("{"))
+            .unwrap();
+        Json::<serde_json::Value>::from_request(request, &())
+            .await
+            .unwrap_err()
+    }
+
+    /// Stops compiling when a variant is added, so it gets added to the test below too
+    fn is_listed(error: &SamError) -> bool {
+        match error {
+            SamError::Database(_)
+            | SamError::InvalidJson(_)
+            | SamError::LoginFailed
+            | SamError::RegistrationFailed
+            | SamError::NotAuthorized
+            | SamError::InvalidToken
+            | SamError::ExpiredToken(_)
+            | SamError::MissingEnviromentVariable(_)
+            | SamError::InvalidConfig(_)
+            | SamError::EmailSendFailed
+            | SamError::EmailNotFound
+            | SamError::InvalidEmail
+            | SamError::InvalidPassword(_)
+            | SamError::Forbidden(_)
+            | SamError::PolicyDenied { .. }
+            | SamError::NotFound(_)
+            | SamError::EmailTaken
+            | SamError::InvalidUpload(_)
+            | SamError::InvalidPolicy(_)
+            | SamError::InvalidAttributes(_)
+            | SamError::InvalidRole(_)
+            | SamError::InvalidNavItem(_)
+            | SamError::Validation(_)
+            | SamError::AccountDisabled
+            | SamError::PasswordResetRequired
+            | SamError::Any
+            | SamError::Err(_) => true,
+        }
+    }
+
+    #[tokio::test]
+    async fn every_error_code_is_in_the_shared_list() {
+        let text = String::new;
+        let errors = [
+            SamError::Database(text()),
+            SamError::InvalidJson(json_rejection().await),
+            SamError::LoginFailed,
+            SamError::RegistrationFailed,
+            SamError::NotAuthorized,
+            SamError::InvalidToken,
+            SamError::ExpiredToken(text()),
+            SamError::MissingEnviromentVariable(text()),
+            SamError::InvalidConfig(text()),
+            SamError::EmailSendFailed,
+            SamError::EmailNotFound,
+            SamError::InvalidEmail,
+            SamError::InvalidPassword(text()),
+            SamError::Forbidden(text()),
+            SamError::PolicyDenied {
+                reason: text(),
+                body: None,
+            },
+            SamError::NotFound(text()),
+            SamError::EmailTaken,
+            SamError::InvalidUpload(text()),
+            SamError::InvalidPolicy(text()),
+            SamError::InvalidAttributes(text()),
+            SamError::InvalidRole(text()),
+            SamError::InvalidNavItem(text()),
+            SamError::Validation(FieldErrors::new()),
+            SamError::AccountDisabled,
+            SamError::PasswordResetRequired,
+            SamError::Any,
+            SamError::Err(text()),
+        ];
+        for error in &errors {
+            assert!(is_listed(error));
+            assert!(
+                error_code::ALL.contains(&error.code()),
+                "{} of {:?} is missing in shared::error_code",
+                error.code(),
+                error
+            );
+        }
+        // One error per code, so every variant is above
+        let mut codes: Vec<_> = errors.iter().map(SamError::code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn codes_of_the_statuses_are_listed() {
+        for status in [400, 401, 403, 404, 405, 409, 413, 415, 422, 429, 500, 503] {
+            assert!(error_code::ALL.contains(&error_code::from_status(status)));
+        }
+    }
+
+    static REPORTS: Mutex<Vec<ErrorReport>> = Mutex::new(Vec::new());
+
+    async fn reported_on(path: &str) -> Vec<ErrorReport> {
+        set_error_reporter(|report| REPORTS.lock().unwrap().push(report));
+        let app = Router::new()
+            .route(
+                "/report/database",
+                get(|| async { Err::<(), _>(SamError::Database("connection lost".to_string())) }),
+            )
+            .route("/report/any", get(|| async { Err::<(), _>(SamError::Any) }))
+            .route(
+                "/report/missing",
+                get(|| async { Err::<(), _>(SamError::NotFound("Role".to_string())) }),
+            )
+            .layer(middleware::from_fn(error_middleware))
+            .layer(middleware::from_fn(request_context_middleware));
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap();
+
+        let route = format!("GET {}", path);
+        REPORTS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|report| report.file == route)
+            .cloned()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_reported_once() {
+        let reports = reported_on("/report/database").await;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].module, "database_error");
+        assert!(reports[0].message.contains("connection lost"));
+    }
+
+    #[tokio::test]
+    async fn any_is_left_to_any_with_log() {
+        assert!(reported_on("/report/any").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_reported() {
+        assert!(reported_on("/report/missing").await.is_empty());
+    }
 }
@@ -0,0 +1,65 @@
+use std::sync::{Arc, OnceLock};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: Arc<RequestContext>;
+}
+
+/// # The request an error happened in
+/// Set for the whole request by `request_context_middleware`,
+/// the id is sent back in the `x-request-id` header.
+#[derive(Debug)]
+pub struct RequestContext {
+    pub request_id: Uuid,
+    pub method: String,
+    /// The matched route, e.g. `/roles/{id}`
+    pub route: String,
+    user_id: OnceLock<Uuid>,
+}
+
+impl RequestContext {
+    /// The context of the request the current task serves, if any
+    pub fn current() -> Option<Arc<Self>> {
+        REQUEST_CONTEXT.try_with(Arc::clone).ok()
+    }
+
+    /// The logged in user, once the session has been checked
+    pub fn user_id(&self) -> Option<Uuid> {
+        self.user_id.get().copied()
+    }
+}
+
+/// Remembers the logged in user for the errors of the current request
+pub fn set_request_user(user_id: Uuid) {
+    if let Some(context) = RequestContext::current() {
+        let _ = context.user_id.set(user_id);
+    }
+}
+
+pub async fn request_context_middleware(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let context = Arc::new(RequestContext {
+        request_id: Uuid::new_v4(),
+        method: req.method().to_string(),
+        route,
+        user_id: OnceLock::new(),
+    });
+    let request_id = context.request_id;
+
+    let mut res = REQUEST_CONTEXT.scope(context, next.run(req)).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id.to_string()) {
+        res.headers_mut().insert("x-request-id", value);
+    }
+    res
+}
@@ -0,0 +1,245 @@
+use crate::audit::{add_audit_entry, snapshot, AuditActor, AuditRecord};
+use crate::error::Result;
+use crate::list_query::{fetch_page, ListColumns};
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use sha2::{Digest, Sha256};
+use shared::{
+    error_log::{ErrorGroup, ErrorOccurrence, ErrorStatus},
+    list::{ListQuery, Page},
+};
+use sqlx::{query, query_as, PgPool, Postgres, Transaction};
+
+use super::reporter::ErrorRecord;
+
+/// # The group of an error
+/// The place plus the message with its variable parts masked,
+/// so `User 12 not found` and `User 13 not found` are counted together.
+fn fingerprint(record: &ErrorRecord) -> String {
+    let message = mask_message(&record.report.message);
+    let key = format!(
+        "{}|{}|{}|{}",
+        record.report.module, record.report.file, record.report.line, message
+    );
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Numbers, UUIDs and hex runs like hashes become `#`
+fn mask_message(message: &str) -> String {
+    let mut masked = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(c) = rest.chars().next() {
+        let len = if is_uuid(rest) {
+            masked.push('#');
+            36
+        } else if c.is_ascii_alphanumeric() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            mask_word(&rest[..len], &mut masked);
+            len
+        } else {
+            masked.push(c);
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    masked
+}
+
+fn is_uuid(text: &str) -> bool {
+    text.get(..36)
+        .is_some_and(|uuid| uuid::Uuid::try_parse(uuid).is_ok())
+}
+
+/// A hex run of 8 or more with a digit is an id or a hash, other words keep all but their numbers
+fn mask_word(word: &str, masked: &mut String) {
+    if word.len() >= 8
+        && word.chars().all(|c| c.is_ascii_hexdigit())
+        && word.chars().any(|c| c.is_ascii_digit())
+    {
+        masked.push('#');
+        return;
+    }
+    let mut in_number = false;
+    for c in word.chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                masked.push('#');
+            }
+            in_number = true;
+        } else {
+            masked.push(c);
+            in_number = false;
+        }
+    }
+}
+
+/// # Count an occurrence in its group
+/// A resolved group is opened again, an ignored one stays ignored.
+pub async fn add_error(pool: &PgPool, record: &ErrorRecord) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let error_id = query!(
+        r#"
+        INSERT INTO errors (fingerprint, module, file, line, message)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (fingerprint) DO UPDATE SET
+            occurrences = errors.occurrences + 1,
+            message = EXCLUDED.message,
+            last_seen_at = CURRENT_TIMESTAMP,
+            status = CASE WHEN errors.status = 'resolved' THEN 'open' ELSE errors.status END,
+            resolved_at = CASE WHEN errors.status = 'resolved' THEN NULL ELSE errors.resolved_at END,
+            resolved_by = CASE WHEN errors.status = 'resolved' THEN NULL ELSE errors.resolved_by END
+        RETURNING id
+        "#,
+        fingerprint(record),
+        record.report.module,
+        record.report.file,
+        record.report.line as i32,
+        record.report.message
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .id;
+
+    query!(
+        r#"
+        INSERT INTO error_occurrences (error_id, request_id, user_id, method, route, message)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        error_id,
+        record.request_id,
+        record.user_id,
+        record.method,
+        record.route,
+        record.report.message
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// # Remove the occurrences older than `days`
+/// The groups keep their counts, like `add_error` errors here are only logged by the caller.
+pub async fn delete_old_occurrences(pool: &PgPool, days: i32) -> Result<u64> {
+    let deleted = query!(
+        r#"
+        DELETE FROM error_occurrences
+        WHERE created_at < CURRENT_TIMESTAMP - make_interval(days => $1)
+        "#,
+        days
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(deleted)
+}
+
+/// The error group fields a list can be sorted and filtered on
+pub fn error_columns() -> ListColumns {
+    ListColumns::new("id", "id", "integer")
+        .column("last_seen_at", "last_seen_at", "timestamptz")
+        .column("created_at", "created_at", "timestamptz")
+        .column("occurrences", "occurrences", "integer")
+        .column("status", "status", "error_status")
+        .filter_column("module", "module", "text")
+        .filter_column("message", "message", "text")
+}
+
+pub async fn list_errors(pool: &PgPool, list: ListQuery) -> Result<Page<ErrorGroup>> {
+    fetch_page(pool, "errors", &error_columns(), &list, None).await
+}
+
+/// The latest occurrences of a group, newest first
+#[catch_error]
+pub async fn list_occurrences(pool: &PgPool, error_id: i32) -> Result<Vec<ErrorOccurrence>> {
+    let occurrences = query_as!(
+        ErrorOccurrence,
+        r#"
+        SELECT id, request_id, user_id, method, route, message, created_at
+        FROM error_occurrences
+        WHERE error_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT 50
+        "#,
+        error_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(occurrences)
+}
+
+#[catch_error]
+pub async fn set_error_status(
+    pool: &PgPool,
+    actor: &AuditActor,
+    id: i32,
+    status: ErrorStatus,
+) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "errors", &id.to_string()).await?;
+    let result = query!(
+        r#"
+        UPDATE errors SET
+            status = $2,
+            resolved_at = CASE WHEN $2 = 'resolved'::error_status THEN CURRENT_TIMESTAMP END,
+            resolved_by = CASE WHEN $2 = 'resolved'::error_status THEN $3::uuid END
+        WHERE id = $1
+        "#,
+        id,
+        status as ErrorStatus,
+        actor.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Error".to_string()));
+    }
+    let after = snapshot(&mut tx, "errors", &id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("set_status", "error", id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mask_message;
+
+    #[test]
+    fn numbers_are_masked() {
+        assert_eq!(mask_message("User 12 not found"), "User # not found");
+        assert_eq!(mask_message("page2 of 10"), "page# of #");
+    }
+
+    #[test]
+    fn uuids_and_hashes_are_masked() {
+        assert_eq!(
+            mask_message("No user 550e8400-e29b-41d4-a716-446655440000."),
+            "No user #."
+        );
+        assert_eq!(
+            mask_message("File 9f86d081884c7d65 is missing"),
+            "File # is missing"
+        );
+    }
+
+    #[test]
+    fn words_stay() {
+        assert_eq!(
+            mask_message("deadbeefcafe: ünïcode"),
+            "deadbeefcafe: ünïcode"
+        );
+        assert_eq!(mask_message("sha256 mismatch"), "sha# mismatch");
+    }
+}
@@ -0,0 +1,60 @@
+// Routes for the errors and panics of the backend
+// GET	/errors	Page through the error groups, a ListQuery, e.g. ?status=open&sort=-last_seen_at
+// GET	/errors/{id}/occurrences	The latest occurrences of a group
+// PUT	/errors/{id}/status	Resolve, ignore or open a group again
+
+use axum::{extract::State, middleware, response::Response, Router};
+use shared::error_log::{ErrorOccurrences, ListErrors, SetErrorStatus};
+
+use super::error_log_db::{list_errors, list_occurrences, set_error_status};
+use crate::{
+    abac::{authorize, Authorize},
+    api::{reply, EndpointRouter, Params, Payload},
+    audit::AuditActor,
+    error::Result,
+    list_query::ListParams,
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn error_log_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .endpoint(ListErrors, list_errors_handler)
+        .endpoint(ErrorOccurrences, list_occurrences_handler)
+        .endpoint(SetErrorStatus, set_status_handler)
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "error", "manage"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
+
+async fn list_errors_handler(
+    State(state): State<AppState>,
+    ListParams(list): ListParams,
+) -> Result<Response> {
+    let errors = list_errors(&state.pool, list).await?;
+    reply::<ListErrors>(errors)
+}
+
+async fn list_occurrences_handler(
+    State(state): State<AppState>,
+    Params(id): Params<ErrorOccurrences>,
+) -> Result<Response> {
+    let occurrences = list_occurrences(&state.pool, id).await?;
+    reply::<ErrorOccurrences>(occurrences)
+}
+
+async fn set_status_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Params(id): Params<SetErrorStatus>,
+    Payload(payload): Payload<SetErrorStatus>,
+) -> Result<Response> {
+    set_error_status(&state.pool, &actor, id, payload.status).await?;
+    reply::<SetErrorStatus>(format!("Error marked as {}", payload.status.as_str()))
+}
@@ -0,0 +1,7 @@
+mod context;
+pub mod error_log_db;
+mod error_log_routes;
+mod reporter;
+pub use context::{request_context_middleware, set_request_user, RequestContext};
+pub use error_log_routes::error_log_routes;
+pub use reporter::{handle_panic, prune_error_log, start_error_log};
@@ -0,0 +1,105 @@
+use std::{any::Any, cell::RefCell, sync::Arc};
+
+use axum::response::{IntoResponse, Response};
+use sam_error::{report_error, set_error_reporter, ErrorReport};
+use sqlx::PgPool;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use uuid::Uuid;
+
+use super::{
+    error_log_db::{add_error, delete_old_occurrences},
+    RequestContext,
+};
+use crate::response::{IntoUserResponse, UserResponse};
+
+/// An error with the request it happened in, waiting to be written
+#[derive(Debug)]
+pub struct ErrorRecord {
+    pub report: ErrorReport,
+    pub request_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub method: Option<String>,
+    pub route: Option<String>,
+}
+
+thread_local! {
+    /// Where the last panic of this thread happened, the panic payload only has the message
+    static PANIC_LOCATION: RefCell<Option<(String, u32)>> = const { RefCell::new(None) };
+}
+
+/// # Write the errors of `any_with_log!` and the panics to the `errors` table
+/// Errors are queued and written by a background task so the request never waits for them.
+/// Meant to be called once at startup.
+pub fn start_error_log(pool: Arc<PgPool>) {
+    let (sender, receiver) = unbounded_channel::<ErrorRecord>();
+    set_error_reporter(move |report| {
+        let context = RequestContext::current();
+        let _ = sender.send(ErrorRecord {
+            report,
+            request_id: context.as_ref().map(|context| context.request_id),
+            user_id: context.as_ref().and_then(|context| context.user_id()),
+            method: context.as_ref().map(|context| context.method.clone()),
+            route: context.as_ref().map(|context| context.route.clone()),
+        });
+    });
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(location) = info.location() {
+            let location = (location.file().to_string(), location.line());
+            PANIC_LOCATION.with(|last| *last.borrow_mut() = Some(location));
+        }
+        default_hook(info);
+    }));
+
+    tokio::spawn(write_errors(pool, receiver));
+}
+
+async fn write_errors(pool: Arc<PgPool>, mut receiver: UnboundedReceiver<ErrorRecord>) {
+    while let Some(record) = receiver.recv().await {
+        // Logged only, reporting it would queue it again
+        if let Err(err) = add_error(&pool, &record).await {
+            tracing::error!("Failed to write error: {}", err);
+        }
+    }
+}
+
+/// # Remove the occurrences older than `retention_days`
+/// Runs forever, meant to be spawned once at startup. 0 keeps them forever.
+pub async fn prune_error_log(pool: Arc<PgPool>, retention_days: u32) {
+    if retention_days == 0 {
+        return;
+    }
+    let days = i32::try_from(retention_days).unwrap_or(i32::MAX);
+    loop {
+        match delete_old_occurrences(&pool, days).await {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!("Removed {} old error occurrences", deleted),
+            Err(err) => tracing::error!("Failed to remove old error occurrences: {}", err),
+        }
+
+        // Run every hour
+        tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+    }
+}
+
+/// # The response of a panicking handler, for `CatchPanicLayer`
+/// The panic is reported like the errors of `any_with_log!`.
+pub fn handle_panic(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Panic".to_string());
+    let (file, line) = PANIC_LOCATION
+        .with(|last| last.borrow_mut().take())
+        .unwrap_or_else(|| ("unknown".to_string(), 0));
+
+    report_error(ErrorReport {
+        module: "panic".to_string(),
+        file,
+        line,
+        message,
+    });
+    UserResponse::with_error_and_code("Something went wrong", 500).into_response()
+}
@@ -1,19 +1,24 @@
+use crate::audit::{add_audit_entry, snapshot, AuditActor, AuditRecord};
 use crate::error::Result;
+use crate::list_query::{fetch_page, ListColumns};
 use sam_proc_macros::catch_error;
+use shared::list::{ListQuery, Page};
 use shared::Language;
-use sqlx::{query, query_as, PgPool};
+use sqlx::{query_as, PgPool, Postgres, Transaction};
 
-#[catch_error]
-pub async fn list_languages(pool: &PgPool) -> Result<Vec<Language>> {
-    let languages: Vec<Language> = query_as!(
-        Language,
-        r#"
-        SELECT * FROM languages 
-        "#
-    )
-    .fetch_all(pool)
-    .await?;
-    Ok(languages)
+// The CRUD queries are derived on `Language` (see `sam_proc_macros::Repository`)
+
+/// The language fields a list can be sorted and filtered on
+pub fn language_columns() -> ListColumns {
+    ListColumns::new("id", "id", "integer")
+        .column("code", "code", "text")
+        .column("name", "name", "text")
+        .column("flag", "flag", "text")
+        .column("active", "active", "boolean")
+}
+
+pub async fn list_languages(pool: &PgPool, list: ListQuery) -> Result<Page<Language>> {
+    fetch_page(pool, "languages", &language_columns(), &list, None).await
 }
 
 #[catch_error]
@@ -32,55 +37,50 @@ pub async fn get_language(pool: &PgPool, language_code: String) -> Result<Langua
 }
 
 #[catch_error]
-pub async fn add_language(pool: &PgPool, language: Language) -> Result<()> {
-    query!(
-        r#"
-        INSERT INTO languages (code,name,flag,active)
-        VALUES ($1, $2, $3, $4) 
-        "#,
-        language.code,
-        language.name,
-        language.flag,
-        language.active
+pub async fn add_language(pool: &PgPool, actor: &AuditActor, language: Language) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let row = language.add(&mut *tx).await?;
+    let after = snapshot(&mut tx, "languages", &row.id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("create", "language", row.id).after(after),
     )
-    .execute(pool)
     .await?;
+    tx.commit().await?;
     Ok(())
 }
 
 #[catch_error]
-pub async fn update_language(pool: &PgPool, language: Language) -> Result<()> {
-    query!(
-        r#"
-        UPDATE languages
-        SET
-            code = $1,
-            name = $2,
-            flag = $3,
-            active = $4
-        WHERE id = $5
-        "#,
-        language.code,
-        language.name,
-        language.flag,
-        language.active,
-        language.id
+pub async fn update_language(pool: &PgPool, actor: &AuditActor, language: Language) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let id = language.id.to_string();
+    let before = snapshot(&mut tx, "languages", &id).await?;
+    language.update(&mut *tx).await?;
+    let after = snapshot(&mut tx, "languages", &id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("update", "language", id)
+            .before(before)
+            .after(after),
     )
-    .execute(pool)
     .await?;
+    tx.commit().await?;
     Ok(())
 }
 
 #[catch_error]
-pub async fn delete_language(pool: &PgPool, language_id: i32) -> Result<()> {
-    query!(
-        r#"
-        DELETE FROM languages 
-        WHERE id = $1
-        "#,
-        language_id,
+pub async fn delete_language(pool: &PgPool, actor: &AuditActor, language_id: i32) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = snapshot(&mut tx, "languages", &language_id.to_string()).await?;
+    Language::delete(&mut *tx, language_id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("delete", "language", language_id).before(before),
     )
-    .execute(pool)
     .await?;
+    tx.commit().await?;
     Ok(())
 }
@@ -1,68 +1,36 @@
 use super::language_db::*;
 use crate::error::Result;
-use crate::{response::*, AppState};
-use axum::{
-    extract::{rejection::JsonRejection, Path, Query, State},
-    response::{IntoResponse, Response},
-    routing::{delete, get, post},
-    Json, Router,
-};
-use sam_error::SamError;
-use serde_json::to_value;
-use shared::Language;
+use crate::AppState;
+use sam_error::{FieldErrors, SamError};
+use sam_proc_macros::rest_routes;
+use shared::list::ListQuery;
+use shared::{AddLanguage, DeleteLanguage, GetLanguage, Language, ListLanguages, UpdateLanguage};
 
-pub fn language_routes(state: AppState) -> Router<AppState> {
-    Router::new()
-        .route(
-            "/languages",
-            get(list_languages_handler)
-                .post(add_language_handler)
-                .put(update_language_handler),
-        )
-        .route(
-            "/languages/{id}",
-            delete(delete_language_handler).get(get_language_handler),
-        )
+rest_routes! {
+    pub fn language_routes(AppState) for Language at "/languages" {
+        authorize: "language",
+        validate: validate_language,
+        list(ListQuery): list_languages => ListLanguages,
+        get: get_language => GetLanguage,
+        create: add_language => AddLanguage,
+        update: update_language => UpdateLanguage,
+        delete: delete_language => DeleteLanguage,
+    }
 }
 
-async fn list_languages_handler(State(state): State<AppState>) -> Result<Response> {
-    let languages: Vec<Language> = list_languages(&state.pool).await?;
-    let res = UserResponse::with_json(languages).into_response();
-    Ok(res)
-}
-
-async fn get_language_handler(
-    State(state): State<AppState>,
-    Path(id): Path<String>,
-) -> Result<Response> {
-    let language: Language = get_language(&state.pool, id).await?;
-    let res = UserResponse::with_json(language).into_response();
-    Ok(res)
-}
-
-async fn add_language_handler(
-    State(state): State<AppState>,
-    Json(language): Json<Language>,
-) -> Result<Response> {
-    add_language(&state.pool, language).await?;
-    let res = UserResponse::with_success("Language Added Successfully").into_response();
-    Ok(res)
-}
-
-async fn update_language_handler(
-    State(state): State<AppState>,
-    Json(language): Json<Language>,
-) -> Result<Response> {
-    update_language(&state.pool, language).await?;
-    let res = UserResponse::with_success("Language Added Successfully").into_response();
-    Ok(res)
-}
-
-async fn delete_language_handler(
-    State(state): State<AppState>,
-    Path(id): Path<i32>,
-) -> Result<Response> {
-    delete_language(&state.pool, id).await?;
-    let res = UserResponse::with_success("Language Deleted Successfully").into_response();
-    Ok(res)
+fn validate_language(language: &Language) -> Result<()> {
+    let mut errors = FieldErrors::new();
+    for (field, value) in [("code", &language.code), ("name", &language.name)] {
+        if value.trim().is_empty() {
+            errors
+                .entry(field.to_string())
+                .or_default()
+                .push(format!("The {} is required", field));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SamError::Validation(errors))
+    }
 }
@@ -0,0 +1,461 @@
+use std::str::FromStr;
+
+use axum::{
+    extract::{FromRequestParts, RawQuery},
+    http::request::Parts,
+};
+use sam_error::{FieldErrors, SamError};
+use sam_proc_macros::catch_error;
+use shared::list::{FilterOp, ListQuery, Page, SortDirection};
+use sqlx::{postgres::PgRow, query, FromRow, PgPool, Row};
+
+use crate::{
+    abac::{SqlFilter, SqlParam},
+    error::Result,
+};
+
+/// # The `ListQuery` of the query string
+/// A query that doesn't parse is answered with a validation error on `query`.
+pub struct ListParams<T = ListQuery>(pub T);
+
+impl<T, S> FromRequestParts<S> for ListParams<T>
+where
+    T: FromStr<Err = String>,
+    S: Send + Sync,
+{
+    type Rejection = SamError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let RawQuery(raw) = RawQuery::from_request_parts(parts, state)
+            .await
+            .unwrap_or(RawQuery(None));
+        raw.as_deref()
+            .unwrap_or_default()
+            .parse()
+            .map(Self)
+            .map_err(|message: String| SamError::Validation(field_error("query", &message)))
+    }
+}
+
+/// A field of the list, its SQL expression and the type its values are cast to
+struct ListColumn {
+    field: &'static str,
+    sql: &'static str,
+    ty: &'static str,
+    sortable: bool,
+}
+
+/// # The fields a list can be sorted and filtered on
+/// `key` is the unique column ending every sort, so the order and the cursors are stable.
+/// Sortable columns must not be NULL, a cursor doesn't move past NULLs.
+///
+/// ```ignore
+/// ListColumns::new("id", "id", "integer")
+///     .column("code", "code", "text")
+///     .filter_column("parent_id", "parent_id", "text")
+/// ```
+pub struct ListColumns {
+    columns: Vec<ListColumn>,
+}
+
+impl ListColumns {
+    pub fn new(field: &'static str, sql: &'static str, ty: &'static str) -> Self {
+        Self {
+            columns: vec![ListColumn {
+                field,
+                sql,
+                ty,
+                sortable: true,
+            }],
+        }
+    }
+
+    /// A column to sort and filter on
+    pub fn column(mut self, field: &'static str, sql: &'static str, ty: &'static str) -> Self {
+        self.columns.push(ListColumn {
+            field,
+            sql,
+            ty,
+            sortable: true,
+        });
+        self
+    }
+
+    /// A column to filter on only, e.g. a nullable one
+    pub fn filter_column(
+        mut self,
+        field: &'static str,
+        sql: &'static str,
+        ty: &'static str,
+    ) -> Self {
+        self.columns.push(ListColumn {
+            field,
+            sql,
+            ty,
+            sortable: false,
+        });
+        self
+    }
+
+    fn get(&self, field: &str) -> Option<&ListColumn> {
+        self.columns.iter().find(|column| column.field == field)
+    }
+
+    fn key(&self) -> &ListColumn {
+        &self.columns[0]
+    }
+}
+
+/// The sort keys of a query with the key column last
+fn sort_keys<'a>(
+    list: &ListQuery,
+    columns: &'a ListColumns,
+    errors: &mut FieldErrors,
+) -> Vec<(&'a ListColumn, SortDirection)> {
+    let mut keys = Vec::new();
+    for key in &list.sort {
+        match columns.get(&key.field) {
+            Some(column) if column.sortable => keys.push((column, key.direction)),
+            _ => errors
+                .entry("sort".to_string())
+                .or_default()
+                .push(format!("Can't sort by {}", key.field)),
+        }
+    }
+    if !keys
+        .iter()
+        .any(|(column, _)| column.field == columns.key().field)
+    {
+        keys.push((columns.key(), SortDirection::Asc));
+    }
+    keys
+}
+
+/// Binds `value` after the params already there, returns its placeholder cast to `ty`
+fn push_param(params: &mut Vec<SqlParam>, value: String, ty: &str) -> String {
+    params.push(SqlParam::Text(value));
+    format!("${}::{}", params.len(), ty)
+}
+
+/// Whether Postgres casts `value` to `ty`, types without a check are left to the database
+fn valid_value(value: &str, ty: &str) -> bool {
+    match ty {
+        "smallint" | "int2" => value.parse::<i16>().is_ok(),
+        "integer" | "int" | "int4" => value.parse::<i32>().is_ok(),
+        "bigint" | "int8" => value.parse::<i64>().is_ok(),
+        "numeric" | "decimal" => value.parse::<rust_decimal::Decimal>().is_ok(),
+        "boolean" | "bool" => matches!(
+            value.trim().to_lowercase().as_str(),
+            "true" | "false" | "t" | "f" | "yes" | "no" | "y" | "n" | "on" | "off" | "1" | "0"
+        ),
+        "uuid" => value.parse::<uuid::Uuid>().is_ok(),
+        _ => true,
+    }
+}
+
+/// A value the checks above let through and the cast rejects, e.g. a malformed date
+fn invalid_value(err: sqlx::Error) -> SamError {
+    match &err {
+        sqlx::Error::Database(db)
+            if matches!(
+                db.code().as_deref(),
+                Some("22P02" | "22003" | "22007" | "22008")
+            ) =>
+        {
+            SamError::Validation(field_error("filter", "Invalid value"))
+        }
+        _ => err.into(),
+    }
+}
+
+fn field_error(field: &str, message: &str) -> FieldErrors {
+    FieldErrors::from([(field.to_string(), vec![message.to_string()])])
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// The cursor is the text of the sort keys of the last row, hex encoded JSON
+fn encode_cursor(values: &[String]) -> String {
+    serde_json::to_string(values)
+        .unwrap_or_default()
+        .bytes()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn decode_cursor(cursor: &str) -> Option<Vec<String>> {
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// # A page of `table`
+/// Filters, sorts and pages the rows as `list` asks. `scope` narrows the rows first,
+/// e.g. to the ones the policies allow. Unknown fields are validation errors.
+///
+/// ```ignore
+/// let page: Page<Language> = fetch_page(pool, "languages", &language_columns(), &list, None).await?;
+/// ```
+#[catch_error(skip(columns, scope))]
+pub async fn fetch_page<T>(
+    pool: &PgPool,
+    table: &str,
+    columns: &ListColumns,
+    list: &ListQuery,
+    scope: Option<SqlFilter>,
+) -> Result<Page<T>>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let SqlFilter { clause, mut params } = scope.unwrap_or(SqlFilter {
+        clause: "TRUE".to_string(),
+        params: Vec::new(),
+    });
+    let mut errors = FieldErrors::new();
+    let mut conditions = vec![format!("({})", clause)];
+    for filter in &list.filters {
+        let Some(column) = columns.get(&filter.field) else {
+            errors
+                .entry("filter".to_string())
+                .or_default()
+                .push(format!("Can't filter on {}", filter.field));
+            continue;
+        };
+        let condition = match filter.op {
+            FilterOp::Contains => {
+                let value = push_param(&mut params, escape_like(&filter.value), "text");
+                format!("({})::text ILIKE '%' || {} || '%'", column.sql, value)
+            }
+            _ if !valid_value(&filter.value, column.ty) => {
+                errors
+                    .entry("filter".to_string())
+                    .or_default()
+                    .push(format!("{} is not a valid {}", filter.field, column.ty));
+                continue;
+            }
+            op => {
+                let value = push_param(&mut params, filter.value.clone(), column.ty);
+                let op = match op {
+                    FilterOp::Ne => "<>",
+                    FilterOp::Lt => "<",
+                    FilterOp::Lte => "<=",
+                    FilterOp::Gt => ">",
+                    FilterOp::Gte => ">=",
+                    _ => "=",
+                };
+                format!("{} {} {}", column.sql, op, value)
+            }
+        };
+        conditions.push(condition);
+    }
+    let keys = sort_keys(list, columns, &mut errors);
+    if !errors.is_empty() {
+        return Err(SamError::Validation(errors));
+    }
+
+    // The total counts every page, without the cursor
+    let count_sql = format!(
+        "SELECT COUNT(*) FROM {} WHERE {}",
+        table,
+        conditions.join(" AND ")
+    );
+    let count = SqlFilter {
+        clause: count_sql.clone(),
+        params: params.clone(),
+    };
+    let total: i64 = count
+        .bind(query(&count_sql))
+        .fetch_one(pool)
+        .await
+        .map_err(invalid_value)?
+        .try_get(0)?;
+
+    // The rows after the cursor: the first key greater, or equal and the next one greater, ...
+    if let Some(cursor) = &list.cursor {
+        let values = decode_cursor(cursor)
+            .filter(|values| {
+                values.len() == keys.len()
+                    && keys
+                        .iter()
+                        .zip(values)
+                        .all(|((column, _), value)| valid_value(value, column.ty))
+            })
+            .ok_or_else(|| SamError::Validation(field_error("cursor", "Invalid cursor")))?;
+        let mut after = Vec::new();
+        for i in 0..keys.len() {
+            let mut terms = Vec::new();
+            for (j, ((column, direction), value)) in
+                keys.iter().zip(&values).enumerate().take(i + 1)
+            {
+                let value = push_param(&mut params, value.clone(), column.ty);
+                let op = match (j == i, direction) {
+                    (false, _) => "=",
+                    (true, SortDirection::Asc) => ">",
+                    (true, SortDirection::Desc) => "<",
+                };
+                terms.push(format!("{} {} {}", column.sql, op, value));
+            }
+            after.push(format!("({})", terms.join(" AND ")));
+        }
+        conditions.push(format!("({})", after.join(" OR ")));
+    }
+
+    let order_by = keys
+        .iter()
+        .map(|(column, direction)| match direction {
+            SortDirection::Asc => format!("{} ASC", column.sql),
+            SortDirection::Desc => format!("{} DESC", column.sql),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let cursor_columns = keys
+        .iter()
+        .enumerate()
+        .map(|(i, (column, _))| format!(", ({})::text AS _cursor_{}", column.sql, i))
+        .collect::<String>();
+    let per_page = list.per_page();
+    let offset = if list.cursor.is_some() {
+        0
+    } else {
+        list.offset()
+    };
+    // One more row tells whether there is a next page
+    let sql = format!(
+        "SELECT *{} FROM {} WHERE {} ORDER BY {} LIMIT {} OFFSET {}",
+        cursor_columns,
+        table,
+        conditions.join(" AND "),
+        order_by,
+        per_page + 1,
+        offset
+    );
+    let filter = SqlFilter {
+        clause: sql.clone(),
+        params,
+    };
+    let mut rows = filter
+        .bind(query(&sql))
+        .fetch_all(pool)
+        .await
+        .map_err(invalid_value)?;
+
+    let next_cursor = if rows.len() as i64 > per_page {
+        rows.truncate(per_page as usize);
+        let last = rows.last().expect("a row before the extra one");
+        let values = (0..keys.len())
+            .map(|i| last.try_get::<Option<String>, _>(format!("_cursor_{}", i).as_str()))
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect::<Vec<_>>();
+        Some(encode_cursor(&values))
+    } else {
+        None
+    };
+    let items = rows
+        .iter()
+        .map(T::from_row)
+        .collect::<std::result::Result<Vec<T>, _>>()?;
+
+    Ok(Page {
+        items,
+        total,
+        per_page,
+        page: list.cursor.is_none().then(|| list.page()),
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> ListColumns {
+        ListColumns::new("id", "id", "integer")
+            .column("name", "name", "text")
+            .filter_column("parent_id", "parent_id", "text")
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let values = vec!["Ünïcode \"quoted\"".to_string(), "42".to_string()];
+        let cursor = encode_cursor(&values);
+        assert!(cursor.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(decode_cursor(&cursor), Some(values));
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        assert_eq!(decode_cursor("zz"), None);
+        assert_eq!(decode_cursor("5b2"), None);
+        assert_eq!(decode_cursor(&encode_cursor(&[]).replace("5d", "")), None);
+    }
+
+    #[test]
+    fn the_key_ends_every_sort() {
+        let columns = columns();
+        let mut errors = FieldErrors::new();
+        let list: ListQuery = "sort=-name".parse().unwrap();
+        let keys = sort_keys(&list, &columns, &mut errors);
+        let keys: Vec<_> = keys
+            .iter()
+            .map(|(column, direction)| (column.field, *direction))
+            .collect();
+        assert_eq!(
+            keys,
+            [("name", SortDirection::Desc), ("id", SortDirection::Asc)]
+        );
+        assert!(errors.is_empty());
+
+        let list: ListQuery = "sort=-id,name".parse().unwrap();
+        let keys = sort_keys(&list, &columns, &mut errors);
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn unknown_and_filter_only_fields_are_not_sortable() {
+        let columns = columns();
+        let mut errors = FieldErrors::new();
+        let list: ListQuery = "sort=parent_id,secret".parse().unwrap();
+        sort_keys(&list, &columns, &mut errors);
+        assert_eq!(
+            errors["sort"],
+            ["Can't sort by parent_id", "Can't sort by secret"]
+        );
+    }
+
+    #[test]
+    fn values_are_checked_against_the_column_type() {
+        assert!(valid_value("12", "integer"));
+        assert!(!valid_value("abc", "integer"));
+        assert!(!valid_value("3000000000", "integer"));
+        assert!(valid_value("3000000000", "bigint"));
+        assert!(valid_value("0.5", "numeric"));
+        assert!(valid_value("TRUE", "boolean"));
+        assert!(!valid_value("maybe", "boolean"));
+        assert!(!valid_value("not-a-uuid", "uuid"));
+        assert!(valid_value("anything", "text"));
+    }
+
+    #[test]
+    fn placeholders_follow_the_scope_params() {
+        let mut params = vec![SqlParam::Text("scope".to_string())];
+        assert_eq!(
+            push_param(&mut params, "1".to_string(), "integer"),
+            "$2::integer"
+        );
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn like_wildcards_are_escaped() {
+        assert_eq!(escape_like("50%_a\\b"), "50\\%\\_a\\\\b");
+    }
+}
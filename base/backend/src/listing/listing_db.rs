@@ -1 +1,36 @@
+use crate::abac::{ResourceColumns, SqlFilter};
+use crate::error::Result;
+use sam_proc_macros::catch_error;
+use sqlx::{postgres::PgRow, query, PgPool};
 
+/// The listing attributes policies can filter on
+pub fn listing_columns() -> ResourceColumns {
+    ResourceColumns::new("listing")
+        .column("id", "id")
+        .column("owner_id", "user_id")
+        .column("category_id", "category_id")
+        .column("status", "status")
+        .column("featured", "featured")
+        .column("views_count", "views_count")
+        .column("expires_at", "expires_at")
+        .column("created_at", "created_at")
+}
+
+/// Only the listings passing the policy `filter`
+#[catch_error]
+pub async fn list_listings(pool: &PgPool, filter: SqlFilter) -> Result<Vec<PgRow>> {
+    // Uuids and dates are cast to text for `rows_to_dataframe`
+    let sql = format!(
+        r#"
+        SELECT
+            id::text, user_id::text, category_id, title, description, status, featured,
+            views_count, expires_at::text, created_at::text, updated_at::text
+        FROM listings
+        WHERE {}
+        ORDER BY created_at DESC
+        "#,
+        filter.clause
+    );
+    let rows: Vec<PgRow> = filter.bind(query(&sql)).fetch_all(pool).await?;
+    Ok(rows)
+}
@@ -1 +1,49 @@
+// GET	/listings?action=edit	List the listings the user may perform the action on (view by default)
 
+use axum::{
+    extract::{Query, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use sam_util::{df_to_json, rows_to_dataframe};
+use serde::Deserialize;
+
+use super::listing_db::{list_listings, listing_columns};
+use crate::{
+    abac::Authorizer,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn listing_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/listings", get(list_listings_handler))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingsQuery {
+    action: Option<String>,
+}
+
+async fn list_listings_handler(
+    State(state): State<AppState>,
+    authorizer: Authorizer,
+    Query(query): Query<ListingsQuery>,
+) -> Result<Response> {
+    let action = query.action.unwrap_or_else(|| "view".to_string());
+    let filter = authorizer.filter(&listing_columns(), &action, 1);
+    let rows = list_listings(&state.pool, filter).await?;
+    let mut df = rows_to_dataframe(rows).await?;
+    let listings = df_to_json(&mut df)?;
+    let res = UserResponse::with_json(listings).into_response();
+    Ok(res)
+}
@@ -0,0 +1,682 @@
+use serde_json::{json, Map, Value};
+use shared::{
+    api::{Endpoint, Method},
+    error_code, AddCategory, AddLanguage, DeleteCategory, DeleteLanguage, GetCategory, GetLanguage,
+    ListCategories, ListCategoryNames, ListLanguages, UpdateCategory, UpdateLanguage,
+};
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// A `shared::list::Page` of `items`
+fn page(items: Value) -> Value {
+    json!({
+        "type": "object",
+        "required": ["items", "total", "per_page"],
+        "properties": {
+            "items": array(items),
+            "total": { "type": "integer", "format": "int64", "description": "The rows matching the filters" },
+            "per_page": { "type": "integer", "format": "int64" },
+            "page": { "type": ["integer", "null"], "format": "int64", "description": "Null when read by cursor" },
+            "next_cursor": { "type": ["string", "null"], "description": "Null on the last page" }
+        }
+    })
+}
+
+/// The `UserResponse` envelope with `data` holding `Json`
+fn json_envelope(data: Value) -> Value {
+    json!({
+        "allOf": [
+            schema_ref("UserResponse"),
+            {
+                "type": "object",
+                "properties": {
+                    "success": { "const": true },
+                    "data": {
+                        "type": "object",
+                        "required": ["Json"],
+                        "properties": { "Json": data }
+                    }
+                }
+            }
+        ]
+    })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// Who may call an operation
+enum Access {
+    Public,
+    /// The user is read from the token when there is one
+    OptionalUser,
+    SignedIn,
+    /// Signed in and allowed by the ABAC policies
+    Authorized {
+        resource_type: &'static str,
+        action: &'static str,
+    },
+}
+
+/// # An operation of the document
+/// Built like the router, e.g.
+/// `Operation::endpoint(GetLanguage, "Languages", "A language by code").path_param(..)`, or
+/// from the method and path for the routes without an endpoint declaration.
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    access: Access,
+    parameters: Vec<Value>,
+    body: Option<Value>,
+    /// The schema of `data.Json`, the success message otherwise
+    response: Option<Value>,
+    status: u16,
+}
+
+impl Operation {
+    fn new(
+        method: &'static str,
+        path: &'static str,
+        tag: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Self {
+            method,
+            path,
+            tag,
+            summary,
+            access: Access::Public,
+            parameters: Vec::new(),
+            body: None,
+            response: None,
+            status: 200,
+        }
+    }
+
+    /// At the method and path of a `shared::api` endpoint, like `EndpointRouter::endpoint`
+    fn endpoint<E: Endpoint>(_endpoint: E, tag: &'static str, summary: &'static str) -> Self {
+        let method = match E::METHOD {
+            Method::Get => "get",
+            Method::Post => "post",
+            Method::Put => "put",
+            Method::Delete => "delete",
+        };
+        Self::new(method, E::PATH, tag, summary)
+    }
+
+    fn get(path: &'static str, tag: &'static str, summary: &'static str) -> Self {
+        Self::new("get", path, tag, summary)
+    }
+
+    fn post(path: &'static str, tag: &'static str, summary: &'static str) -> Self {
+        Self::new("post", path, tag, summary)
+    }
+
+    fn optional_user(mut self) -> Self {
+        self.access = Access::OptionalUser;
+        self
+    }
+
+    fn signed_in(mut self) -> Self {
+        self.access = Access::SignedIn;
+        self
+    }
+
+    fn authorize(mut self, resource_type: &'static str, action: &'static str) -> Self {
+        self.access = Access::Authorized {
+            resource_type,
+            action,
+        };
+        self
+    }
+
+    fn path_param(mut self, name: &str, schema: Value) -> Self {
+        self.parameters.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": schema
+        }));
+        self
+    }
+
+    fn query_param(mut self, name: &str, required: bool, description: &str) -> Self {
+        self.parameters.push(json!({
+            "name": name,
+            "in": "query",
+            "required": required,
+            "description": description,
+            "schema": { "type": "string" }
+        }));
+        self
+    }
+
+    /// The `shared::list::ListQuery` parameters, `fields` are the ones to sort and filter on
+    fn list_query(mut self, fields: &str) -> Self {
+        self = self
+            .query_param("page", false, "The page, from 1")
+            .query_param(
+                "per_page",
+                false,
+                "Rows per page, 50 by default and 200 at most",
+            )
+            .query_param(
+                "cursor",
+                false,
+                "The next_cursor of the previous page, instead of page",
+            )
+            .query_param(
+                "sort",
+                false,
+                &format!(
+                    "Comma separated fields, `-` in front sorts descending: {}",
+                    fields
+                ),
+            );
+        self.parameters.push(json!({
+            "name": "filters",
+            "in": "query",
+            "required": false,
+            "style": "form",
+            "explode": true,
+            "description": "`field=value` or `field.op=value` with op one of eq, ne, lt, lte, gt, \
+                gte and contains",
+            "schema": { "type": "object", "additionalProperties": { "type": "string" } }
+        }));
+        self
+    }
+
+    fn body(mut self, schema: Value) -> Self {
+        self.body = Some(schema);
+        self
+    }
+
+    fn response(mut self, schema: Value) -> Self {
+        self.response = Some(schema);
+        self
+    }
+
+    fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let success = match &self.response {
+            Some(data) => json_envelope(data.clone()),
+            None => schema_ref("MessageResponse"),
+        };
+        let mut responses = Map::new();
+        responses.insert(
+            self.status.to_string(),
+            json!({ "description": "Success", "content": json_content(success) }),
+        );
+        // The shared error responses, see `error_responses`
+        let mut error = |status: u16| {
+            responses.insert(
+                status.to_string(),
+                json!({ "$ref": format!("#/components/responses/{}", status) }),
+            );
+        };
+        if self.body.is_some() || !self.parameters.is_empty() {
+            error(400);
+        }
+        match &self.access {
+            Access::Public | Access::OptionalUser => {}
+            Access::SignedIn => error(401),
+            Access::Authorized { .. } => {
+                error(401);
+                error(403);
+            }
+        }
+        if self.parameters.iter().any(|param| param["in"] == "path") {
+            error(404);
+        }
+        error(500);
+
+        let mut operation = json!({
+            "tags": [self.tag],
+            "summary": self.summary,
+            "operationId": format!("{}{}", self.method, self.path.replace(['/', '{', '}', '-'], "_")),
+            "parameters": self.parameters,
+            "responses": responses,
+        });
+        if let Some(body) = &self.body {
+            operation["requestBody"] =
+                json!({ "required": true, "content": json_content(body.clone()) });
+        }
+        match &self.access {
+            Access::Public => {}
+            Access::OptionalUser => operation["security"] = json!([{}, { "cookieAuth": [] }]),
+            Access::SignedIn => operation["security"] = json!([{ "cookieAuth": [] }]),
+            Access::Authorized {
+                resource_type,
+                action,
+            } => {
+                operation["security"] = json!([{ "cookieAuth": [] }]);
+                operation["x-abac"] = json!({ "resource_type": resource_type, "action": action });
+            }
+        }
+        operation
+    }
+}
+
+fn operations() -> Vec<Operation> {
+    let language_id = json!({ "type": "integer", "format": "int32" });
+    vec![
+        // user_routes
+        Operation::get("/users/check-auth", "Users", "The signed in user")
+            .signed_in()
+            .response(schema_ref("UserInfo")),
+        Operation::post(
+            "/users/add",
+            "Users",
+            "Sign up, a verification email is sent",
+        )
+        .body(schema_ref("LoginUser"))
+        .status(201),
+        Operation::post(
+            "/users/login",
+            "Users",
+            "Sign in, the token is set as a cookie",
+        )
+        .body(schema_ref("LoginUser")),
+        Operation::get(
+            "/users/login/google",
+            "Users",
+            "Redirects to the Google sign in",
+        ),
+        Operation::get(
+            "/users/login/google/callback",
+            "Users",
+            "Where Google redirects back, answers with an HTML page",
+        )
+        .query_param("code", true, "The authorization code")
+        .query_param("state", true, "The CSRF state"),
+        Operation::post("/users/logout", "Users", "Removes the token cookie"),
+        Operation::get(
+            "/users/verify-email",
+            "Users",
+            "Verifies the email of a sign up",
+        )
+        .query_param("token", true, "The token of the verification email"),
+        Operation::get(
+            "/users/resend-verification",
+            "Users",
+            "Sends the verification email again",
+        )
+        .query_param(
+            "token",
+            true,
+            "The token of the verification email, expired or not",
+        ),
+        Operation::post(
+            "/users/forgot-password",
+            "Users",
+            "Sends a password reset email, answers the same when the email is unknown",
+        )
+        .body(schema_ref("ForgotPasswordPayload")),
+        Operation::post("/users/reset-password", "Users", "Sets a new password")
+            .body(schema_ref("ResetPasswordPayload")),
+        Operation::get(
+            "/users/dashboard/nav-items",
+            "Users",
+            "The dashboard nav items the user may see",
+        )
+        .signed_in()
+        .query_param(
+            "lang",
+            false,
+            "Language code of the labels, the preferred language of the user otherwise",
+        )
+        .response(array(schema_ref("DashNavItemInfo"))),
+        // category_routes
+        Operation::endpoint(
+            ListCategories,
+            "Categories",
+            "The categories the user may view",
+        )
+        .optional_user()
+        .list_query("id (parent_id filters only)")
+        .response(page(schema_ref("Category"))),
+        Operation::endpoint(
+            ListCategoryNames,
+            "Categories",
+            "The names of the categories",
+        )
+        .list_query("id, name, language_id, category_id, slug")
+        .response(page(schema_ref("CategoryName"))),
+        Operation::endpoint(GetCategory, "Categories", "A category")
+            .optional_user()
+            .path_param("id", json!({ "type": "string" }))
+            .response(schema_ref("Category")),
+        Operation::endpoint(AddCategory, "Categories", "Adds a category with its names")
+            .authorize("category", "write")
+            .body(schema_ref("CategoryWithNames")),
+        Operation::endpoint(
+            UpdateCategory,
+            "Categories",
+            "Sets the parent of a category and replaces its names",
+        )
+        .authorize("category", "write")
+        .path_param("id", json!({ "type": "string" }))
+        .body(schema_ref("CategoryWithNames")),
+        Operation::endpoint(DeleteCategory, "Categories", "Deletes a category")
+            .authorize("category", "write")
+            .path_param("id", json!({ "type": "string" })),
+        // language_routes
+        Operation::endpoint(ListLanguages, "Languages", "The languages")
+            .list_query("id, code, name, flag, active")
+            .response(page(schema_ref("Language"))),
+        Operation::endpoint(GetLanguage, "Languages", "A language by code")
+            .path_param(
+                "id",
+                json!({ "type": "string", "description": "The code, e.g. en" }),
+            )
+            .response(schema_ref("Language")),
+        Operation::endpoint(AddLanguage, "Languages", "Adds a language")
+            .authorize("language", "write")
+            .body(schema_ref("Language")),
+        Operation::endpoint(UpdateLanguage, "Languages", "Updates a language")
+            .authorize("language", "write")
+            .body(schema_ref("Language")),
+        Operation::endpoint(DeleteLanguage, "Languages", "Deletes a language")
+            .authorize("language", "write")
+            .path_param("id", language_id),
+        // listing_routes
+        Operation::get(
+            "/listings",
+            "Listings",
+            "The listings the user may perform the action on",
+        )
+        .signed_in()
+        .query_param("action", false, "The action, view by default")
+        .response(array(json!({ "type": "object" }))),
+    ]
+}
+
+fn schemas() -> Value {
+    json!({
+        "UserResponse": {
+            "type": "object",
+            "description": "The envelope of every response",
+            "required": ["success", "data", "status_code"],
+            "properties": {
+                "success": { "type": "boolean" },
+                "data": {
+                    "description": "The message or the JSON of the response",
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "required": ["String"],
+                            "properties": { "String": { "type": "string" } }
+                        },
+                        {
+                            "type": "object",
+                            "required": ["Json"],
+                            "properties": { "Json": {} }
+                        }
+                    ]
+                },
+                "status_code": { "type": "integer" }
+            }
+        },
+        "MessageResponse": {
+            "allOf": [
+                { "$ref": "#/components/schemas/UserResponse" },
+                {
+                    "type": "object",
+                    "properties": {
+                        "success": { "const": true },
+                        "data": {
+                            "type": "object",
+                            "required": ["String"],
+                            "properties": { "String": { "type": "string" } }
+                        }
+                    }
+                }
+            ]
+        },
+        "ErrorResponse": {
+            "allOf": [
+                { "$ref": "#/components/schemas/UserResponse" },
+                {
+                    "type": "object",
+                    "required": ["code"],
+                    "properties": {
+                        "success": { "const": false },
+                        "data": {
+                            "type": "object",
+                            "required": ["String"],
+                            "properties": { "String": { "type": "string", "description": "The message" } }
+                        },
+                        "code": { "$ref": "#/components/schemas/ErrorCode" },
+                        "field_errors": {
+                            "type": "object",
+                            "description": "Messages by field name for the validation errors",
+                            "additionalProperties": { "type": "array", "items": { "type": "string" } }
+                        },
+                        "correlation_id": {
+                            "type": "string",
+                            "format": "uuid",
+                            "description": "The id of the request, also in the x-request-id header"
+                        }
+                    }
+                }
+            ]
+        },
+        "ErrorCode": {
+            "type": "string",
+            "description": "Stable, clients can match on it",
+            "enum": error_code::ALL
+        },
+        "LoginUser": {
+            "type": "object",
+            "required": ["email", "password"],
+            "properties": {
+                "email": { "type": "string", "format": "email" },
+                "password": { "type": "string", "format": "password" }
+            }
+        },
+        "ForgotPasswordPayload": {
+            "type": "object",
+            "required": ["email"],
+            "properties": { "email": { "type": "string", "format": "email" } }
+        },
+        "ResetPasswordPayload": {
+            "type": "object",
+            "required": ["token", "new_password"],
+            "properties": {
+                "token": { "type": "string" },
+                "new_password": { "type": "string", "format": "password" }
+            }
+        },
+        "UserInfo": {
+            "type": "object",
+            "required": ["id", "email", "role", "attributes", "disabled", "password_reset_required", "created_at"],
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "email": { "type": "string", "format": "email" },
+                "role": { "type": "string", "enum": ["SuperAdmin", "Admin", "User"] },
+                "attributes": { "type": "object", "description": "The ABAC attributes of the user" },
+                "disabled": { "type": "boolean" },
+                "password_reset_required": { "type": "boolean" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "roles": { "type": "array", "items": { "type": "string" } },
+                "permissions": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "DashNavItemInfo": {
+            "type": "object",
+            "required": ["name", "icon", "route"],
+            "properties": {
+                "name": { "type": "string" },
+                "icon": { "type": "string" },
+                "route": { "type": "string" },
+                "sub_items": { "type": "array", "items": { "$ref": "#/components/schemas/DashNavItemInfo" } }
+            }
+        },
+        "Category": {
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": { "type": "string" },
+                "parent_id": { "type": ["string", "null"] }
+            }
+        },
+        "CategoryName": {
+            "type": "object",
+            "required": ["name", "language_id", "category_id", "slug"],
+            "properties": {
+                "id": { "type": ["integer", "null"], "format": "int32" },
+                "name": { "type": "string" },
+                "language_id": { "type": "integer", "format": "int32" },
+                "category_id": { "type": "string" },
+                "slug": { "type": "string" }
+            }
+        },
+        "CategoryWithNames": {
+            "type": "object",
+            "required": ["category", "names"],
+            "properties": {
+                "category": { "$ref": "#/components/schemas/Category" },
+                "names": { "type": "array", "items": { "$ref": "#/components/schemas/CategoryName" } }
+            }
+        },
+        "Language": {
+            "type": "object",
+            "required": ["id", "code", "name", "flag", "active"],
+            "properties": {
+                "id": { "type": "integer", "format": "int32", "description": "Ignored when adding" },
+                "code": { "type": "string" },
+                "name": { "type": "string" },
+                "flag": { "type": "string", "description": "The URL of the flag" },
+                "active": { "type": "boolean" }
+            }
+        }
+    })
+}
+
+fn error_responses() -> Value {
+    let mut responses = Map::new();
+    for (status, description) in [
+        (400, "Invalid request or validation failed"),
+        (401, "Not signed in"),
+        (403, "Denied by the policies"),
+        (404, "Not found"),
+        (500, "Internal error"),
+    ] {
+        responses.insert(
+            status.to_string(),
+            json!({
+                "description": description,
+                "content": json_content(schema_ref("ErrorResponse"))
+            }),
+        );
+    }
+    Value::Object(responses)
+}
+
+/// # The OpenAPI 3.1 document of the backend
+pub fn openapi_document() -> Value {
+    let mut paths = Map::new();
+    for operation in operations() {
+        let path = paths
+            .entry(operation.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path[operation.method] = operation.to_json();
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Backend API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Every response is a UserResponse envelope, errors carry a stable code."
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "responses": error_responses(),
+            "securitySchemes": {
+                "cookieAuth": { "type": "apiKey", "in": "cookie", "name": "token" }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use sqlx::postgres::PgPoolOptions;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{abac::PolicyEngine, config::Config, AppState};
+
+    /// A `{..}` segment filled in, the handlers may still reject it
+    fn sample_path(path: &str) -> String {
+        let mut sample = String::new();
+        let mut rest = path;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').expect("closed segment") + start;
+            sample.push_str(&rest[..start]);
+            sample.push('1');
+            rest = &rest[end + 1..];
+        }
+        sample.push_str(rest);
+        sample
+    }
+
+    #[tokio::test]
+    async fn every_operation_is_routed() {
+        // Nothing listens there, the handlers reaching the database fail right away
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://localhost:1/none")
+            .unwrap();
+        let state = AppState {
+            pool: Arc::new(pool),
+            policy_engine: Arc::new(PolicyEngine::with_default_policies()),
+            config: Arc::new(Config::test()),
+        };
+        let app = crate::api_routes(&state).with_state(state);
+
+        for operation in operations() {
+            let request = Request::builder()
+                .method(operation.method.to_uppercase().as_str())
+                .uri(sample_path(operation.path))
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            // The router answers a path or a method it doesn't know with an empty body
+            assert!(
+                !(body.is_empty()
+                    && matches!(
+                        status,
+                        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+                    )),
+                "{} {} isn't routed",
+                operation.method,
+                operation.path
+            );
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod document;
+mod openapi_routes;
+
+pub use document::openapi_document;
+pub use openapi_routes::openapi_routes;
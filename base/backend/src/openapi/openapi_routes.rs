@@ -0,0 +1,56 @@
+// The machine readable description of the API
+// GET	/openapi.json	The OpenAPI 3.1 document
+// GET	/docs	A viewer of the document, only in dev builds
+
+use std::sync::OnceLock;
+
+use axum::{
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::Value;
+
+use super::openapi_document;
+use crate::AppState;
+
+/// Swagger UI from the CDN, pointed at `/openapi.json`
+const VIEWER: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8" />
+    <title>API docs</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+    <script>
+        window.ui = SwaggerUIBundle({
+            url: "/openapi.json",
+            dom_id: "#swagger-ui",
+            withCredentials: true,
+        });
+    </script>
+</body>
+</html>
+"##;
+
+pub fn openapi_routes() -> Router<AppState> {
+    let router = Router::new().route("/openapi.json", get(openapi_handler));
+    if cfg!(debug_assertions) {
+        router.route("/docs", get(viewer_handler))
+    } else {
+        router
+    }
+}
+
+async fn openapi_handler() -> Response {
+    // Built once, the routes don't change while running
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+    Json(DOCUMENT.get_or_init(openapi_document)).into_response()
+}
+
+async fn viewer_handler() -> Html<&'static str> {
+    Html(VIEWER)
+}
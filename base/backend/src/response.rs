@@ -4,8 +4,11 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use shared::error_code;
 pub use shared::user::UserResponse;
 
+use crate::error_log::RequestContext;
+
 struct UserResponseWrapper(UserResponse);
 
 impl IntoResponse for UserResponseWrapper {
@@ -14,7 +17,19 @@ impl IntoResponse for UserResponseWrapper {
             Ok(s) => s,
             Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
-        (status_code, Json(self.0)).into_response()
+        let mut res = self.0;
+        // Errors can be found again in the logs and the error dashboard
+        if !res.success {
+            if res.code.is_none() {
+                res.code = Some(error_code::from_status(res.status_code).to_string());
+            }
+            if res.correlation_id.is_none() {
+                if let Some(context) = RequestContext::current() {
+                    res.correlation_id = Some(context.request_id.to_string());
+                }
+            }
+        }
+        (status_code, Json(res)).into_response()
     }
 }
 
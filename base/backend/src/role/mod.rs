@@ -0,0 +1,3 @@
+pub mod role_db;
+mod role_routes;
+pub use role_routes::role_routes;
@@ -0,0 +1,227 @@
+use crate::audit::{add_audit_entry, AuditActor, AuditRecord};
+use crate::error::Result;
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use serde_json::Value;
+use shared::role::{Role, RolePayload, BUILT_IN_ROLES};
+use sqlx::{query, query_as, PgPool, Postgres, Transaction};
+
+#[catch_error]
+pub async fn list_roles(pool: &PgPool) -> Result<Vec<Role>> {
+    let roles: Vec<Role> = query_as!(
+        Role,
+        r#"
+        SELECT r.id, r.name, r.description, r.permissions,
+            ARRAY(
+                SELECT p.name FROM role_parents rp JOIN roles p ON p.id = rp.parent_id
+                WHERE rp.role_id = r.id ORDER BY p.name
+            ) as "parents!",
+            r.created_at, r.updated_at
+        FROM roles r
+        ORDER BY r.name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(roles)
+}
+
+/// # The effective roles and permissions of role names
+/// Follows the inheritance like for a user, used by the policy simulator.
+#[catch_error]
+pub async fn resolve_roles(pool: &PgPool, names: &[String]) -> Result<(Vec<String>, Vec<String>)> {
+    let row = query!(
+        r#"
+        SELECT effective_roles($1) as "roles!", role_permissions($1) as "permissions!"
+        "#,
+        names
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((row.roles, row.permissions))
+}
+
+/// Replaces the parents, a role can't end up inheriting from itself
+async fn set_parents(
+    tx: &mut Transaction<'static, Postgres>,
+    role_id: i32,
+    payload: &RolePayload,
+) -> Result<()> {
+    query!(
+        r#"
+        DELETE FROM role_parents
+        WHERE role_id = $1
+        "#,
+        role_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let result = query!(
+        r#"
+        INSERT INTO role_parents (role_id, parent_id)
+        SELECT $1, id
+        FROM roles
+        WHERE name = ANY($2)
+        "#,
+        role_id,
+        &payload.parents
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() != payload.parents.len() as u64 {
+        return Err(SamError::InvalidRole(
+            "some of the parents don't exist".to_string(),
+        ));
+    }
+
+    let cycle = query!(
+        r#"
+        SELECT $1 = ANY(effective_roles($2)) as "cycle!"
+        "#,
+        payload.name,
+        &payload.parents
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .cycle;
+    if cycle {
+        return Err(SamError::InvalidRole(format!(
+            "{} would inherit from itself",
+            payload.name
+        )));
+    }
+    Ok(())
+}
+
+#[catch_error]
+pub async fn add_role(pool: &PgPool, actor: &AuditActor, payload: &RolePayload) -> Result<i32> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let row = query!(
+        r#"
+        INSERT INTO roles (name, description, permissions)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        payload.name,
+        payload.description,
+        &payload.permissions
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    set_parents(&mut tx, row.id, payload).await?;
+    let after = role_snapshot(&mut tx, row.id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("create", "role", row.id).after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(row.id)
+}
+
+/// Built-in roles keep their name, the `UserRole` enum points to them
+#[catch_error]
+pub async fn update_role(
+    pool: &PgPool,
+    actor: &AuditActor,
+    id: i32,
+    payload: &RolePayload,
+) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = role_snapshot(&mut tx, id).await?;
+    let current = query!(
+        r#"
+        SELECT name
+        FROM roles
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(SamError::NotFound("Role".to_string()))?;
+    if current.name != payload.name && BUILT_IN_ROLES.contains(&current.name.as_str()) {
+        return Err(SamError::InvalidRole(format!(
+            "{} is built in and can't be renamed",
+            current.name
+        )));
+    }
+
+    query!(
+        r#"
+        UPDATE roles
+        SET name = $1, description = $2, permissions = $3, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $4
+        "#,
+        payload.name,
+        payload.description,
+        &payload.permissions,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    set_parents(&mut tx, id, payload).await?;
+    let after = role_snapshot(&mut tx, id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("update", "role", id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Built-in roles can't be deleted, they are not found
+#[catch_error]
+pub async fn delete_role(pool: &PgPool, actor: &AuditActor, id: i32) -> Result<()> {
+    let mut tx: Transaction<'static, Postgres> = pool.begin().await?;
+    let before = role_snapshot(&mut tx, id).await?;
+    let result = query!(
+        r#"
+        DELETE FROM roles
+        WHERE id = $1 AND NOT (name = ANY($2))
+        "#,
+        id,
+        &BUILT_IN_ROLES.map(String::from)[..]
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Role".to_string()));
+    }
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("delete", "role", id).before(before),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The role with the names of its parents
+async fn role_snapshot(tx: &mut Transaction<'static, Postgres>, id: i32) -> Result<Option<Value>> {
+    let row = query!(
+        r#"
+        SELECT to_jsonb(r) || jsonb_build_object(
+            'parents',
+            ARRAY(
+                SELECT p.name FROM role_parents rp JOIN roles p ON p.id = rp.parent_id
+                WHERE rp.role_id = r.id ORDER BY p.name
+            )
+        ) as "role!"
+        FROM roles r
+        WHERE r.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row.map(|row| row.role))
+}
@@ -0,0 +1,85 @@
+// Routes for managing the roles (super admins only)
+// GET	/roles	List the roles with their permissions and parents
+// POST	/roles	Create a role
+// PUT	/roles/{id}	Update a role
+// DELETE	/roles/{id}	Delete a role, built-in roles can't be deleted
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use sam_error::SamError;
+use serde_json::json;
+use shared::role::RolePayload;
+
+use super::role_db::{add_role, delete_role, list_roles, update_role};
+use crate::{
+    abac::{authorize, Authorize},
+    audit::AuditActor,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    user::auth_middleware,
+    AppState,
+};
+
+pub fn role_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/roles", get(list_roles_handler).post(add_role_handler))
+        .route(
+            "/roles/{id}",
+            put(update_role_handler).delete(delete_role_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "role", "manage"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
+
+async fn list_roles_handler(State(state): State<AppState>) -> Result<Response> {
+    let roles = list_roles(&state.pool).await?;
+    let res = UserResponse::with_json(roles).into_response();
+    Ok(res)
+}
+
+async fn add_role_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    payload: Result<Json<RolePayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    payload.validate().map_err(SamError::InvalidRole)?;
+    let id = add_role(&state.pool, &actor, &payload).await?;
+    let res = UserResponse::with_json_and_code(json!({ "id": id }), 201).into_response();
+    Ok(res)
+}
+
+async fn update_role_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+    payload: Result<Json<RolePayload>, JsonRejection>,
+) -> Result<Response> {
+    let payload = payload?.0;
+    payload.validate().map_err(SamError::InvalidRole)?;
+    update_role(&state.pool, &actor, id, &payload).await?;
+    let res = UserResponse::with_success("Role updated").into_response();
+    Ok(res)
+}
+
+async fn delete_role_handler(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    delete_role(&state.pool, &actor, id).await?;
+    let res = UserResponse::with_success("Role deleted").into_response();
+    Ok(res)
+}
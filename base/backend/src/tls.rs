@@ -0,0 +1,204 @@
+// HTTPS without a proxy in front, when `tls_cert` and `tls_key` are set.
+// The files are read at startup and again whenever they change, e.g. after a renewal,
+// the connections already open keep the certificate they started with.
+
+use std::{
+    io,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use axum::serve::Listener;
+use sam_error::SamError;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
+        ServerConfig,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+use crate::{config::TlsConfig, error::Result};
+
+/// How often the files are checked for a new certificate
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+/// A client that doesn't finish the handshake in time is dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The connections waiting for `axum::serve` to take them
+const ACCEPT_QUEUE: usize = 128;
+
+/// # Accepts TLS connections for `axum::serve`
+/// Every handshake runs in its own task, a slow client doesn't hold up the others.
+pub struct TlsListener {
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub async fn bind(addr: SocketAddr, tls: &TlsConfig) -> Result<Self> {
+        let certificate = Arc::new(ReloadingCertificate::load(tls.clone())?);
+        tokio::spawn(watch_certificate(certificate.clone()));
+
+        let mut server = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| SamError::InvalidConfig(format!("TLS: {}", e)))?
+            .with_no_client_auth()
+            .with_cert_resolver(certificate);
+        // axum::serve speaks HTTP/1 only
+        server.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| format!("Can't listen on {}: {}", addr, e))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| format!("Can't listen on {}: {}", addr, e))?;
+        let (sender, connections) = mpsc::channel(ACCEPT_QUEUE);
+        tokio::spawn(accept(
+            listener,
+            TlsAcceptor::from(Arc::new(server)),
+            sender,
+        ));
+        Ok(Self {
+            connections,
+            local_addr,
+        })
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        // The accept task only stops once the listener is dropped
+        self.connections
+            .recv()
+            .await
+            .expect("the TLS accept task is running")
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+async fn accept(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    connections: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    while !connections.is_closed() {
+        let (stream, addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                // e.g. too many open files, give the open connections time to close
+                tracing::error!("Accepting a connection failed: {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let connections = connections.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = connections.send((stream, addr)).await;
+                }
+                Ok(Err(e)) => tracing::debug!("TLS handshake with {} failed: {}", addr, e),
+                Err(_) => tracing::debug!("TLS handshake with {} timed out", addr),
+            }
+        });
+    }
+}
+
+/// The certificate every handshake gets, swapped by `watch_certificate`
+#[derive(Debug)]
+struct ReloadingCertificate {
+    tls: TlsConfig,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadingCertificate {
+    fn load(tls: TlsConfig) -> Result<Self> {
+        let current = RwLock::new(Arc::new(load_certified_key(&tls)?));
+        Ok(Self { tls, current })
+    }
+
+    /// When the certificate or the key last changed
+    fn modified(&self) -> Option<SystemTime> {
+        [&self.tls.cert, &self.tls.key]
+            .into_iter()
+            .filter_map(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+            .max()
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertificate {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(
+            self.current
+                .read()
+                .unwrap_or_else(|err| err.into_inner())
+                .clone(),
+        )
+    }
+}
+
+/// # Reloads the certificate when its files change
+/// A certificate that doesn't load, e.g. a key written after its certificate, is tried
+/// again on the next check, the current one stays until then.
+async fn watch_certificate(certificate: Arc<ReloadingCertificate>) {
+    let mut loaded = certificate.modified();
+    loop {
+        tokio::time::sleep(RELOAD_INTERVAL).await;
+        let modified = certificate.modified();
+        if modified == loaded {
+            continue;
+        }
+        match load_certified_key(&certificate.tls) {
+            Ok(key) => {
+                *certificate
+                    .current
+                    .write()
+                    .unwrap_or_else(|err| err.into_inner()) = Arc::new(key);
+                loaded = modified;
+                tracing::info!(
+                    "Reloaded the TLS certificate {}",
+                    certificate.tls.cert.display()
+                );
+            }
+            Err(e) => tracing::error!("Keeping the current TLS certificate: {}", e),
+        }
+    }
+}
+
+fn load_certified_key(tls: &TlsConfig) -> Result<CertifiedKey> {
+    let certs = CertificateDer::pem_file_iter(&tls.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid_file(&tls.cert, e))?;
+    if certs.is_empty() {
+        return Err(invalid_file(&tls.cert, "no certificate"));
+    }
+    let key = PrivateKeyDer::from_pem_file(&tls.key).map_err(|e| invalid_file(&tls.key, e))?;
+    let key = ring::sign::any_supported_type(&key).map_err(|e| invalid_file(&tls.key, e))?;
+    let certified = CertifiedKey::new(certs, key);
+    certified
+        .keys_match()
+        .map_err(|e| invalid_file(&tls.key, e))?;
+    Ok(certified)
+}
+
+fn invalid_file(path: &Path, error: impl std::fmt::Display) -> SamError {
+    SamError::InvalidConfig(format!("{}: {}", path.display(), error))
+}
@@ -0,0 +1,405 @@
+use crate::audit::{add_audit_entry, AuditActor, AuditRecord};
+use crate::error::Result;
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use serde_json::{json, Value};
+use shared::user::{AdminAction, UserFilter, UserList, UserRole, UserSummary};
+use sqlx::{query, query_as, PgPool};
+use uuid::Uuid;
+
+#[catch_error]
+pub async fn list_users(pool: &PgPool, filter: &UserFilter) -> Result<UserList> {
+    let search = filter
+        .search
+        .as_ref()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    let rows = query!(
+        r#"
+        SELECT
+            id as "id!",
+            email as "email!",
+            role as "role: UserRole",
+            roles as "roles!",
+            verified as "verified!",
+            disabled as "disabled!",
+            attributes as "attributes!",
+            created_at as "created_at!",
+            COUNT(*) OVER() as "total!"
+        FROM (
+            SELECT id, email, role,
+                ARRAY(
+                    SELECT r.name FROM user_roles ur JOIN roles r ON r.id = ur.role_id
+                    WHERE ur.user_id = users.id ORDER BY r.name
+                ) AS roles,
+                TRUE AS verified, disabled, attributes, created_at
+            FROM users
+            UNION ALL
+            SELECT id, email, NULL::user_role, '{}'::text[], FALSE, FALSE, '{}'::jsonb, created_at
+            FROM pending_users
+        ) AS all_users
+        WHERE ($1::text IS NULL OR email ILIKE '%' || $1 || '%')
+            AND ($2::user_role IS NULL OR role = $2)
+            AND ($3::bool IS NULL OR verified = $3)
+        ORDER BY created_at DESC
+        LIMIT $4 OFFSET $5
+        "#,
+        search,
+        filter.role.clone() as Option<UserRole>,
+        filter.verified,
+        filter.per_page(),
+        filter.offset()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let total = rows.first().map(|row| row.total).unwrap_or(0);
+    let users = rows
+        .into_iter()
+        .map(|row| UserSummary {
+            id: row.id,
+            email: row.email,
+            role: row.role,
+            roles: row.roles,
+            verified: row.verified,
+            disabled: row.disabled,
+            attributes: row.attributes,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok(UserList {
+        users,
+        total,
+        page: filter.page(),
+        per_page: filter.per_page(),
+    })
+}
+
+#[catch_error]
+pub async fn get_user_summary(pool: &PgPool, user_id: Uuid) -> Result<UserSummary> {
+    let user: UserSummary = query_as!(
+        UserSummary,
+        r#"
+        SELECT
+            id as "id!",
+            email as "email!",
+            role as "role: UserRole",
+            roles as "roles!",
+            verified as "verified!",
+            disabled as "disabled!",
+            attributes as "attributes!",
+            created_at as "created_at!"
+        FROM (
+            SELECT id, email, role,
+                ARRAY(
+                    SELECT r.name FROM user_roles ur JOIN roles r ON r.id = ur.role_id
+                    WHERE ur.user_id = users.id ORDER BY r.name
+                ) AS roles,
+                TRUE AS verified, disabled, attributes, created_at
+            FROM users
+            UNION ALL
+            SELECT id, email, NULL::user_role, '{}'::text[], FALSE, FALSE, '{}'::jsonb, created_at
+            FROM pending_users
+        ) AS all_users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(user)
+}
+
+#[catch_error]
+pub async fn list_admin_actions(pool: &PgPool, user_id: Uuid) -> Result<Vec<AdminAction>> {
+    let actions: Vec<AdminAction> = query_as!(
+        AdminAction,
+        r#"
+        SELECT id, admin_id, user_id, action, details, created_at
+        FROM admin_actions
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(actions)
+}
+
+/// Replaces the roles assigned on top of the built-in role
+#[catch_error]
+pub async fn set_user_roles(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    roles: &[String],
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+
+    query!(
+        r#"
+        DELETE FROM user_roles
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = query!(
+        r#"
+        INSERT INTO user_roles (user_id, role_id)
+        SELECT $1, id
+        FROM roles
+        WHERE name = ANY($2)
+        "#,
+        user_id,
+        roles
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() != roles.len() as u64 {
+        return Err(SamError::InvalidRole(
+            "some of the roles don't exist".to_string(),
+        ));
+    }
+
+    add_admin_action(
+        &mut tx,
+        actor,
+        user_id,
+        "set_roles",
+        json!({ "roles": roles }),
+        before,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn change_user_role(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    role: UserRole,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+
+    let result = query!(
+        r#"
+        UPDATE users
+        SET role = $1
+        WHERE id = $2
+        "#,
+        role.clone() as UserRole,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    add_admin_action(
+        &mut tx,
+        actor,
+        user_id,
+        "change_role",
+        json!({ "role": role }),
+        before,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn update_user_attributes(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    attributes: Value,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+
+    let result = query!(
+        r#"
+        UPDATE users
+        SET attributes = $1
+        WHERE id = $2
+        "#,
+        attributes,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    add_admin_action(
+        &mut tx,
+        actor,
+        user_id,
+        "update_attributes",
+        json!({ "attributes": attributes }),
+        before,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn set_user_disabled(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    disabled: bool,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+
+    let result = query!(
+        r#"
+        UPDATE users
+        SET disabled = $1
+        WHERE id = $2
+        "#,
+        disabled,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    let action = if disabled { "disable" } else { "enable" };
+    add_admin_action(&mut tx, actor, user_id, action, json!({}), before).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn require_password_reset(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+
+    let result = query!(
+        r#"
+        UPDATE users
+        SET password_reset_required = TRUE
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    add_admin_action(
+        &mut tx,
+        actor,
+        user_id,
+        "force_password_reset",
+        json!({}),
+        before,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn log_admin_action(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    action: &str,
+    details: Value,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+    add_admin_action(&mut tx, actor, user_id, action, details, before).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// # Record an operation on a user account
+/// In `admin_actions` for the history of the account and in the audit log
+/// with the account before and after the operation.
+async fn add_admin_action(
+    tx: &mut sqlx::Transaction<'static, sqlx::Postgres>,
+    actor: &AuditActor,
+    user_id: Uuid,
+    action: &str,
+    details: Value,
+    before: Option<Value>,
+) -> Result<()> {
+    query!(
+        r#"
+        INSERT INTO admin_actions (admin_id, user_id, action, details)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        actor.user_id,
+        user_id,
+        action,
+        details
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let after = user_snapshot(tx, user_id).await?;
+    add_audit_entry(
+        tx,
+        actor,
+        AuditRecord::new(action, "user", user_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+    Ok(())
+}
+
+/// The account with its assigned roles, the password left out
+pub async fn user_snapshot(
+    tx: &mut sqlx::Transaction<'static, sqlx::Postgres>,
+    user_id: Uuid,
+) -> Result<Option<Value>> {
+    let row = query!(
+        r#"
+        SELECT (to_jsonb(u) - 'password') || jsonb_build_object(
+            'roles',
+            ARRAY(
+                SELECT r.name FROM user_roles ur JOIN roles r ON r.id = ur.role_id
+                WHERE ur.user_id = u.id ORDER BY r.name
+            )
+        ) as "user!"
+        FROM users u
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row.map(|row| row.user))
+}
@@ -0,0 +1,321 @@
+// Routes for managing user accounts from the dashboard
+// GET	/admin/users	List users (search, role and verification filters, pagination)
+// GET	/admin/users/{id}	Get a user with its admin actions trail
+// PUT	/admin/users/{id}/role	Change the role (super admin only)
+// PUT	/admin/users/{id}/roles	Replace the roles assigned on top of the built-in one (super admin only)
+// PUT	/admin/users/{id}/attributes	Replace the attributes
+// PUT	/admin/users/{id}/disabled	Disable or enable the account
+// POST	/admin/users/{id}/force-password-reset	Block the account until the password is reset
+// POST	/admin/users/{id}/impersonate	Log in as the user (super admin only)
+// POST	/users/stop-impersonation	Go back to the super admin session
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Router,
+};
+use axum_extra::extract::CookieJar;
+use sam_error::SamError;
+use serde_json::{json, Value};
+use shared::{
+    attribute_schema::validate_attributes,
+    role::UserRolesPayload,
+    user::{
+        ChangeRolePayload, ChangeUserRole, ForcePasswordReset, Impersonate, ListUsers,
+        SetDisabledPayload, SetUserAttributes, SetUserDisabled, SetUserRoles, UserInfo, UserRole,
+    },
+    AttributeMap, Resource,
+};
+use uuid::Uuid;
+
+use super::{
+    admin_db::{
+        change_user_role, get_user_summary, list_admin_actions, list_users, log_admin_action,
+        require_password_reset, set_user_disabled, set_user_roles, update_user_attributes,
+    },
+    auth::auth_middleware,
+    cookie::create_cookie,
+    jwt::{create_impersonation_jwt, create_jwt, validate_jwt},
+    token::{issue_token, TokenPurpose},
+    user_db::fetch_user_by_id,
+    user_emails::{generate_forgot_password_body, send_verification_email},
+};
+use crate::{
+    abac::{authorize, list_attribute_schemas, Authorize, Authorizer},
+    api::{reply, EndpointRouter, Params, Payload},
+    audit::AuditActor,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    AppState,
+};
+
+// Impersonation sessions are kept short on purpose
+const IMPERSONATION_SECONDS: i64 = 60 * 60;
+
+pub fn admin_routes(state: AppState) -> Router<AppState> {
+    let admin = Router::new()
+        .endpoint(ListUsers, list_users_handler)
+        .route("/admin/users/{id}", get(get_user_handler))
+        .endpoint(ChangeUserRole, change_role_handler)
+        .endpoint(SetUserRoles, set_roles_handler)
+        .endpoint(SetUserAttributes, update_attributes_handler)
+        .endpoint(SetUserDisabled, set_disabled_handler)
+        .endpoint(ForcePasswordReset, force_password_reset_handler)
+        .endpoint(Impersonate, impersonate_handler)
+        .route_layer(middleware::from_fn_with_state(
+            Authorize::new(&state, "user", "manage"),
+            authorize,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
+    Router::new()
+        .merge(admin)
+        .route(
+            "/users/stop-impersonation",
+            post(stop_impersonation_handler),
+        )
+        .with_state(state)
+}
+
+fn ensure_super_admin(actor: &UserInfo) -> Result<()> {
+    if actor.role != UserRole::SuperAdmin {
+        return Err(SamError::Forbidden("Super admins only".to_string()));
+    }
+    Ok(())
+}
+
+/// Admins can manage regular users and other admins, only super admins can touch super admins
+fn ensure_can_manage(actor: &UserInfo, target: &UserInfo) -> Result<()> {
+    if actor.id == target.id {
+        return Err(SamError::Forbidden(
+            "You can't perform this action on your own account".to_string(),
+        ));
+    }
+    if target.role == UserRole::SuperAdmin {
+        ensure_super_admin(actor)?;
+    }
+    Ok(())
+}
+
+async fn fetch_target(state: &AppState, id: Uuid) -> Result<UserInfo> {
+    fetch_user_by_id(&state.pool, id)
+        .await
+        .map_err(|err| match err {
+            SamError::LoginFailed => SamError::NotFound("User".to_string()),
+            _ => err,
+        })
+}
+
+fn parse_id(id: &str) -> Result<Uuid> {
+    Uuid::parse_str(id).map_err(|_| SamError::Err("Invalid user id".to_string()))
+}
+
+async fn list_users_handler(
+    State(state): State<AppState>,
+    Payload(filter): Payload<ListUsers>,
+) -> Result<Response> {
+    let users = list_users(&state.pool, &filter).await?;
+    reply::<ListUsers>(users)
+}
+
+async fn get_user_handler(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<Response> {
+    let user = get_user_summary(&state.pool, id).await?;
+    let actions = list_admin_actions(&state.pool, id).await?;
+    let res = UserResponse::with_json(json!({ "user": user, "actions": actions })).into_response();
+    Ok(res)
+}
+
+async fn change_role_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    authorizer: Authorizer,
+    Params(id): Params<ChangeUserRole>,
+    Payload(ChangeRolePayload { role }): Payload<ChangeUserRole>,
+) -> Result<Response> {
+    // Roles decide who can do what, so only super admins can hand them out
+    authorizer.authorize(&Resource::new("user", id.to_string()), "change_role")?;
+    let target = fetch_target(&state, id).await?;
+    ensure_can_manage(&actor, &target)?;
+
+    change_user_role(&state.pool, &audit_actor, id, role).await?;
+    reply::<ChangeUserRole>("Role changed successfully".to_string())
+}
+
+async fn set_roles_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    authorizer: Authorizer,
+    Params(id): Params<SetUserRoles>,
+    Payload(UserRolesPayload { mut roles }): Payload<SetUserRoles>,
+) -> Result<Response> {
+    roles.sort();
+    roles.dedup();
+
+    authorizer.authorize(&Resource::new("user", id.to_string()), "change_role")?;
+    let target = fetch_target(&state, id).await?;
+    ensure_can_manage(&actor, &target)?;
+
+    set_user_roles(&state.pool, &audit_actor, id, &roles).await?;
+    reply::<SetUserRoles>("Roles changed successfully".to_string())
+}
+
+async fn update_attributes_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    Params(id): Params<SetUserAttributes>,
+    Payload(attributes): Payload<SetUserAttributes>,
+) -> Result<Response> {
+    if !attributes.is_object() {
+        return Err(SamError::Err(
+            "Attributes must be a JSON object".to_string(),
+        ));
+    }
+    // Checked against the `user` schemas and stored the way `AttributeMap` writes them
+    let mut attributes = AttributeMap::from(attributes);
+    let schemas = list_attribute_schemas(&state.pool, Some("user")).await?;
+    validate_attributes(&schemas, &mut attributes)
+        .map_err(|errors| SamError::InvalidAttributes(errors.join(", ")))?;
+    let attributes = Value::from(attributes);
+
+    let target = fetch_target(&state, id).await?;
+    ensure_can_manage(&actor, &target)?;
+
+    update_user_attributes(&state.pool, &audit_actor, id, attributes).await?;
+    reply::<SetUserAttributes>("Attributes updated successfully".to_string())
+}
+
+async fn set_disabled_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    Params(id): Params<SetUserDisabled>,
+    Payload(SetDisabledPayload { disabled }): Payload<SetUserDisabled>,
+) -> Result<Response> {
+    let target = fetch_target(&state, id).await?;
+    ensure_can_manage(&actor, &target)?;
+
+    set_user_disabled(&state.pool, &audit_actor, id, disabled).await?;
+    let msg = if disabled {
+        "Account disabled successfully"
+    } else {
+        "Account enabled successfully"
+    };
+    reply::<SetUserDisabled>(msg.to_string())
+}
+
+async fn force_password_reset_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    Params(id): Params<ForcePasswordReset>,
+) -> Result<Response> {
+    let target = fetch_target(&state, id).await?;
+    ensure_can_manage(&actor, &target)?;
+
+    // The account is blocked (including open sessions) until the password is reset
+    require_password_reset(&state.pool, &audit_actor, id).await?;
+
+    let reset_token = issue_token(&state.pool, &target.email, TokenPurpose::PasswordReset).await?;
+    let body = generate_forgot_password_body(&state.config, reset_token)?;
+    send_verification_email(&state.config, body, "Reset Your Password", &target.email).await?;
+
+    reply::<ForcePasswordReset>("Password reset email sent".to_string())
+}
+
+async fn impersonate_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    authorizer: Authorizer,
+    cookies: CookieJar,
+    Params(id): Params<Impersonate>,
+) -> Result<Response> {
+    authorizer.authorize(&Resource::new("user", id.to_string()), "impersonate")?;
+    let target = fetch_target(&state, id).await?;
+    ensure_can_manage(&actor, &target)?;
+    if target.role == UserRole::SuperAdmin {
+        return Err(SamError::Forbidden(
+            "Super admins can't be impersonated".to_string(),
+        ));
+    }
+
+    log_admin_action(
+        &state.pool,
+        &audit_actor,
+        id,
+        "impersonate",
+        json!({ "email": target.email }),
+    )
+    .await?;
+    tracing::warn!(
+        "Super admin {} is impersonating {}",
+        actor.email,
+        target.email
+    );
+
+    let token = create_impersonation_jwt(
+        &target.id.to_string(),
+        &actor.id.to_string(),
+        IMPERSONATION_SECONDS as usize,
+    )?;
+    let cookie = create_cookie("token".to_string(), token, IMPERSONATION_SECONDS);
+    let cookies = cookies.add(cookie);
+
+    let res = reply::<Impersonate>(format!("You are now logged in as {}", target.email))?;
+    Ok((cookies, res).into_response())
+}
+
+async fn stop_impersonation_handler(
+    State(state): State<AppState>,
+    audit_actor: AuditActor,
+    cookies: CookieJar,
+) -> Result<Response> {
+    let token = cookies
+        .get("token")
+        .map(|c| c.value())
+        .ok_or(SamError::NotAuthorized)?;
+    let claims = validate_jwt(token)?;
+    let admin_id = claims.imp.ok_or(SamError::Forbidden(
+        "You are not impersonating anyone".to_string(),
+    ))?;
+
+    // The impersonator must still be an active super admin
+    let admin = fetch_user_by_id(&state.pool, parse_id(&admin_id)?).await?;
+    ensure_super_admin(&admin)?;
+    if admin.disabled {
+        return Err(SamError::AccountDisabled);
+    }
+
+    // The route has no logged in user, the super admin is the actor
+    let audit_actor = AuditActor {
+        user_id: Some(parse_id(&admin_id)?),
+        email: Some(admin.email.clone()),
+        ..audit_actor
+    };
+    log_admin_action(
+        &state.pool,
+        &audit_actor,
+        parse_id(&claims.sub)?,
+        "stop_impersonation",
+        json!({}),
+    )
+    .await?;
+
+    let seconds = 60 * 60 * 24 * 7;
+    let token = create_jwt(&admin_id, seconds as usize)?;
+    let cookie = create_cookie("token".to_string(), token, seconds);
+    let cookies = cookies.add(cookie);
+
+    let res = UserResponse::with_success("Impersonation stopped").into_response();
+    Ok((cookies, res).into_response())
+}
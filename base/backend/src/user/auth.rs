@@ -8,7 +8,11 @@ use axum::{
 use axum_extra::extract::CookieJar;
 use sam_error::SamError;
 
-use crate::{error::Result, user::user_db::fetch_user_by_id, AppState};
+use shared::user::UserInfo;
+
+use crate::{
+    error::Result, error_log::set_request_user, user::user_db::fetch_user_by_id, AppState,
+};
 
 use super::jwt::validate_jwt;
 
@@ -18,6 +22,29 @@ pub async fn auth_middleware(
     mut req: Request,
     next: Next,
 ) -> Result<Response> {
+    let user = session_user(&cookies, &state).await?;
+
+    // Add claims contains user info to req
+    req.extensions_mut().insert(Arc::new(user));
+
+    Ok(next.run(req).await)
+}
+
+/// Like `auth_middleware` but lets visitors through,
+/// the user is in the request extensions only when the session is valid
+pub async fn optional_auth_middleware(
+    cookies: CookieJar,
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if let Ok(user) = session_user(&cookies, &state).await {
+        req.extensions_mut().insert(Arc::new(user));
+    }
+    next.run(req).await
+}
+
+async fn session_user(cookies: &CookieJar, state: &AppState) -> Result<UserInfo> {
     // Extract the token if exist
     let token = cookies
         .get("token")
@@ -30,11 +57,20 @@ pub async fn auth_middleware(
     let user_id = uuid::Uuid::parse_str(&claims.sub)
         .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
     let user = fetch_user_by_id(&state.pool, user_id).await?;
+    ensure_active(&user)?;
+    set_request_user(user_id);
+    Ok(user)
+}
 
-    // Add claims contains user info to req
-    req.extensions_mut().insert(Arc::new(user));
-
-    Ok(next.run(req).await)
+/// Disabled accounts and accounts waiting for a forced password reset can't log in or use a session
+pub fn ensure_active(user: &UserInfo) -> Result<()> {
+    if user.disabled {
+        return Err(SamError::AccountDisabled);
+    }
+    if user.password_reset_required {
+        return Err(SamError::PasswordResetRequired);
+    }
+    Ok(())
 }
 
 // pub async fn auth_middleware(cookies: CookieJar, req: Request, next: Next) -> Response {
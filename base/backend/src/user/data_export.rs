@@ -0,0 +1,174 @@
+use std::{
+    io::{Cursor, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use sam_error::SamError;
+use sam_util::{df_to_csv, df_to_json, rows_to_dataframe};
+use sqlx::{postgres::PgRow, PgPool};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::{
+    export_db::{
+        export_listing_field_value_rows, export_listing_rows, export_session_rows,
+        export_user_rows, fail_interrupted_exports, set_export_failed, set_export_ready,
+        set_export_running, take_expired_export_files,
+    },
+    profile_db::fetch_profile,
+    user_emails::{generate_data_export_ready_body, send_verification_email},
+};
+use crate::{
+    config::Config,
+    error::Result,
+    utils::{EXPORTS_DIR, UPLOADS_DIR},
+};
+
+/// How long a finished export can be downloaded
+pub const EXPORT_TTL_HOURS: i64 = 48;
+
+const README: &str = "\
+This archive contains everything we store about your account.
+
+user.json / user.csv                 Your account (the password is never exported)
+attributes.json                      The attributes attached to your account
+listings.json / listings.csv         Your listings
+listing_field_values.json / .csv     The field values of your listings
+sessions.json / sessions.csv         Issued email/password tokens and impersonation sessions
+files/                               The files you uploaded (e.g. your avatar)
+";
+
+/// # Build the export in the background
+/// The status is kept in `data_exports` and the user gets an email once it's ready.
+pub fn spawn_export(pool: Arc<PgPool>, config: Arc<Config>, export_id: Uuid, user_id: Uuid) {
+    tokio::spawn(async move {
+        if let Err(err) = run_export(&pool, &config, export_id, user_id).await {
+            tracing::error!("Data export {} failed: {}", export_id, err);
+            let _ = set_export_failed(&pool, export_id, &err.to_string()).await;
+        }
+    });
+}
+
+async fn run_export(pool: &PgPool, config: &Config, export_id: Uuid, user_id: Uuid) -> Result<()> {
+    set_export_running(pool, export_id).await?;
+
+    let archive = build_archive(pool, user_id).await?;
+    let file_name = format!("{}.zip", export_id.simple());
+    tokio::fs::create_dir_all(EXPORTS_DIR)
+        .await
+        .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+    tokio::fs::write(Path::new(EXPORTS_DIR).join(&file_name), archive)
+        .await
+        .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+
+    let expires_at = OffsetDateTime::now_utc() + Duration::hours(EXPORT_TTL_HOURS);
+    set_export_ready(pool, export_id, &file_name, expires_at).await?;
+
+    let profile = fetch_profile(pool, user_id).await?;
+    let body = generate_data_export_ready_body(config, export_id, EXPORT_TTL_HOURS)?;
+    send_verification_email(config, body, "Your Data Export Is Ready", &profile.email).await?;
+    Ok(())
+}
+
+async fn build_archive(pool: &PgPool, user_id: Uuid) -> Result<Vec<u8>> {
+    let profile = fetch_profile(pool, user_id).await?;
+    let mut files: Vec<(String, Vec<u8>)> = vec![("README.txt".to_string(), README.into())];
+
+    add_dataset(&mut files, "user", export_user_rows(pool, user_id).await?).await?;
+    add_dataset(
+        &mut files,
+        "listings",
+        export_listing_rows(pool, user_id).await?,
+    )
+    .await?;
+    add_dataset(
+        &mut files,
+        "listing_field_values",
+        export_listing_field_value_rows(pool, user_id).await?,
+    )
+    .await?;
+    add_dataset(
+        &mut files,
+        "sessions",
+        export_session_rows(pool, user_id).await?,
+    )
+    .await?;
+
+    let attributes = sqlx::query_scalar!("SELECT attributes FROM users WHERE id = $1", user_id)
+        .fetch_one(pool)
+        .await?;
+    files.push((
+        "attributes.json".to_string(),
+        serde_json::to_vec_pretty(&attributes).map_err(|err| SamError::Err(err.to_string()))?,
+    ));
+
+    if let Some(avatar) = profile.avatar {
+        if let Some(relative) = avatar.strip_prefix("/uploads/") {
+            match tokio::fs::read(Path::new(UPLOADS_DIR).join(relative)).await {
+                Ok(bytes) => files.push((format!("files/{}", relative), bytes)),
+                Err(err) => tracing::warn!("Avatar {} missing from export: {}", avatar, err),
+            }
+        }
+    }
+
+    zip_files(files)
+}
+
+/// Adds `<name>.json` and `<name>.csv` built with polars
+async fn add_dataset(
+    files: &mut Vec<(String, Vec<u8>)>,
+    name: &str,
+    rows: Vec<PgRow>,
+) -> Result<()> {
+    let mut df = rows_to_dataframe(rows).await?;
+    let json = df_to_json(&mut df)?;
+    files.push((
+        format!("{}.json", name),
+        serde_json::to_vec_pretty(&json).map_err(|err| SamError::Err(err.to_string()))?,
+    ));
+    files.push((format!("{}.csv", name), df_to_csv(&mut df)?.into_bytes()));
+    Ok(())
+}
+
+fn zip_files(files: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, bytes) in files {
+        zip.start_file(name, options)
+            .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+        zip.write_all(&bytes)
+            .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+    }
+    let cursor = zip
+        .finish()
+        .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+    Ok(cursor.into_inner())
+}
+
+/// # Remove expired exports
+/// Runs forever, meant to be spawned once at startup.
+pub async fn cleanup_data_exports(pool: Arc<PgPool>) {
+    if let Err(err) = fail_interrupted_exports(&pool).await {
+        tracing::error!("Failed to mark interrupted exports: {}", err);
+    }
+
+    loop {
+        match take_expired_export_files(&pool).await {
+            Ok(files) => {
+                for file in files {
+                    if let Err(err) =
+                        tokio::fs::remove_file(Path::new(EXPORTS_DIR).join(&file)).await
+                    {
+                        tracing::warn!("Failed to remove export {}: {}", file, err);
+                    }
+                }
+            }
+            Err(err) => tracing::error!("Failed to clean up exports: {}", err),
+        }
+
+        // Run every hour
+        tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+    }
+}
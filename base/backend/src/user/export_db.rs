@@ -0,0 +1,263 @@
+use crate::error::Result;
+use sam_proc_macros::catch_error;
+use shared::user::{DataExport, DataExportStatus};
+use sqlx::{postgres::PgRow, query, query_as, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ExportFile {
+    pub status: DataExportStatus,
+    pub file_path: Option<String>,
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+#[catch_error]
+pub async fn add_data_export(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<DataExport> {
+    let export: DataExport = query_as!(
+        DataExport,
+        r#"
+        INSERT INTO data_exports (id, user_id)
+        VALUES ($1, $2)
+        RETURNING id, status as "status: DataExportStatus", expires_at, created_at, completed_at
+        "#,
+        id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(export)
+}
+
+/// The export still being built for this user, if any
+#[catch_error]
+pub async fn fetch_active_data_export(pool: &PgPool, user_id: Uuid) -> Result<Option<DataExport>> {
+    let export: Option<DataExport> = query_as!(
+        DataExport,
+        r#"
+        SELECT id, status as "status: DataExportStatus", expires_at, created_at, completed_at
+        FROM data_exports
+        WHERE user_id = $1 AND status IN ('pending', 'running')
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(export)
+}
+
+#[catch_error]
+pub async fn list_data_exports(pool: &PgPool, user_id: Uuid) -> Result<Vec<DataExport>> {
+    let exports: Vec<DataExport> = query_as!(
+        DataExport,
+        r#"
+        SELECT id, status as "status: DataExportStatus", expires_at, created_at, completed_at
+        FROM data_exports
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(exports)
+}
+
+#[catch_error]
+pub async fn fetch_export_file(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<ExportFile>> {
+    let file: Option<ExportFile> = query_as!(
+        ExportFile,
+        r#"
+        SELECT status as "status: DataExportStatus", file_path, expires_at
+        FROM data_exports
+        WHERE id = $1 AND user_id = $2
+        "#,
+        id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(file)
+}
+
+#[catch_error]
+pub async fn set_export_running(pool: &PgPool, id: Uuid) -> Result<()> {
+    query!(
+        r#"
+        UPDATE data_exports
+        SET status = 'running'
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn set_export_ready(
+    pool: &PgPool,
+    id: Uuid,
+    file_path: &str,
+    expires_at: OffsetDateTime,
+) -> Result<()> {
+    query!(
+        r#"
+        UPDATE data_exports
+        SET status = 'ready', file_path = $1, expires_at = $2, completed_at = CURRENT_TIMESTAMP
+        WHERE id = $3
+        "#,
+        file_path,
+        expires_at,
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn set_export_failed(pool: &PgPool, id: Uuid, error: &str) -> Result<()> {
+    query!(
+        r#"
+        UPDATE data_exports
+        SET status = 'failed', error = $1, completed_at = CURRENT_TIMESTAMP
+        WHERE id = $2
+        "#,
+        error,
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[catch_error]
+pub async fn list_export_files(pool: &PgPool, user_id: Uuid) -> Result<Vec<String>> {
+    let rows = query!(
+        r#"
+        SELECT file_path as "file_path!"
+        FROM data_exports
+        WHERE user_id = $1 AND file_path IS NOT NULL
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.file_path).collect())
+}
+
+/// Jobs don't survive a restart, the user can simply request a new export
+#[catch_error]
+pub async fn fail_interrupted_exports(pool: &PgPool) -> Result<()> {
+    query!(
+        r#"
+        UPDATE data_exports
+        SET status = 'failed', error = 'interrupted', completed_at = CURRENT_TIMESTAMP
+        WHERE status IN ('pending', 'running')
+        "#
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Forgets the file of every expired export and returns the paths to remove from disk
+#[catch_error]
+pub async fn take_expired_export_files(pool: &PgPool) -> Result<Vec<String>> {
+    let rows = query!(
+        r#"
+        UPDATE data_exports
+        SET file_path = NULL
+        WHERE expires_at < CURRENT_TIMESTAMP AND file_path IS NOT NULL
+        RETURNING file_path as "file_path!"
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.file_path).collect())
+}
+
+// The export queries cast dates, uuids and json to text
+// because `rows_to_dataframe` only keeps text, numbers and booleans.
+
+#[catch_error]
+pub async fn export_user_rows(pool: &PgPool, user_id: Uuid) -> Result<Vec<PgRow>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            id::text, email, role::text, attributes::text, disabled, password_reset_required,
+            preferred_language_id, avatar, created_at::text
+        FROM users
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[catch_error]
+pub async fn export_listing_rows(pool: &PgPool, user_id: Uuid) -> Result<Vec<PgRow>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            id::text, category_id, title, description, status, featured, views_count,
+            expires_at::text, created_at::text, updated_at::text
+        FROM listings
+        WHERE user_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[catch_error]
+pub async fn export_listing_field_value_rows(pool: &PgPool, user_id: Uuid) -> Result<Vec<PgRow>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            v.listing_id::text, v.field_id, v.value_text, v.value_integer, v.value_decimal,
+            v.value_boolean, v.value_date::text, v.value_datetime::text, v.value_json::text
+        FROM listing_field_values v
+        JOIN listings l ON l.id = v.listing_id
+        WHERE l.user_id = $1
+        ORDER BY l.created_at, v.field_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Sessions are stateless JWTs, so the security related records stand in for them:
+/// issued tokens (without their hashes) and impersonation sessions by admins.
+#[catch_error]
+pub async fn export_session_rows(pool: &PgPool, user_id: Uuid) -> Result<Vec<PgRow>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT purpose::text AS kind, created_at::text, expires_at::text, consumed_at::text AS ended_at
+        FROM user_tokens
+        WHERE email = (SELECT email FROM users WHERE id = $1)
+        UNION ALL
+        SELECT action AS kind, created_at::text, NULL, NULL
+        FROM admin_actions
+        WHERE user_id = $1 AND action IN ('impersonate', 'stop_impersonation')
+        ORDER BY created_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -15,10 +15,22 @@ pub const SECRET_KEY: &str = "your-secret-key";
 pub struct Claims {
     pub sub: String, // Subject (e.g., user ID)
     pub exp: usize,  // Expiration time
+    // Id of the super admin who is impersonating the subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imp: Option<String>,
 }
 
 // Generate a JWT
 pub fn create_jwt(sub: &str, duration: usize) -> Result<String> {
+    encode_claims(sub, None, duration)
+}
+
+// Generate a JWT for a super admin acting as another user
+pub fn create_impersonation_jwt(sub: &str, impersonator: &str, duration: usize) -> Result<String> {
+    encode_claims(sub, Some(impersonator.to_string()), duration)
+}
+
+fn encode_claims(sub: &str, imp: Option<String>, duration: usize) -> Result<String> {
     let expiration = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map_err(|_| SamError::Any)?
@@ -28,6 +40,7 @@ pub fn create_jwt(sub: &str, duration: usize) -> Result<String> {
     let claims = Claims {
         sub: sub.to_string(),
         exp: expiration,
+        imp,
     };
 
     encode(
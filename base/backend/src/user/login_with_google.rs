@@ -1,5 +1,5 @@
 use axum::{
-    extract::Query,
+    extract::{Query, State},
     response::{Html, Redirect},
     routing::get,
     Router,
@@ -12,6 +12,8 @@ use reqwest::{Client, Url};
 use sam_error::SamError;
 use std::{collections::HashMap, net::SocketAddr};
 
+use crate::{config::Config, AppState};
+
 type SamClient = oauth2::Client<
     oauth2::StandardErrorResponse<oauth2::basic::BasicErrorResponseType>,
     oauth2::StandardTokenResponse<oauth2::EmptyExtraTokenFields, oauth2::basic::BasicTokenType>,
@@ -28,22 +30,11 @@ type SamClient = oauth2::Client<
     oauth2::EndpointSet,
 >;
 
-fn Oauth2Client() -> Result<SamClient, SamError> {
-    let dev_host = dotenvy::var("DEV_HOST")
-        .map_err(|_| SamError::MissingEnviromentVariable("DEV_HOST".to_string()))?;
-    let dist_host = dotenvy::var("HOST")
-        .map_err(|_| SamError::MissingEnviromentVariable("HOST".to_string()))?;
-    let host = match cfg!(debug_assertions) {
-        true => dev_host,
-        false => dist_host,
-    };
-    let client_id = dotenvy::var("GOOGLE_CLIENT_ID")
-        .map_err(|_| SamError::MissingEnviromentVariable("GOOGLE_CLIENT_ID".to_string()))?;
-    let secret = dotenvy::var("GOOGLE_CLIENT_SECRET")
-        .map_err(|_| SamError::MissingEnviromentVariable("GOOGLE_CLIENT_SECRET".to_string()))?;
+fn Oauth2Client(config: &Config) -> Result<SamClient, SamError> {
+    let google = config.google()?;
 
-    let google_client_id: ClientId = ClientId::new(client_id);
-    let google_client_secret: ClientSecret = ClientSecret::new(secret);
+    let google_client_id: ClientId = ClientId::new(google.client_id.clone());
+    let google_client_secret: ClientSecret = ClientSecret::new(google.client_secret.clone());
     let auth_url = AuthUrl::new("https://accounts.google.com/o/oauth2/auth".to_string())
         .expect("Invalid authorization endpoint URL");
     let token_url: TokenUrl = TokenUrl::new("https://oauth2.googleapis.com/token".to_string())
@@ -51,7 +42,7 @@ fn Oauth2Client() -> Result<SamClient, SamError> {
 
     // You should have added this url in your google cloud console
     // Navigate to: https://console.cloud.google.com/ > "APIs & Services" > "Credentials" > "Authorized redirect URIs"
-    let redir_url = host + "/users/login/google/callback";
+    let redir_url = format!("{}/users/login/google/callback", config.host);
     let redirect_url: RedirectUrl = RedirectUrl::new(redir_url).expect("Invalid redirect URL");
 
     let client = BasicClient::new(google_client_id)
@@ -63,8 +54,8 @@ fn Oauth2Client() -> Result<SamClient, SamError> {
 }
 
 // Handler to start the OAuth2 login process
-pub async fn handler() -> Redirect {
-    match Oauth2Client() {
+pub async fn handler(State(state): State<AppState>) -> Redirect {
+    match Oauth2Client(&state.config) {
         Ok(client) => {
             // Generate a PKCE challenge.
             let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -88,8 +79,11 @@ pub async fn handler() -> Redirect {
 // TODO:
 // This handler should return Response
 // Handler for the OAuth2 callback
-pub async fn callback(Query(params): Query<HashMap<String, String>>) -> Html<String> {
-    match Oauth2Client() {
+pub async fn callback(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Html<String> {
+    match Oauth2Client(&state.config) {
         Ok(client) => {
             let http_client = reqwest::ClientBuilder::new()
                 // Following redirects opens the client up to SSRF vulnerabilities.
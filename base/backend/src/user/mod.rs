@@ -5,17 +5,28 @@ use shared::user::LoginUser;
 use sqlx::FromRow;
 use time::OffsetDateTime;
 
-pub use auth::auth_middleware;
+pub use admin_routes::admin_routes;
+pub use auth::{auth_middleware, optional_auth_middleware};
+pub use data_export::cleanup_data_exports;
+pub use profile_routes::profile_routes;
+pub use user_db::fetch_user_by_id;
 pub use user_routes::user_routes;
 
 //delete later
 pub use jwt::Claims;
 
+mod admin_db;
+mod admin_routes;
 mod auth;
 mod cookie;
+mod data_export;
+mod export_db;
 mod jwt;
 mod login_with_google;
 mod password;
+mod profile_db;
+mod profile_routes;
+mod token;
 mod user_db;
 mod user_emails;
 mod user_routes;
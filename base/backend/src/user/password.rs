@@ -1,10 +1,36 @@
-use crate::error::Result;
+use std::collections::BTreeMap;
+
+use crate::{
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+};
 use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
+use axum::response::Response;
 use rand::rngs::OsRng;
 use sam_error::SamError;
+use sam_util::validators::{PasswordError, PasswordPolicy};
+use shared::error_code;
+
+/// The policy every new password has to satisfy
+pub fn password_policy() -> PasswordPolicy {
+    PasswordPolicy {
+        min_length: 8,
+        ..Default::default()
+    }
+}
+
+/// # The failed rules of a password as an error response
+/// The rules are the `json` for the frontend to localize, their messages are the errors of `field`.
+pub fn password_errors_response(field: &str, errors: Vec<PasswordError>) -> Response {
+    let messages = errors.iter().map(|error| error.to_string()).collect();
+    UserResponse::with_error_json_and_code(errors, 400)
+        .code(error_code::INVALID_PASSWORD)
+        .field_errors(BTreeMap::from([(field.to_string(), messages)]))
+        .into_response()
+}
 
 //pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
 pub fn hash_password(password: impl Into<String>) -> Result<String> {
@@ -0,0 +1,290 @@
+use super::admin_db::user_snapshot;
+use crate::{
+    audit::{add_audit_entry, AuditActor, AuditRecord},
+    error::Result,
+};
+use sam_error::SamError;
+use sam_proc_macros::catch_error;
+use shared::user::{Profile, UserRole};
+use sqlx::{query, query_as, PgPool};
+use uuid::Uuid;
+
+#[catch_error]
+pub async fn fetch_profile(pool: &PgPool, user_id: Uuid) -> Result<Profile> {
+    let profile: Profile = query_as!(
+        Profile,
+        r#"
+        SELECT
+            u.id,
+            u.email,
+            u.role as "role: UserRole",
+            u.preferred_language_id,
+            u.avatar,
+            p.new_email as "pending_email?",
+            u.created_at
+        FROM users u
+        LEFT JOIN pending_email_changes p ON p.user_id = u.id
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(profile)
+}
+
+/// Keeps a single pending change per user, a new request replaces the old one
+#[catch_error]
+pub async fn add_pending_email_change(pool: &PgPool, user_id: Uuid, new_email: &str) -> Result<()> {
+    query!(
+        r#"
+        INSERT INTO pending_email_changes (user_id, new_email)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id)
+        DO UPDATE SET new_email = EXCLUDED.new_email, created_at = CURRENT_TIMESTAMP
+        "#,
+        user_id,
+        new_email
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Moves the pending email to the user row.
+/// Returns the old email.
+#[catch_error]
+pub async fn confirm_email_change(
+    pool: &PgPool,
+    actor: &AuditActor,
+    new_email: &str,
+) -> Result<String> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+
+    let pending = query!(
+        r#"
+        DELETE FROM pending_email_changes
+        WHERE new_email = $1
+        RETURNING user_id
+        "#,
+        new_email
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(SamError::InvalidToken)?;
+    let before = user_snapshot(&mut tx, pending.user_id).await?;
+
+    let user = query!(
+        r#"
+        UPDATE users u
+        SET email = $1
+        FROM users old
+        WHERE u.id = $2 AND old.id = u.id
+        RETURNING old.email
+        "#,
+        new_email,
+        pending.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // The link is opened without a session, the owner of the account is the actor
+    let actor = AuditActor {
+        user_id: Some(pending.user_id),
+        email: Some(user.email.clone()),
+        ..actor.clone()
+    };
+    let after = user_snapshot(&mut tx, pending.user_id).await?;
+    add_audit_entry(
+        &mut tx,
+        &actor,
+        AuditRecord::new("change_email", "user", pending.user_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(user.email)
+}
+
+#[catch_error(skip(hash))]
+pub async fn update_password(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    hash: String,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+    let before = user_snapshot(&mut tx, user_id).await?;
+
+    query!(
+        r#"
+        UPDATE users
+        SET password = $1
+        WHERE id = $2
+        "#,
+        hash,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // The snapshots leave the password out, the entry only says it changed
+    let after = user_snapshot(&mut tx, user_id).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("change_password", "user", user_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Only active languages can be chosen, `None` clears the preference
+#[catch_error]
+pub async fn set_preferred_language(
+    pool: &PgPool,
+    user_id: Uuid,
+    language_id: Option<i32>,
+) -> Result<()> {
+    let result = query!(
+        r#"
+        UPDATE users
+        SET preferred_language_id = $1
+        WHERE id = $2
+            AND ($1::int IS NULL OR EXISTS (SELECT 1 FROM languages WHERE id = $1 AND active))
+        "#,
+        language_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(SamError::NotFound("Language".to_string()));
+    }
+    Ok(())
+}
+
+/// Returns the previous avatar path so the old file can be removed
+#[catch_error]
+pub async fn set_avatar(pool: &PgPool, user_id: Uuid, avatar: &str) -> Result<Option<String>> {
+    let row = query!(
+        r#"
+        UPDATE users u
+        SET avatar = $1
+        FROM users old
+        WHERE u.id = $2 AND old.id = u.id
+        RETURNING old.avatar
+        "#,
+        avatar,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.avatar)
+}
+
+/// # Delete an account (GDPR)
+/// With `anonymize_listings` the listings stay published without an owner,
+/// otherwise they are removed with the account (ON DELETE CASCADE).
+/// Tokens and personal details kept in the admin trail and the audit log are erased too.
+#[catch_error]
+pub async fn delete_account(
+    pool: &PgPool,
+    actor: &AuditActor,
+    user_id: Uuid,
+    email: &str,
+    anonymize_listings: bool,
+) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
+
+    if anonymize_listings {
+        query!(
+            r#"
+            UPDATE listings
+            SET user_id = NULL
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    query!(
+        r#"
+        DELETE FROM user_tokens
+        WHERE email = $1
+        "#,
+        email
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Keep the trail of what happened but without personal data
+    query!(
+        r#"
+        UPDATE admin_actions
+        SET details = '{}'
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!(
+        r#"
+        UPDATE audit_log
+        SET before = NULL, after = NULL, diff = '{}'
+        WHERE resource_type = 'user' AND resource_id = $1
+        "#,
+        user_id.to_string()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!(
+        r#"
+        UPDATE audit_log
+        SET actor_email = NULL
+        WHERE actor_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Only that the account was deleted is kept, `actor_id` is cleared with the user
+    let actor = AuditActor {
+        email: None,
+        ..actor.clone()
+    };
+    add_audit_entry(
+        &mut tx,
+        &actor,
+        AuditRecord::new("delete_account", "user", user_id),
+    )
+    .await?;
+
+    let result = query!(
+        r#"
+        DELETE FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
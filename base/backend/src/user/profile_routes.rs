@@ -0,0 +1,379 @@
+// Routes for the logged in user to manage their own account
+// GET	/users/profile	Get the profile
+// PUT	/users/profile/email	Request an email change (confirmed from the new address)
+// GET	/users/profile/confirm-email	Confirm the new email
+// PUT	/users/profile/password	Change the password (requires the current one)
+// PUT	/users/profile/language	Choose the preferred language
+// POST	/users/profile/avatar	Upload an avatar (multipart, field `avatar`)
+// DELETE	/users/profile	Delete the account (requires the password)
+// POST	/users/profile/exports	Request a GDPR export of all the user data
+// GET	/users/profile/exports	List the requested exports
+// GET	/users/profile/exports/{id}/download	Download a finished export (ZIP)
+
+use std::{path::Path as FsPath, sync::Arc};
+
+use axum::{
+    extract::{rejection::JsonRejection, DefaultBodyLimit, Multipart, Path, Query, State},
+    http::header,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Extension, Json, Router,
+};
+use axum_extra::extract::CookieJar;
+use sam_error::SamError;
+use sam_util::validators::validate_email;
+use serde::Deserialize;
+use shared::user::{
+    ChangeEmailPayload, ChangePasswordPayload, DataExportStatus, DeleteAccountPayload,
+    PreferredLanguagePayload, UserInfo, UserRole,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{
+    auth_middleware,
+    cookie::create_cookie,
+    data_export::spawn_export,
+    export_db::{
+        add_data_export, fetch_active_data_export, fetch_export_file, list_data_exports,
+        list_export_files,
+    },
+    password::{hash_password, password_errors_response, password_policy, verify_password},
+    profile_db::{
+        add_pending_email_change, confirm_email_change, delete_account, fetch_profile, set_avatar,
+        set_preferred_language, update_password,
+    },
+    token::{consume_token, issue_token, TokenPurpose},
+    user_db::{fetch_hash_user_by_email, invalidate_user_tokens, user_exists},
+    user_emails::{generate_confirm_email_change_body, send_verification_email},
+};
+use crate::{
+    audit::AuditActor,
+    config::AccountDeletionPolicy,
+    error::Result,
+    response::{IntoUserResponse, UserResponse},
+    utils::{EXPORTS_DIR, UPLOADS_DIR},
+    AppState,
+};
+
+const AVATAR_MAX_BYTES: usize = 2 * 1024 * 1024;
+// Leaves room for the multipart boundaries and headers
+const AVATAR_BODY_LIMIT: usize = AVATAR_MAX_BYTES + 64 * 1024;
+
+pub fn profile_routes(state: AppState) -> Router<AppState> {
+    let profile = Router::new()
+        .route(
+            "/users/profile",
+            get(get_profile_handler).delete(delete_account_handler),
+        )
+        .route("/users/profile/email", put(change_email_handler))
+        .route("/users/profile/password", put(change_password_handler))
+        .route("/users/profile/language", put(set_language_handler))
+        .route(
+            "/users/profile/avatar",
+            post(upload_avatar_handler).layer(DefaultBodyLimit::max(AVATAR_BODY_LIMIT)),
+        )
+        .route(
+            "/users/profile/exports",
+            get(list_exports_handler).post(request_export_handler),
+        )
+        .route(
+            "/users/profile/exports/{id}/download",
+            get(download_export_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
+    Router::new()
+        .merge(profile)
+        .route("/users/profile/confirm-email", get(confirm_email_handler))
+        .with_state(state)
+}
+
+/// Sensitive changes need the current password, a stolen session alone is not enough
+async fn check_password(state: &AppState, email: &str, password: &str) -> Result<()> {
+    let user = fetch_hash_user_by_email(&state.pool, email).await?;
+    verify_password(password, &user.password)
+        .map(|_| ())
+        .map_err(|_| SamError::InvalidPassword("the current password is wrong".to_string()))
+}
+
+fn user_id(user: &UserInfo) -> Result<Uuid> {
+    Uuid::parse_str(&user.id).map_err(|err| sam_error::any_with_log!(err.to_string()))
+}
+
+async fn get_profile_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+) -> Result<Response> {
+    let profile = fetch_profile(&state.pool, user_id(&user)?).await?;
+    let res = UserResponse::with_json(profile).into_response();
+    Ok(res)
+}
+
+async fn change_email_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+    payload: Result<Json<ChangeEmailPayload>, JsonRejection>,
+) -> Result<Response> {
+    let ChangeEmailPayload {
+        new_email,
+        password,
+    } = payload?.0;
+    let new_email = new_email.trim().to_string();
+    validate_email(&new_email)?;
+    check_password(&state, &user.email, &password).await?;
+
+    if new_email == user.email || user_exists(&state.pool, &new_email).await? {
+        return Err(SamError::EmailTaken);
+    }
+
+    // The email only changes once the link sent to the new address is opened
+    add_pending_email_change(&state.pool, user_id(&user)?, &new_email).await?;
+    let token = issue_token(&state.pool, &new_email, TokenPurpose::EmailChange).await?;
+    let body = generate_confirm_email_change_body(&state.config, token)?;
+    send_verification_email(&state.config, body, "Confirm Your New Email", &new_email).await?;
+
+    let res = UserResponse::with_success("Check your new email box to confirm the change")
+        .into_response();
+    Ok(res)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailParams {
+    token: String,
+}
+
+async fn confirm_email_handler(
+    Query(params): Query<ConfirmEmailParams>,
+    State(state): State<AppState>,
+    audit_actor: AuditActor,
+) -> Result<Response> {
+    let new_email = consume_token(&state.pool, &params.token, TokenPurpose::EmailChange).await?;
+    let old_email = confirm_email_change(&state.pool, &audit_actor, &new_email).await?;
+
+    // Reset links sent to the old address must not work anymore
+    invalidate_user_tokens(&state.pool, &old_email, TokenPurpose::PasswordReset).await?;
+
+    // Let the owner of the old address know in case the change wasn't made by them
+    let body = format!("Your email was changed to {}.", new_email);
+    send_verification_email(&state.config, body, "Your Email Was Changed", &old_email).await?;
+
+    let res = UserResponse::with_success("Email changed successfully").into_response();
+    Ok(res)
+}
+
+async fn change_password_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    payload: Result<Json<ChangePasswordPayload>, JsonRejection>,
+) -> Result<Response> {
+    let ChangePasswordPayload {
+        current_password,
+        new_password,
+    } = payload?.0;
+    check_password(&state, &user.email, &current_password).await?;
+
+    if let Err(errors) = password_policy().validate(&new_password) {
+        return Ok(password_errors_response("new_password", errors));
+    }
+
+    let hash = hash_password(new_password)?;
+    update_password(&state.pool, &audit_actor, user_id(&user)?, hash).await?;
+    invalidate_user_tokens(&state.pool, &user.email, TokenPurpose::PasswordReset).await?;
+
+    let res = UserResponse::with_success("Password changed successfully").into_response();
+    Ok(res)
+}
+
+async fn set_language_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+    payload: Result<Json<PreferredLanguagePayload>, JsonRejection>,
+) -> Result<Response> {
+    let PreferredLanguagePayload { language_id } = payload?.0;
+    set_preferred_language(&state.pool, user_id(&user)?, language_id).await?;
+    let res = UserResponse::with_success("Language saved successfully").into_response();
+    Ok(res)
+}
+
+/// Detects the image type from its first bytes instead of trusting the client
+fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("jpg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("webp"),
+        _ => None,
+    }
+}
+
+async fn upload_avatar_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    let mut avatar = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| SamError::InvalidUpload(err.body_text()))?
+    {
+        if field.name() == Some("avatar") {
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|err| SamError::InvalidUpload(err.body_text()))?;
+            avatar = Some(bytes);
+            break;
+        }
+    }
+    let bytes = avatar.ok_or(SamError::InvalidUpload("missing avatar".to_string()))?;
+    if bytes.len() > AVATAR_MAX_BYTES {
+        return Err(SamError::InvalidUpload(
+            "the image must be smaller than 2 MB".to_string(),
+        ));
+    }
+    let extension = image_extension(&bytes).ok_or(SamError::InvalidUpload(
+        "only png, jpg, gif and webp images are allowed".to_string(),
+    ))?;
+
+    // A new name for every upload so browsers don't show a cached image
+    let id = user_id(&user)?;
+    let file_name = format!("{}-{}.{}", id.simple(), Uuid::new_v4().simple(), extension);
+    let dir = FsPath::new(UPLOADS_DIR).join("avatars");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+    tokio::fs::write(dir.join(&file_name), &bytes)
+        .await
+        .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+
+    let path = format!("/uploads/avatars/{}", file_name);
+    if let Some(old) = set_avatar(&state.pool, id, &path).await? {
+        remove_upload(&old).await;
+    }
+
+    let res = UserResponse::with_json(path).into_response();
+    Ok(res)
+}
+
+/// Removes a file referenced by its public `/uploads/...` path.
+/// A missing file is not an error.
+pub async fn remove_upload(public_path: &str) {
+    if let Some(relative) = public_path.strip_prefix("/uploads/") {
+        if let Err(err) = tokio::fs::remove_file(FsPath::new(UPLOADS_DIR).join(relative)).await {
+            tracing::warn!("Failed to remove {}: {}", public_path, err);
+        }
+    }
+}
+
+async fn delete_account_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+    audit_actor: AuditActor,
+    cookies: CookieJar,
+    payload: Result<Json<DeleteAccountPayload>, JsonRejection>,
+) -> Result<Response> {
+    let DeleteAccountPayload { password } = payload?.0;
+    check_password(&state, &user.email, &password).await?;
+
+    // Keep at least one account able to manage the site
+    if user.role == UserRole::SuperAdmin {
+        return Err(SamError::Forbidden(
+            "Super admin accounts can't be deleted".to_string(),
+        ));
+    }
+
+    let id = user_id(&user)?;
+    let profile = fetch_profile(&state.pool, id).await?;
+    let export_files = list_export_files(&state.pool, id).await?;
+    let anonymize_listings =
+        state.config.account_deletion_policy == AccountDeletionPolicy::Anonymize;
+    delete_account(
+        &state.pool,
+        &audit_actor,
+        id,
+        &user.email,
+        anonymize_listings,
+    )
+    .await?;
+    if let Some(avatar) = profile.avatar {
+        remove_upload(&avatar).await;
+    }
+    for file in export_files {
+        if let Err(err) = tokio::fs::remove_file(FsPath::new(EXPORTS_DIR).join(&file)).await {
+            tracing::warn!("Failed to remove export {}: {}", file, err);
+        }
+    }
+
+    // Log out
+    let expired_cookie = create_cookie("token".to_string(), "".to_string(), 0);
+    let cookies = cookies.add(expired_cookie);
+    let res = UserResponse::with_success("Account deleted successfully").into_response();
+    Ok((cookies, res).into_response())
+}
+
+async fn request_export_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+) -> Result<Response> {
+    let id = user_id(&user)?;
+
+    // One export at a time, asking again returns the one being built
+    if let Some(export) = fetch_active_data_export(&state.pool, id).await? {
+        return Ok(UserResponse::with_json_and_code(export, 202).into_response());
+    }
+
+    let export = add_data_export(&state.pool, Uuid::new_v4(), id).await?;
+    spawn_export(state.pool.clone(), state.config.clone(), export.id, id);
+
+    let res = UserResponse::with_json_and_code(export, 202).into_response();
+    Ok(res)
+}
+
+async fn list_exports_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+) -> Result<Response> {
+    let exports = list_data_exports(&state.pool, user_id(&user)?).await?;
+    let res = UserResponse::with_json(exports).into_response();
+    Ok(res)
+}
+
+async fn download_export_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<Arc<UserInfo>>,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    // Other users' exports look like missing ones
+    let export = fetch_export_file(&state.pool, id, user_id(&user)?)
+        .await?
+        .ok_or(SamError::NotFound("Export".to_string()))?;
+    if export.status != DataExportStatus::Ready {
+        return Err(SamError::NotFound("Export".to_string()));
+    }
+    let expired = export
+        .expires_at
+        .is_none_or(|expires_at| expires_at < OffsetDateTime::now_utc());
+    let file_path = match export.file_path {
+        Some(file_path) if !expired => file_path,
+        _ => return Err(SamError::Forbidden("This export has expired".to_string())),
+    };
+
+    let bytes = tokio::fs::read(FsPath::new(EXPORTS_DIR).join(file_path))
+        .await
+        .map_err(|err| sam_error::any_with_log!(err.to_string()))?;
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"data-export-{}.zip\"", id.simple()),
+        ),
+    ];
+    Ok((headers, bytes).into_response())
+}
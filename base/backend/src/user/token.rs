@@ -0,0 +1,116 @@
+use rand::{rngs::OsRng, RngCore};
+use sam_error::SamError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use subtle::ConstantTimeEq;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::user_db::{
+    add_user_token, consume_user_token, fetch_user_token, invalidate_user_tokens,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "user_token_purpose", rename_all = "snake_case")]
+pub enum TokenPurpose {
+    EmailVerification,
+    PasswordReset,
+    EmailChange,
+}
+
+impl TokenPurpose {
+    /// How long a freshly issued token stays valid
+    pub fn ttl(&self) -> Duration {
+        match self {
+            TokenPurpose::EmailVerification | TokenPurpose::EmailChange => Duration::hours(24),
+            TokenPurpose::PasswordReset => Duration::hours(1),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UserToken {
+    pub id: Uuid,
+    pub email: String,
+    pub purpose: TokenPurpose,
+    pub token_hash: String,
+    pub expires_at: OffsetDateTime,
+    pub consumed_at: Option<OffsetDateTime>,
+}
+
+/// # Issue a new single-use token
+/// Any outstanding token with the same purpose for this email is invalidated first.
+/// The returned string has the form `<id>.<secret>` and is the only place the secret exists,
+/// the database keeps a SHA-256 hash of it.
+pub async fn issue_token(pool: &PgPool, email: &str, purpose: TokenPurpose) -> Result<String> {
+    invalidate_user_tokens(pool, email, purpose).await?;
+
+    let id = Uuid::new_v4();
+    let secret = generate_secret();
+    let expires_at = OffsetDateTime::now_utc() + purpose.ttl();
+    add_user_token(pool, id, email, purpose, hash_secret(&secret), expires_at).await?;
+
+    Ok(format!("{}.{}", id.simple(), secret))
+}
+
+/// # Check a token without consuming it
+/// Returns the email the token was issued for.
+/// An expired token returns `SamError::ExpiredToken(email)` so callers can still
+/// find out who it belonged to (e.g. for resending a verification email).
+pub async fn check_token(pool: &PgPool, raw_token: &str, purpose: TokenPurpose) -> Result<String> {
+    let token = find_token(pool, raw_token, purpose).await?;
+    if token.expires_at < OffsetDateTime::now_utc() {
+        return Err(SamError::ExpiredToken(token.email));
+    }
+    Ok(token.email)
+}
+
+/// # Validate and consume a token
+/// A token can only be consumed once, even when two requests race for it.
+pub async fn consume_token(
+    pool: &PgPool,
+    raw_token: &str,
+    purpose: TokenPurpose,
+) -> Result<String> {
+    let token = find_token(pool, raw_token, purpose).await?;
+    if token.expires_at < OffsetDateTime::now_utc() {
+        return Err(SamError::ExpiredToken(token.email));
+    }
+    if !consume_user_token(pool, token.id).await? {
+        return Err(SamError::InvalidToken);
+    }
+    Ok(token.email)
+}
+
+async fn find_token(pool: &PgPool, raw_token: &str, purpose: TokenPurpose) -> Result<UserToken> {
+    let (id, secret) = raw_token.split_once('.').ok_or(SamError::InvalidToken)?;
+    let id = Uuid::parse_str(id).map_err(|_| SamError::InvalidToken)?;
+
+    let token = fetch_user_token(pool, id, purpose)
+        .await?
+        .ok_or(SamError::InvalidToken)?;
+
+    let hash = hash_secret(secret);
+    let matches: bool = hash.as_bytes().ct_eq(token.token_hash.as_bytes()).into();
+    if !matches || token.consumed_at.is_some() {
+        return Err(SamError::InvalidToken);
+    }
+    Ok(token)
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+fn hash_secret(secret: &str) -> String {
+    to_hex(&Sha256::digest(secret.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
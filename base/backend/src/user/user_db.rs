@@ -1,6 +1,7 @@
-use super::jwt::create_jwt;
-use super::user_routes::{ResetPasswordPayload, TokenInfo};
-use super::{jwt::validate_jwt, LoginUser};
+use super::admin_db::user_snapshot;
+use super::token::{TokenPurpose, UserToken};
+use super::LoginUser;
+use crate::audit::{add_audit_entry, snapshot, AuditActor, AuditRecord};
 use crate::error::*;
 use sam_error::any_with_log;
 use sam_error::SamError;
@@ -10,32 +11,39 @@ use shared::user::{HashUser, UserInfo};
 use sqlx::{query, query_as, PgPool, Postgres};
 use std::error::Error;
 use std::sync::Arc;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
-#[catch_error]
-pub async fn add_pending_user(
-    pool: &PgPool,
-    user: LoginUser,
-    verification_token: String,
-) -> Result<()> {
+#[catch_error(skip(user))]
+pub async fn add_pending_user(pool: &PgPool, actor: &AuditActor, user: LoginUser) -> Result<()> {
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await?;
     let id = Uuid::new_v4();
     query!(
         r#"
-        INSERT INTO pending_users (id,email, password, verification_token) 
-        VALUES ($1, $2, $3,$4)
+        INSERT INTO pending_users (id,email, password) 
+        VALUES ($1, $2, $3)
         "#,
         id,
         user.email,
-        user.password,
-        verification_token
+        user.password
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
+
+    let after = snapshot(&mut tx, "pending_users", &id.to_string()).await?;
+    add_audit_entry(
+        &mut tx,
+        actor,
+        AuditRecord::new("signup", "pending_user", id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
     Ok(())
 }
 
-#[catch_error]
-pub async fn move_pending_user(pool: &PgPool, user: HashUser) -> Result<()> {
+#[catch_error(skip(user))]
+pub async fn move_pending_user(pool: &PgPool, actor: &AuditActor, user: HashUser) -> Result<()> {
     // Start a transaction
     let mut transaction = pool.begin().await?;
     let id = user.id;
@@ -76,6 +84,14 @@ pub async fn move_pending_user(pool: &PgPool, user: HashUser) -> Result<()> {
         return Err(err.into());
     }
 
+    let after = user_snapshot(&mut transaction, id).await?;
+    add_audit_entry(
+        &mut transaction,
+        actor,
+        AuditRecord::new("verify_email", "user", id).after(after),
+    )
+    .await?;
+
     // Commit the transaction if everything succeeds
     transaction.commit().await?;
     Ok(())
@@ -89,7 +105,9 @@ where
     let user: UserInfo = query_as!(
         UserInfo,
         r#"
-        SELECT id, email, role as "role: UserRole", attributes, created_at
+        SELECT id, email, role as "role: UserRole", attributes, disabled, password_reset_required, created_at,
+            effective_roles(user_role_names(id)) as "roles!",
+            role_permissions(user_role_names(id)) as "permissions!"
         FROM users
         WHERE email = $1
         "#,
@@ -126,7 +144,9 @@ pub async fn fetch_user_by_id(pool: &PgPool, id: Uuid) -> Result<UserInfo> {
     let user: UserInfo = query_as!(
         UserInfo,
         r#"
-        SELECT id, email, role as "role: UserRole", attributes, created_at
+        SELECT id, email, role as "role: UserRole", attributes, disabled, password_reset_required, created_at,
+            effective_roles(user_role_names(id)) as "roles!",
+            role_permissions(user_role_names(id)) as "permissions!"
         FROM users
         WHERE id = $1
         "#,
@@ -184,85 +204,100 @@ where
     Ok(user)
 }
 
-#[catch_error]
-pub async fn reset_email_verification_token<S>(
+#[catch_error(skip(token_hash))]
+pub async fn add_user_token(
     pool: &PgPool,
-    verification_token: S,
-    email: S,
-) -> Result<()>
-where
-    S: Into<String>,
-{
+    id: Uuid,
+    email: &str,
+    purpose: TokenPurpose,
+    token_hash: String,
+    expires_at: OffsetDateTime,
+) -> Result<()> {
     query!(
         r#"
-        UPDATE pending_users
-        SET verification_token = $1
-        WHERE email = $2
+        INSERT INTO user_tokens (id, email, purpose, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
         "#,
-        verification_token.into(),
-        email.into()
+        id,
+        email,
+        purpose as TokenPurpose,
+        token_hash,
+        expires_at
     )
     .execute(pool)
     .await?;
-
     Ok(())
 }
 
 #[catch_error]
-pub async fn add_reset_password_token(pool: &PgPool, email: String, token: String) -> Result<()> {
-    query!(
+pub async fn fetch_user_token(
+    pool: &PgPool,
+    id: Uuid,
+    purpose: TokenPurpose,
+) -> Result<Option<UserToken>> {
+    let token: Option<UserToken> = query_as!(
+        UserToken,
         r#"
-        insert into password_reset_tokens (email,token) 
-        values ($1,$2); 
-    "#,
-        email,
-        token
+        SELECT id, email, purpose as "purpose: TokenPurpose", token_hash, expires_at, consumed_at
+        FROM user_tokens
+        WHERE id = $1 AND purpose = $2
+        "#,
+        id,
+        purpose as TokenPurpose
     )
-    .execute(pool)
+    .fetch_optional(pool)
     .await?;
-    Ok(())
+    Ok(token)
 }
 
+/// Marks the token as consumed.
+/// Returns false if it was already consumed by someone else.
 #[catch_error]
-pub async fn fetch_reset_password_token(pool: &PgPool, token: String) -> Result<TokenInfo> {
-    let token_info: TokenInfo = query_as!(
-        TokenInfo,
+pub async fn consume_user_token(pool: &PgPool, id: Uuid) -> Result<bool> {
+    let result = query!(
         r#"
-        select token, email from password_reset_tokens
-        where token = $1
+        UPDATE user_tokens
+        SET consumed_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND consumed_at IS NULL
         "#,
-        token
+        id
     )
-    .fetch_one(pool)
+    .execute(pool)
     .await?;
-    Ok(token_info)
+    Ok(result.rows_affected() == 1)
 }
 
+/// Invalidates every outstanding token of the given purpose for this email
 #[catch_error]
-pub async fn reset_password(pool: &PgPool, hash: String, email: String) -> Result<()> {
+pub async fn invalidate_user_tokens(
+    pool: &PgPool,
+    email: &str,
+    purpose: TokenPurpose,
+) -> Result<()> {
     query!(
         r#"
-        UPDATE users
-        SET password = $1
-        WHERE email = $2
+        UPDATE user_tokens
+        SET consumed_at = CURRENT_TIMESTAMP
+        WHERE email = $1 AND purpose = $2 AND consumed_at IS NULL
         "#,
-        hash,
-        email
+        email,
+        purpose as TokenPurpose
     )
     .execute(pool)
     .await?;
-
     Ok(())
 }
 
-#[catch_error]
-pub async fn delete_token(pool: &PgPool, token: String) -> Result<()> {
+#[catch_error(skip(hash))]
+pub async fn reset_password(pool: &PgPool, hash: String, email: String) -> Result<()> {
     query!(
         r#"
-        delete from password_reset_tokens
-        WHERE token = $1
+        UPDATE users
+        SET password = $1, password_reset_required = FALSE
+        WHERE email = $2
         "#,
-        token
+        hash,
+        email
     )
     .execute(pool)
     .await?;
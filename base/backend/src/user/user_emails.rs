@@ -1,11 +1,10 @@
 use sam_error::SamError;
 use sam_util::gmail::{send, GmailConfig};
 
-use crate::error::Result;
+use crate::{config::Config, error::Result};
 
-pub fn generate_verify_email_body(verification_token: String) -> Result<String> {
-    let host = dotenvy::var("DEV_HOST")
-        .map_err(|_| SamError::MissingEnviromentVariable("DEV_HOST".to_string()))?;
+pub fn generate_verify_email_body(config: &Config, verification_token: String) -> Result<String> {
+    let host = &config.host;
     let body = format!(
         r#"
         <a href="{}/users/verify-email?token={}">verify your email</a>
@@ -15,9 +14,11 @@ pub fn generate_verify_email_body(verification_token: String) -> Result<String>
     Ok(body)
 }
 
-pub fn generate_forgot_password_body(verification_token: String) -> Result<String> {
-    let host = dotenvy::var("DEV_HOST")
-        .map_err(|_| SamError::MissingEnviromentVariable("DEV_HOST".to_string()))?;
+pub fn generate_forgot_password_body(
+    config: &Config,
+    verification_token: String,
+) -> Result<String> {
+    let host = &config.host;
     let body = format!(
         r#"
         <a href="{}/users/reset-password?token={}">Request a new password</a>
@@ -27,20 +28,45 @@ pub fn generate_forgot_password_body(verification_token: String) -> Result<Strin
     Ok(body)
 }
 
+pub fn generate_confirm_email_change_body(config: &Config, token: String) -> Result<String> {
+    let host = &config.host;
+    let body = format!(
+        r#"
+        <a href="{}/users/profile/confirm-email?token={}">confirm your new email</a>
+        "#,
+        host, token
+    );
+    Ok(body)
+}
+
+pub fn generate_data_export_ready_body(
+    config: &Config,
+    export_id: uuid::Uuid,
+    hours: i64,
+) -> Result<String> {
+    let host = &config.host;
+    let body = format!(
+        r#"
+        <p>Your data export is ready and can be downloaded during the next {} hours.</p>
+        <a href="{}/users/profile/exports/{}/download">download your data</a>
+        "#,
+        hours, host, export_id
+    );
+    Ok(body)
+}
+
 pub async fn send_verification_email(
+    config: &Config,
     body: String,
     subject: impl Into<String>,
     user_email: &str,
 ) -> Result<()> {
-    let email_sender = dotenvy::var("EMAIL_SENDER")
-        .map_err(|_| SamError::MissingEnviromentVariable("EMAIL_SENDER".to_string()))?;
-    let email_sender_password = dotenvy::var("EMAIL_SENDER_PASSWORD")
-        .map_err(|_| SamError::MissingEnviromentVariable("EMAIL_SENDER_PASSWORD".to_string()))?;
+    let email = config.email()?;
 
     let config = GmailConfig {
-        username: email_sender.clone(),
-        password: email_sender_password,
-        from: email_sender,
+        username: email.sender.clone(),
+        password: email.password.clone(),
+        from: email.sender.clone(),
         to: user_email.to_string(),
         subject: subject.into(),
         body,
@@ -2,30 +2,33 @@ use std::sync::Arc;
 
 use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use sam_error::SamError;
-use sam_util::validators::{validate_email, validate_password};
-use shared::{
-    dashboard::{DashNavItem, DashNavItemInfo},
-    user::{UserInfo, UserRole},
-};
+use sam_util::validators::validate_email;
+use shared::user::UserInfo;
 
 use super::{
     cookie::create_cookie,
-    jwt::{create_jwt, validate_jwt},
-    password::{hash_password, verify_password},
+    jwt::create_jwt,
+    password::{hash_password, password_errors_response, password_policy, verify_password},
+    token::{check_token, consume_token, issue_token, TokenPurpose},
     user_db::{
-        add_pending_user, add_reset_password_token, delete_token, fetch_hash_user_by_email,
-        fetch_pending_user, fetch_reset_password_token, fetch_user_by_email, move_pending_user,
-        reset_email_verification_token, reset_password, user_exists,
+        add_pending_user, fetch_hash_user_by_email, fetch_pending_user, fetch_user_by_email,
+        fetch_user_by_id, invalidate_user_tokens, move_pending_user, reset_password, user_exists,
     },
     user_emails::{
         generate_forgot_password_body, generate_verify_email_body, send_verification_email,
     },
-    Claims, LoginUser, LoginUserExt,
+    LoginUser, LoginUserExt,
 };
 use crate::{
+    abac::Authorizer,
+    audit::AuditActor,
+    dashboard::{
+        nav_item_db::{list_nav_items, nav_language},
+        visible_nav_items,
+    },
     error::Result,
     response::{IntoUserResponse, UserResponse},
-    user::auth_middleware,
+    user::{auth::ensure_active, auth_middleware},
     AppState,
 };
 use axum::{
@@ -39,6 +42,7 @@ use axum_extra::extract::CookieJar;
 use sam_proc_macros::catch_error;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use uuid::Uuid;
 
 pub fn user_routes(state: AppState) -> Router<AppState> {
     Router::new()
@@ -54,7 +58,7 @@ pub fn user_routes(state: AppState) -> Router<AppState> {
         .route("/users/login", post(login_user_handler))
         .route(
             "/users/login/google",
-            get(move || super::login_with_google::handler()),
+            get(super::login_with_google::handler),
         )
         .route(
             "/users/login/google/callback",
@@ -78,45 +82,24 @@ pub fn user_routes(state: AppState) -> Router<AppState> {
         .with_state(state)
 }
 
-fn dash_nav_items() -> Vec<DashNavItem> {
-    vec![
-        DashNavItem::new(
-            "Categories".to_string(),
-            "cat".to_string(),
-            "/dashboard/categories".to_string(),
-            vec![UserRole::SuperAdmin],
-        ),
-        DashNavItem::new(
-            "Languages".to_string(),
-            "languages".to_string(),
-            "/dashboard/languages".to_string(),
-            vec![UserRole::SuperAdmin],
-        ),
-        DashNavItem::new(
-            "Fields".to_string(),
-            "fields".to_string(),
-            "/dashboard/fields".to_string(),
-            vec![UserRole::SuperAdmin],
-        ),
-        DashNavItem::new(
-            "Listings".to_string(),
-            "list".to_string(),
-            "/dashboard/settings".to_string(),
-            vec![UserRole::SuperAdmin, UserRole::Admin, UserRole::User],
-        ),
-    ]
+#[derive(Debug, Deserialize)]
+struct NavItemsParams {
+    /// Language code of the labels, the preferred language of the user otherwise
+    lang: Option<String>,
 }
 
 async fn dash_nav_items_handler(
     State(state): State<AppState>,
-    Extension(user): Extension<Arc<UserInfo>>,
+    Query(params): Query<NavItemsParams>,
+    authorizer: Authorizer,
 ) -> Result<Response> {
-    let items = dash_nav_items()
-        .into_iter()
-        .filter(|item| item.required_roles.contains(&user.role))
-        .map(DashNavItemInfo::from)
-        .collect::<Vec<_>>();
-    let res = UserResponse::with_json(items).into_response();
+    let items = list_nav_items(&state.pool).await?;
+    let language_id = match Uuid::parse_str(&authorizer.user().id) {
+        Ok(user_id) => nav_language(&state.pool, user_id, params.lang.as_deref()).await?,
+        Err(_) => None,
+    };
+    let res = UserResponse::with_json(visible_nav_items(&items, language_id, &authorizer))
+        .into_response();
     Ok(res)
 }
 
@@ -130,12 +113,17 @@ async fn check_auth_handler(
 
 async fn add_user_handler(
     State(state): State<AppState>,
+    audit_actor: AuditActor,
     user: Result<Json<LoginUser>, JsonRejection>,
 ) -> Result<Response> {
     // let user = user?.0.hash()?;
     let user = user?.0;
     validate_email(&user.email)?;
-    validate_password(&user.password, 8)?;
+
+    // Report every failed password rule at once
+    if let Err(errors) = password_policy().validate(&user.password) {
+        return Ok(password_errors_response("password", errors));
+    }
 
     // Hash the password
     let user = user.hash()?;
@@ -145,15 +133,21 @@ async fn add_user_handler(
         return Err(sam_error::any_with_log!("User already found...".to_string()));
     }
 
-    // Generate a verification token
-    let verification_token = create_jwt(&email, 24 * 60 * 60)?; // valid for 24 hours
-
     // Add the user to the pending users table until he verifies its email.
-    add_pending_user(&state.pool, user, verification_token.clone()).await?;
+    // Nobody is logged in, the new user is the actor
+    let audit_actor = AuditActor {
+        email: Some(email.clone()),
+        ..audit_actor
+    };
+    add_pending_user(&state.pool, &audit_actor, user).await?;
+
+    // Generate a single-use verification token
+    let verification_token =
+        issue_token(&state.pool, &email, TokenPurpose::EmailVerification).await?;
 
     // Send email for verifying
-    let body = generate_verify_email_body(verification_token)?;
-    send_verification_email(body, "Verify Your Email", &email).await?;
+    let body = generate_verify_email_body(&state.config, verification_token)?;
+    send_verification_email(&state.config, body, "Verify Your Email", &email).await?;
 
     let res = UserResponse::with_success_and_code(
         "User added successfully! The only step left is to check your email and verify it.",
@@ -175,6 +169,10 @@ async fn login_user_handler(
 
     verify_password(password.as_str(), hash.as_str())?;
 
+    // Disabled accounts or accounts waiting for a forced reset can't log in
+    let user_info = fetch_user_by_id(&s.pool, user_id).await?;
+    ensure_active(&user_info)?;
+
     // Create jwt from id then store it in a cookie
     // Token expiration in seconds
     let seconds = 60 * 60 * 24 * 7; // TODO: change this in production mode to be shorter
@@ -204,15 +202,21 @@ pub struct VerifyEmailParams {
 pub async fn verify_email_handler(
     Query(params): Query<VerifyEmailParams>,
     State(state): State<AppState>,
+    audit_actor: AuditActor,
 ) -> Result<Response> {
-    // Validate the verification token
-    let claims = validate_jwt(&params.token)?;
+    // Validate and consume the verification token
+    let email = consume_token(&state.pool, &params.token, TokenPurpose::EmailVerification).await?;
 
     // Fetch user data from pending users table
-    let user = fetch_pending_user(&state.pool, claims.sub).await?;
+    let user = fetch_pending_user(&state.pool, email).await?;
 
     // Add the user to the 'users' table and delete it from the 'pending_users' table
-    move_pending_user(&state.pool, user).await?;
+    let audit_actor = AuditActor {
+        user_id: Some(user.id),
+        email: Some(user.email.clone()),
+        ..audit_actor
+    };
+    move_pending_user(&state.pool, &audit_actor, user).await?;
 
     let res = UserResponse::with_success("Email verified successfully").into_response();
     Ok(res)
@@ -222,23 +226,23 @@ pub async fn resend_verification_handler(
     Query(params): Query<VerifyEmailParams>,
     State(state): State<AppState>,
 ) -> Result<Response> {
-    // Extract the email from the expired token
-    let email = match validate_jwt(&params.token) {
-        Ok(token) => token.sub,
+    // Extract the email from the (possibly expired) token
+    let email = match check_token(&state.pool, &params.token, TokenPurpose::EmailVerification).await
+    {
+        Ok(email) => email,
         Err(err) => match err {
             SamError::ExpiredToken(email) => email,
             _ => return Err(SamError::InvalidToken),
         },
     };
 
-    // Create a new token
-    let verification_token = create_jwt(&email, 24 * 60 * 60)?; // valid for 24 hours
-
-    reset_email_verification_token(&state.pool, &verification_token, &email).await?;
+    // Create a new token, the old one is invalidated
+    let verification_token =
+        issue_token(&state.pool, &email, TokenPurpose::EmailVerification).await?;
 
     // Send email for verifying
-    let body = generate_verify_email_body(verification_token)?;
-    send_verification_email(body, "Verify Your Email", &email).await?;
+    let body = generate_verify_email_body(&state.config, verification_token)?;
+    send_verification_email(&state.config, body, "Verify Your Email", &email).await?;
     let res = UserResponse::with_success("Resent successfully! check your email and verify it.")
         .into_response();
     Ok(res)
@@ -260,10 +264,9 @@ pub async fn forgot_password_handler(
         Err(_) => return Ok(UserResponse::with_success("Check Your Email Box").into_response()),
     };
     let email = user.email;
-    let verification_token = create_jwt(&email, 60 * 60)?;
-    let body = generate_forgot_password_body(verification_token.clone())?;
-    send_verification_email(body, "Forgot Password", &email).await?;
-    add_reset_password_token(&state.pool, email, verification_token).await?;
+    let reset_token = issue_token(&state.pool, &email, TokenPurpose::PasswordReset).await?;
+    let body = generate_forgot_password_body(&state.config, reset_token)?;
+    send_verification_email(&state.config, body, "Forgot Password", &email).await?;
     Ok(UserResponse::with_success("Check Your Email Box").into_response())
 }
 
@@ -273,13 +276,7 @@ pub struct ResetPasswordPayload {
     new_password: String,
 }
 
-#[derive(Debug, FromRow)]
-pub struct TokenInfo {
-    pub token: String,
-    pub email: String,
-}
-
-#[catch_error]
+#[catch_error(skip(payload))]
 pub async fn reset_password_handler(
     State(state): State<AppState>,
     payload: Result<Json<ResetPasswordPayload>, JsonRejection>,
@@ -290,20 +287,21 @@ pub async fn reset_password_handler(
         new_password,
     }) = payload;
 
-    // Check if token exists
-    let token_info = fetch_reset_password_token(&state.pool, token).await?;
+    if let Err(errors) = password_policy().validate(&new_password) {
+        return Ok(password_errors_response("new_password", errors));
+    }
 
-    // Validate token
-    let _claims: Claims = validate_jwt(&token_info.token)?;
+    // Validate and consume the token
+    let email = consume_token(&state.pool, &token, TokenPurpose::PasswordReset).await?;
 
     // Hash password
     let hash = hash_password(new_password)?;
 
     // Reset password
-    reset_password(&state.pool, hash, token_info.email).await?;
+    reset_password(&state.pool, hash, email.clone()).await?;
 
-    // Remove token from db
-    delete_token(&state.pool, token_info.token).await?;
+    // Any other reset link sent before the change is no longer valid
+    invalidate_user_tokens(&state.pool, &email, TokenPurpose::PasswordReset).await?;
 
     Ok(UserResponse::with_success("Password reset successfully").into_response())
 }
@@ -1,13 +1,5 @@
-use sam_error::SamError;
+/// Directory of user uploaded files, served under `/uploads`
+pub const UPLOADS_DIR: &str = "uploads";
 
-pub fn get_host() -> Result<String, SamError> {
-    let dev_host = dotenvy::var("DEV_HOST")
-        .map_err(|_| SamError::MissingEnviromentVariable("DEV_HOST".to_string()))?;
-    let dist_host = dotenvy::var("HOST")
-        .map_err(|_| SamError::MissingEnviromentVariable("HOST".to_string()))?;
-    let host = match cfg!(debug_assertions) {
-        true => dev_host,
-        false => dist_host,
-    };
-    Ok(host)
-}
+/// Directory of generated GDPR exports, only downloadable by their owner
+pub const EXPORTS_DIR: &str = "exports";
@@ -18,7 +18,7 @@ pub fn AddCategory() -> Element {
     let mut parent_id = use_signal(String::new);
 
     let languages: Resource<Vec<Language>> = use_resource(move || async move {
-        let url = format!("{}/languages", crate::enviroment::BASE_URL);
+        let url = format!("{}/languages", crate::enviroment::base_url());
         match fetch_data(&url).await {
             Ok(res) => match res.json::<UserResponse>().await {
                 Ok(user_res) => {
@@ -57,7 +57,7 @@ pub fn AddCategory() -> Element {
                 category,
                 names: category_names,
             };
-            let url = format!("{}/categories", crate::enviroment::BASE_URL);
+            let url = format!("{}/categories", crate::enviroment::base_url());
             match post_json(&url, &category_with_names).await {
                 Ok(res) => {
                     spinner_state.set(PopupState::Close);
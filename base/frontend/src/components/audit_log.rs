@@ -0,0 +1,192 @@
+use dioxus::prelude::*;
+use sam_ui::{
+    input::{Input, InputAppearance},
+    popup::{Msg, MsgConfig},
+};
+use sam_util::endpoint_url;
+use shared::audit::{AuditEntry, AuditFilter, ExportAuditLog, ListAuditLog};
+
+use super::pagination::{use_page, Pager};
+
+/// Empty text is no filter
+fn optional(text: String) -> Option<String> {
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// One `key: before → after` line per changed key
+fn diff_lines(entry: &AuditEntry) -> Vec<String> {
+    entry
+        .diff
+        .as_object()
+        .map(|diff| {
+            diff.iter()
+                .map(|(key, change)| format!("{}: {} → {}", key, change["before"], change["after"]))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn export_url(filter: &AuditFilter, format: &str) -> String {
+    format!(
+        "{}?{}&format={}",
+        endpoint_url::<ExportAuditLog>(crate::enviroment::base_url(), &()),
+        filter.to_query_string(),
+        format
+    )
+}
+
+/// Who changed what and when, newest first
+#[component]
+pub fn AuditLogViewer() -> Element {
+    let mut log = use_page::<ListAuditLog, _>(|| AuditFilter::default().to_list_query());
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut actor = use_signal(String::new);
+    let mut action = use_signal(String::new);
+    let mut resource_type = use_signal(String::new);
+    let mut resource_id = use_signal(String::new);
+    let mut from = use_signal(String::new);
+    let mut to = use_signal(String::new);
+    // The applied filter, the inputs only take effect on search
+    let mut filter = use_signal(AuditFilter::default);
+
+    // Errors of the list
+    use_effect(move || {
+        if let Some(e) = (log.error)() {
+            err_msg.set(MsgConfig::with_err(e));
+        }
+    });
+
+    let handle_search = move |_| {
+        let applied = AuditFilter {
+            actor: optional(actor()),
+            action: optional(action()),
+            resource_type: optional(resource_type()),
+            resource_id: optional(resource_id()),
+            from: optional(from()),
+            to: optional(to()),
+        };
+        log.search(applied.to_list_query());
+        filter.set(applied);
+    };
+
+    rsx! {
+        div { class: "audit-log-container p-6",
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-2xl font-bold", "Audit log" }
+                div { class: "flex gap-2",
+                    a { class: "btn-sec", href: export_url(&filter(), "csv"), "Export CSV" }
+                    a { class: "btn-sec", href: export_url(&filter(), "json"), "Export JSON" }
+                }
+            }
+
+            div { class: "grid grid-cols-3 gap-4 mb-6",
+                Input {
+                    name: "actor",
+                    appearance: InputAppearance::square,
+                    label: "Actor email",
+                    value: actor(),
+                    oninput: move |evt: FormEvent| actor.set(evt.value()),
+                }
+                Input {
+                    name: "action",
+                    appearance: InputAppearance::square,
+                    label: "Action, e.g. update",
+                    value: action(),
+                    oninput: move |evt: FormEvent| action.set(evt.value()),
+                }
+                Input {
+                    name: "resource_type",
+                    appearance: InputAppearance::square,
+                    label: "Resource type, e.g. user",
+                    value: resource_type(),
+                    oninput: move |evt: FormEvent| resource_type.set(evt.value()),
+                }
+                Input {
+                    name: "resource_id",
+                    appearance: InputAppearance::square,
+                    label: "Resource id",
+                    value: resource_id(),
+                    oninput: move |evt: FormEvent| resource_id.set(evt.value()),
+                }
+                label { class: "flex flex-col gap-1",
+                    span { class: "text-sm text-gray-500", "From" }
+                    input {
+                        class: "border p-2",
+                        r#type: "date",
+                        value: from(),
+                        oninput: move |evt: FormEvent| from.set(evt.value()),
+                    }
+                }
+                label { class: "flex flex-col gap-1",
+                    span { class: "text-sm text-gray-500", "To" }
+                    input {
+                        class: "border p-2",
+                        r#type: "date",
+                        value: to(),
+                        oninput: move |evt: FormEvent| to.set(evt.value()),
+                    }
+                }
+            }
+            div { class: "flex justify-end mb-6",
+                button { class: "btn", onclick: handle_search, "Search" }
+            }
+
+            if let Some(page) = (log.page)() {
+                table { class: "table table-bordered w-full",
+                    thead {
+                        tr {
+                            th { class: "text-left p-3", "When" }
+                            th { class: "text-left p-3", "Actor" }
+                            th { class: "text-left p-3", "Action" }
+                            th { class: "text-left p-3", "Resource" }
+                            th { class: "text-left p-3", "Changes" }
+                            th { class: "text-left p-3", "Client" }
+                        }
+                    }
+                    tbody {
+                        for entry in page.items.iter() {
+                            tr { key: "{entry.id}", class: "hover:bg-gray-50 align-top",
+                                td { class: "p-3 border-b whitespace-nowrap",
+                                    "{entry.created_at.date()} {entry.created_at.time()}"
+                                }
+                                td { class: "p-3 border-b",
+                                    {entry.actor_email.clone().unwrap_or_else(|| "system".to_string())}
+                                }
+                                td { class: "p-3 border-b font-mono", "{entry.action}" }
+                                td { class: "p-3 border-b font-mono",
+                                    "{entry.resource_type}/{entry.resource_id}"
+                                }
+                                td { class: "p-3 border-b font-mono text-sm",
+                                    for line in diff_lines(entry) {
+                                        div { "{line}" }
+                                    }
+                                }
+                                td { class: "p-3 border-b text-sm",
+                                    div { {entry.ip.clone().unwrap_or_default()} }
+                                    div { class: "text-gray-500",
+                                        {entry.user_agent.clone().unwrap_or_default()}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Pager {
+                    total: page.total,
+                    number: log.number(),
+                    has_previous: log.has_previous(),
+                    has_next: log.has_next(),
+                    on_previous: move |_| log.previous(),
+                    on_next: move |_| log.next(),
+                }
+            } else {
+                div { class: "text-center py-8",
+                    div { class: "loading loading-spinner loading-lg" }
+                    div { class: "mt-2", "Loading audit log..." }
+                }
+            }
+
+            {Msg(err_msg())}
+        }
+    }
+}
@@ -5,8 +5,11 @@ use sam_ui::{
     input::{Input, InputAppearance},
     popup::{Msg, MsgConfig, PopupState, Spinner, Toast},
 };
-use sam_util::{fetch_data, post_json, put_json};
-use shared::{user::UserResponse, Category, CategoryName, CategoryWithNames, Language};
+use sam_util::{call_all_pages, call_endpoint};
+use shared::{
+    list::ListQuery, Category, CategoryName, CategoryWithNames, Language, ListLanguages,
+    UpdateCategory,
+};
 
 #[component]
 pub fn Categories() -> Element {
@@ -68,19 +71,13 @@ pub fn CategoryForm(props: CategoryFormProps) -> Element {
     });
 
     let languages: Resource<Vec<Language>> = use_resource(move || async move {
-        let url = format!("{}/languages", crate::enviroment::BASE_URL);
-        match fetch_data(&url).await {
-            Ok(res) => match res.json::<UserResponse>().await {
-                Ok(user_res) => {
-                    serde_json::from_value::<Vec<Language>>(user_res.json().unwrap()).unwrap()
-                }
-                Err(e) => {
-                    err_msg.set(MsgConfig::with_err(e.to_string()));
-                    vec![]
-                }
-            },
+        // A name per language, so every page of them
+        let query = ListQuery::default().with_per_page(ListQuery::MAX_PER_PAGE);
+        let base_url = crate::enviroment::base_url();
+        match call_all_pages::<ListLanguages, _>(base_url, &(), &query).await {
+            Ok(languages) => languages,
             Err(e) => {
-                err_msg.set(MsgConfig::with_err(e.to_string()));
+                err_msg.set(MsgConfig::with_err(e));
                 vec![]
             }
         }
@@ -110,39 +107,27 @@ pub fn CategoryForm(props: CategoryFormProps) -> Element {
                 names: category_names,
             };
 
+            let base_url = crate::enviroment::base_url();
             let result = if is_edit {
-                // PUT request for editing
-                let url = format!(
-                    "{}/categories/{}",
-                    crate::enviroment::BASE_URL,
-                    category_id()
-                );
-                put_json(&url, &category_with_names).await
+                call_endpoint::<UpdateCategory>(base_url, &category_id(), &category_with_names)
+                    .await
             } else {
-                // POST request for adding
-                let url = format!("{}/categories", crate::enviroment::BASE_URL);
-                post_json(&url, &category_with_names).await
+                call_endpoint::<shared::AddCategory>(base_url, &(), &category_with_names).await
             };
 
+            spinner_state.set(PopupState::Close);
             match result {
-                Ok(res) => {
-                    spinner_state.set(PopupState::Close);
-                    let user_res: UserResponse = res.json().await.unwrap();
-                    if res.ok() {
-                        let success_msg = if is_edit {
-                            "Category updated successfully!"
-                        } else {
-                            "Category added successfully!"
-                        };
-                        msg.set(MsgConfig::with_success(success_msg));
-                        reset();
+                Ok(_) => {
+                    let success_msg = if is_edit {
+                        "Category updated successfully!"
                     } else {
-                        err_msg.set(MsgConfig::with_err(user_res.message()));
-                    }
+                        "Category added successfully!"
+                    };
+                    msg.set(MsgConfig::with_success(success_msg));
+                    reset();
                 }
                 Err(e) => {
-                    spinner_state.set(PopupState::Close);
-                    err_msg.set(MsgConfig::with_err(e.to_string()));
+                    err_msg.set(MsgConfig::with_err(e));
                 }
             }
         }
@@ -0,0 +1,205 @@
+use dioxus::prelude::*;
+use sam_ui::{
+    popup::{Msg, MsgConfig, Toast},
+    Menu, MenuItem,
+};
+use sam_util::call_endpoint;
+use shared::{
+    error_log::{
+        ErrorGroup, ErrorOccurrence, ErrorOccurrences, ErrorStatus, ErrorStatusPayload, ListErrors,
+        SetErrorStatus,
+    },
+    list::{FilterOp, ListQuery, SortDirection},
+};
+
+use sam_icon::icon;
+
+use crate::enviroment::base_url;
+
+use super::pagination::{use_page, Pager};
+
+fn location(error: &ErrorGroup) -> String {
+    format!(
+        "{} {}:{}",
+        error.module.clone().unwrap_or_default(),
+        error.file.clone().unwrap_or_default(),
+        error.line.unwrap_or_default()
+    )
+}
+
+/// The errors and panics of the backend, grouped, with the resolve/ignore workflow
+#[component]
+pub fn ErrorGroups() -> Element {
+    let mut shown_status = use_signal(|| ErrorStatus::Open);
+    let mut list = use_page::<ListErrors, _>(|| {
+        ListQuery::default()
+            .sort_by("last_seen_at", SortDirection::Desc)
+            .sort_by("id", SortDirection::Desc)
+            .filter("status", FilterOp::Eq, ErrorStatus::Open.as_str())
+    });
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut success_msg = use_signal(|| MsgConfig::default());
+    // The group whose occurrences are shown
+    let mut expanded = use_signal(|| None::<i32>);
+    let mut occurrences: Signal<Vec<ErrorOccurrence>> = use_signal(Vec::new);
+
+    // Errors of the list
+    use_effect(move || {
+        if let Some(e) = (list.error)() {
+            err_msg.set(MsgConfig::with_err(e));
+        }
+    });
+
+    let mut handle_expand = move |id: i32| {
+        if expanded() == Some(id) {
+            expanded.set(None);
+            return;
+        }
+        expanded.set(Some(id));
+        occurrences.set(Vec::new());
+        spawn(async move {
+            match call_endpoint::<ErrorOccurrences>(base_url(), &id, &()).await {
+                Ok(found) => occurrences.set(found),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    let handle_status = move |id: i32, status: ErrorStatus| {
+        spawn(async move {
+            let payload = ErrorStatusPayload { status };
+            match call_endpoint::<SetErrorStatus>(base_url(), &id, &payload).await {
+                Ok(message) => {
+                    success_msg.set(MsgConfig::with_success(message));
+                    list.reload();
+                }
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "errors-container p-6",
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-2xl font-bold", "Errors" }
+                div { class: "flex gap-2",
+                    for status in ErrorStatus::ALL {
+                        button {
+                            key: "{status.as_str()}",
+                            class: if shown_status() == status { "btn" } else { "btn-sec" },
+                            onclick: move |_| {
+                                expanded.set(None);
+                                shown_status.set(status);
+                                list.filter("status", FilterOp::Eq, status.as_str());
+                            },
+                            "{status.as_str()}"
+                        }
+                    }
+                }
+            }
+
+            if let Some(page) = (list.page)() {
+                table { class: "table table-bordered w-full",
+                    thead {
+                        tr {
+                            th { class: "text-left p-3", "Message" }
+                            th { class: "text-left p-3", "Location" }
+                            th { class: "text-left p-3", "Count" }
+                            th { class: "text-left p-3", "Last seen" }
+                            th { class: "text-center p-3 w-16", "Actions" }
+                        }
+                    }
+                    tbody {
+                        for error in page.items.iter() {
+                            tr {
+                                key: "{error.id}",
+                                class: "hover:bg-gray-50 cursor-pointer",
+                                onclick: {
+                                    let id = error.id;
+                                    move |_| handle_expand(id)
+                                },
+                                td { class: "p-3 border-b", {error.message.clone().unwrap_or_default()} }
+                                td { class: "p-3 border-b font-mono text-sm", {location(error)} }
+                                td { class: "p-3 border-b", "{error.occurrences}" }
+                                td { class: "p-3 border-b whitespace-nowrap",
+                                    "{error.last_seen_at.date()} {error.last_seen_at.time()}"
+                                }
+                                td {
+                                    class: "p-3 border-b text-center relative",
+                                    onclick: move |evt| evt.stop_propagation(),
+                                    Menu { custom_class: "dropdown_menu",
+                                        MenuItem {
+                                            trigger: rsx! {
+                                                {icon!(LdEllipsis, 20)}
+                                            },
+                                            for status in ErrorStatus::ALL.into_iter().filter(|status| *status != error.status) {
+                                                MenuItem {
+                                                    trigger: rsx! {
+                                                        {status_action(status)}
+                                                    },
+                                                    action: {
+                                                        let id = error.id;
+                                                        move |_| handle_status(id, status)
+                                                    },
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            if expanded() == Some(error.id) {
+                                tr {
+                                    td { class: "p-3 border-b bg-gray-50", colspan: "5",
+                                        for occurrence in occurrences().iter() {
+                                            div { key: "{occurrence.id}", class: "flex gap-4 text-sm py-1",
+                                                span { class: "whitespace-nowrap",
+                                                    "{occurrence.created_at.date()} {occurrence.created_at.time()}"
+                                                }
+                                                span { class: "font-mono",
+                                                    {occurrence.method.clone().unwrap_or_default()}
+                                                    " "
+                                                    {occurrence.route.clone().unwrap_or_default()}
+                                                }
+                                                span { class: "text-gray-500",
+                                                    {occurrence.user_id.map(|id| id.to_string()).unwrap_or_default()}
+                                                }
+                                                span { class: "font-mono text-gray-500",
+                                                    {occurrence.request_id.map(|id| id.to_string()).unwrap_or_default()}
+                                                }
+                                                span { "{occurrence.message}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Pager {
+                    total: page.total,
+                    number: list.number(),
+                    has_previous: list.has_previous(),
+                    has_next: list.has_next(),
+                    on_previous: move |_| list.previous(),
+                    on_next: move |_| list.next(),
+                }
+            } else {
+                div { class: "text-center py-8",
+                    div { class: "loading loading-spinner loading-lg" }
+                    div { class: "mt-2", "Loading errors..." }
+                }
+            }
+
+            {Msg(err_msg())}
+            {Toast(success_msg())}
+        }
+    }
+}
+
+fn status_action(status: ErrorStatus) -> &'static str {
+    match status {
+        ErrorStatus::Open => "reopen",
+        ErrorStatus::Resolved => "resolve",
+        ErrorStatus::Ignored => "ignore",
+    }
+}
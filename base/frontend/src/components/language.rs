@@ -6,69 +6,34 @@ use sam_ui::{
     popup::{Msg, MsgConfig, Popup, PopupState, Spinner, Toast},
     Elem, Menu, MenuItem,
 };
-use sam_util::{delete_entity, fetch_data, post_json, put_json};
-use shared::{user::UserResponse, Language};
+use sam_util::call_endpoint;
+use shared::{
+    list::{FilterOp, ListQuery, SortDirection},
+    AddLanguage, DeleteLanguage, Language, ListLanguages, UpdateLanguage,
+};
 
 use sam_icon::icon;
 use wasm_bindgen::JsCast;
 
+use super::pagination::{use_page, Pager};
+
 #[component]
 pub fn Languages() -> Element {
     let mut show_form = use_signal(|| PopupState::Close);
     let mut edit_language = use_signal(|| None::<Language>);
-    let mut languages_resource: Signal<Option<Vec<Language>>> = use_signal(|| None);
+    let mut languages =
+        use_page::<ListLanguages, _>(|| ListQuery::default().sort_by("code", SortDirection::Asc));
     let mut err_msg = use_signal(|| MsgConfig::default());
     let mut success_msg = use_signal(|| MsgConfig::default());
     let mut deleted_lang_id = use_signal(|| None::<i32>);
 
     let mut confirm_del_msg = use_signal(|| MsgConfig::default());
 
-    // Fetch languages
-    let fetch_languages = move || {
-        spawn(async move {
-            let url = format!("{}/languages", crate::enviroment::BASE_URL);
-            match fetch_data(&url).await {
-                Ok(res) => match res.json::<UserResponse>().await {
-                    Ok(user_res) => {
-                        let langs =
-                            serde_json::from_value::<Vec<Language>>(user_res.json().unwrap())
-                                .unwrap();
-                        languages_resource.set(Some(langs));
-                    }
-                    Err(e) => {
-                        err_msg.set(MsgConfig::with_err(e.to_string()));
-                    }
-                },
-                Err(e) => {
-                    err_msg.set(MsgConfig::with_err(e.to_string()));
-                }
-            }
-        });
-    };
-
-    let mut remove_lang_lacally = move |lang_id: i32| {
-        let mut langs = languages_resource().unwrap_or_default();
-        langs.retain(|lang| lang.id != lang_id);
-        languages_resource.set(Some(langs));
-    };
-
-    let mut update_lang_locally = move |lang: Language| {
-        let mut langs = languages_resource().unwrap_or_default();
-        info!("lang {:?}", lang);
-        info!("before langs {:#?}", langs);
-        if let Some(index) = langs.iter().position(|l| l.id == lang.id) {
-            info!("index {:?}", index);
-            langs[index] = lang;
-        } else {
-            langs.push(lang);
-        }
-        info!("after langs {:#?}", langs);
-        languages_resource.set(Some(langs));
-    };
-
-    // Load languages on component mount
+    // Errors of the list
     use_effect(move || {
-        fetch_languages();
+        if let Some(e) = (languages.error)() {
+            err_msg.set(MsgConfig::with_err(e));
+        }
     });
 
     use_effect(move || {
@@ -81,25 +46,18 @@ pub fn Languages() -> Element {
                             // handle_delete("id".to_string());
                             let id = id.clone();
                             spawn(async move {
-                                let url =
-                                    format!("{}/languages/{}", crate::enviroment::BASE_URL, id);
-                                match delete_entity(&url).await {
-                                    Ok(res) => {
-                                        if res.ok() {
-                                            // Refresh the list by romoving the deleted lang from the vec in the memory.
-                                            // We don't need to refetch the languages from the db.
-                                            remove_lang_lacally(id);
-                                            deleted_lang_id.set(None);
-                                            success_msg.set(MsgConfig::with_success(
-                                                "Lanuage deleted successfullly",
-                                            ));
-                                        } else {
-                                            let user_res: UserResponse = res.json().await.unwrap();
-                                            err_msg.set(MsgConfig::with_err(user_res.message()));
-                                        }
+                                let base_url = crate::enviroment::base_url();
+                                match call_endpoint::<DeleteLanguage>(base_url, &id, &()).await {
+                                    Ok(_) => {
+                                        // Refetch the page, the next rows move up into it
+                                        languages.reload();
+                                        deleted_lang_id.set(None);
+                                        success_msg.set(MsgConfig::with_success(
+                                            "Lanuage deleted successfullly",
+                                        ));
                                     }
                                     Err(e) => {
-                                        err_msg.set(MsgConfig::with_err(e.to_string()));
+                                        err_msg.set(MsgConfig::with_err(e));
                                     }
                                 }
                             });
@@ -133,9 +91,20 @@ pub fn Languages() -> Element {
                 }
             }
 
+            div { class: "mb-4",
+                Input {
+                    name: "search",
+                    appearance: InputAppearance::square,
+                    label: "Search by name",
+                    oninput: move |evt: FormEvent| {
+                        languages.filter("name", FilterOp::Contains, evt.value());
+                    },
+                }
+            }
+
             // Languages table
-            if let Some(languages) = languages_resource() {
-                if languages.is_empty() {
+            if let Some(page) = (languages.page)() {
+                if page.items.is_empty() {
                     div { class: "text-center py-8 text-gray-500",
                         "No languages found. Add your first language!"
                     }
@@ -144,15 +113,23 @@ pub fn Languages() -> Element {
                         table { class: "table table-bordered w-full",
                             thead {
                                 tr {
-                                    th { class: "text-left p-3", "Code" }
-                                    th { class: "text-left p-3", "Name" }
+                                    th {
+                                        class: "text-left p-3 cursor-pointer",
+                                        onclick: move |_| languages.sort_by("code"),
+                                        "Code"
+                                    }
+                                    th {
+                                        class: "text-left p-3 cursor-pointer",
+                                        onclick: move |_| languages.sort_by("name"),
+                                        "Name"
+                                    }
                                     th { class: "text-left p-3", "Flag" }
                                     th { class: "text-left p-3", "Active" }
                                     th { class: "text-center p-3 w-16", "Actions" }
                                 }
                             }
                             tbody {
-                                for lang in languages.iter() {
+                                for lang in page.items.iter() {
                                     tr { class: "hover:bg-gray-50",
                                         td { class: "p-3 border-b", "{lang.code}" }
                                         td { class: "p-3 border-b", "{lang.name}" }
@@ -195,6 +172,14 @@ pub fn Languages() -> Element {
                         }
                     }
                 }
+                Pager {
+                    total: page.total,
+                    number: languages.number(),
+                    has_previous: languages.has_previous(),
+                    has_next: languages.has_next(),
+                    on_previous: move |_| languages.previous(),
+                    on_next: move |_| languages.next(),
+                }
             } else {
                 div { class: "text-center py-8",
                     div { class: "loading loading-spinner loading-lg" }
@@ -212,8 +197,8 @@ pub fn Languages() -> Element {
                     language: edit_language(),
                     on_close: move |lang: Option<Language>| {
                         show_form.set(PopupState::CloseWithAnimation);
-                        if let Some(lang) = lang {
-                            update_lang_locally(lang);
+                        if lang.is_some() {
+                            languages.reload();
                             let msg = if edit_language().is_some() {
                                 "Language updated successfully!"
                             } else {
@@ -294,28 +279,17 @@ pub fn LanguageForm(props: LanguageFormProps) -> Element {
                 active: language_is_active(),
             };
 
-            let url = format!("{}/languages", crate::enviroment::BASE_URL);
+            let base_url = crate::enviroment::base_url();
             let result = if is_edit {
-                // PUT request for editing
-                put_json(&url, &language).await
+                call_endpoint::<UpdateLanguage>(base_url, &(), &language).await
             } else {
-                // POST request for adding
-                post_json(&url, &language).await
+                call_endpoint::<AddLanguage>(base_url, &(), &language).await
             };
 
             spinner_state.set(PopupState::Close);
             match result {
-                Ok(res) => {
-                    let user_res: UserResponse = res.json().await.unwrap();
-                    if res.ok() {
-                        props.on_close.call(Some(language));
-                    } else {
-                        err_msg.set(MsgConfig::with_err(user_res.message()));
-                    }
-                }
-                Err(e) => {
-                    err_msg.set(MsgConfig::with_err(e.to_string()));
-                }
+                Ok(_) => props.on_close.call(Some(language)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
             }
         });
     };
@@ -9,3 +9,24 @@ pub use fields::*;
 
 mod guard;
 pub use guard::*;
+
+mod users;
+pub use users::*;
+
+mod policies;
+pub use policies::*;
+
+mod roles;
+pub use roles::*;
+
+mod nav_items;
+pub use nav_items::*;
+
+mod audit_log;
+pub use audit_log::*;
+
+mod errors;
+pub use errors::*;
+
+mod pagination;
+pub use pagination::*;
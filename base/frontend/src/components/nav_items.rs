@@ -0,0 +1,377 @@
+use dioxus::prelude::*;
+use sam_ui::{
+    input::{Input, InputAppearance},
+    popup::{Msg, MsgConfig, Popup, PopupState, Spinner, Toast},
+    Menu, MenuItem,
+};
+use sam_util::{call_all_pages, call_endpoint};
+use shared::{
+    dashboard::{
+        AddNavItem, DashNavItem, DashNavItemName, DashNavItemPayload, DeleteNavItem, ListNavItems,
+        UpdateNavItem,
+    },
+    list::ListQuery,
+    Language, ListLanguages,
+};
+
+use sam_icon::icon;
+
+/// Items in menu order, each with its depth
+fn menu_order(
+    items: &[DashNavItem],
+    parent_id: Option<i32>,
+    depth: usize,
+) -> Vec<(usize, DashNavItem)> {
+    items
+        .iter()
+        .filter(|item| item.parent_id == parent_id)
+        .flat_map(|item| {
+            let mut list = vec![(depth, item.clone())];
+            list.extend(menu_order(items, Some(item.id), depth + 1));
+            list
+        })
+        .collect()
+}
+
+/// Empty text is no requirement
+fn optional(text: String) -> Option<String> {
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// Edits the dashboard menu, the changes show after the next login
+#[component]
+pub fn NavItems() -> Element {
+    let mut items: Signal<Option<Vec<DashNavItem>>> = use_signal(|| None);
+    let mut reload = use_signal(|| 0);
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut success_msg = use_signal(|| MsgConfig::default());
+    let mut confirm_msg = use_signal(|| MsgConfig::default());
+    let mut show_form = use_signal(|| PopupState::Close);
+    let mut selected_item = use_signal(|| None::<DashNavItem>);
+    // The form is mounted again for every edit so its fields start from the selected item
+    let mut editing = use_signal(|| false);
+
+    use_effect(move || {
+        let _ = reload();
+        spawn(async move {
+            match call_endpoint::<ListNavItems>(crate::enviroment::base_url(), &(), &()).await {
+                Ok(list) => items.set(Some(list)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    });
+
+    let mut handle_delete = move |item: DashNavItem| {
+        confirm_msg.set(
+            MsgConfig::with_confirm(format!(
+                "{} and the items under it will be deleted.",
+                item.name
+            ))
+            .callback(move || {
+                spawn(async move {
+                    let base_url = crate::enviroment::base_url();
+                    match call_endpoint::<DeleteNavItem>(base_url, &item.id, &()).await {
+                        Ok(message) => {
+                            success_msg.set(MsgConfig::with_success(message));
+                            reload += 1;
+                        }
+                        Err(e) => err_msg.set(MsgConfig::with_err(e)),
+                    }
+                });
+            }),
+        );
+    };
+
+    rsx! {
+        div { class: "nav-items-container p-6",
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-2xl font-bold", "Menu" }
+                button {
+                    class: "btn",
+                    onclick: move |_| {
+                        selected_item.set(None);
+                        editing.set(true);
+                        show_form.set(PopupState::Open);
+                    },
+                    "New item"
+                }
+            }
+
+            if let Some(list) = items() {
+                table { class: "table table-bordered w-full",
+                    thead {
+                        tr {
+                            th { class: "text-left p-3", "Position" }
+                            th { class: "text-left p-3", "Name" }
+                            th { class: "text-left p-3", "Route" }
+                            th { class: "text-left p-3", "Permission" }
+                            th { class: "text-left p-3", "Action" }
+                            th { class: "text-center p-3 w-16", "Actions" }
+                        }
+                    }
+                    tbody {
+                        for (depth, item) in menu_order(&list, None, 0) {
+                            tr { key: "{item.id}", class: "hover:bg-gray-50",
+                                td { class: "p-3 border-b", "{item.position}" }
+                                td {
+                                    class: "p-3 border-b",
+                                    style: format!("padding-left: {}rem", 0.75 + depth as f32 * 1.5),
+                                    "{item.name}"
+                                }
+                                td { class: "p-3 border-b", "{item.route}" }
+                                td { class: "p-3 border-b font-mono",
+                                    {item.required_permission.clone().unwrap_or_default()}
+                                }
+                                td { class: "p-3 border-b font-mono",
+                                    {item.required_action.clone().unwrap_or_default()}
+                                }
+                                td { class: "p-3 border-b text-center relative",
+                                    Menu { custom_class: "dropdown_menu",
+                                        MenuItem {
+                                            trigger: rsx! {
+                                                {icon!(LdEllipsis, 20)}
+                                            },
+                                            MenuItem {
+                                                trigger: rsx! { "edit" },
+                                                action: {
+                                                    let item = item.clone();
+                                                    move |_| {
+                                                        selected_item.set(Some(item.clone()));
+                                                        editing.set(true);
+                                                        show_form.set(PopupState::Open);
+                                                    }
+                                                },
+                                            }
+                                            MenuItem {
+                                                trigger: rsx! { "delete" },
+                                                action: {
+                                                    let item = item.clone();
+                                                    move |_| handle_delete(item.clone())
+                                                },
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                div { class: "text-center py-8",
+                    div { class: "loading loading-spinner loading-lg" }
+                    div { class: "mt-2", "Loading menu..." }
+                }
+            }
+
+            {Msg(err_msg())}
+            {Msg(confirm_msg())}
+            Popup {
+                state: show_form,
+                enter_anim_class: "animate__animated animate__zoomIn",
+                leave_anim_class: "animate__animated animate__zoomOut",
+                if editing() {
+                    NavItemForm {
+                        item: selected_item(),
+                        items: items().unwrap_or_default(),
+                        on_close: move |changed: bool| {
+                            show_form.set(PopupState::CloseWithAnimation);
+                            if changed {
+                                success_msg.set(MsgConfig::with_success("Nav item saved successfully!"));
+                                reload += 1;
+                            }
+                            selected_item.set(None);
+                            editing.set(false);
+                        },
+                    }
+                }
+            }
+            {Toast(success_msg())}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Props)]
+pub struct NavItemFormProps {
+    /// `None` creates a new item
+    pub item: Option<DashNavItem>,
+    /// Every item, to choose the parent from
+    pub items: Vec<DashNavItem>,
+    pub on_close: EventHandler<bool>,
+}
+
+#[component]
+pub fn NavItemForm(props: NavItemFormProps) -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let item = props.item.clone();
+    let mut name = use_signal(|| item.as_ref().map(|i| i.name.clone()).unwrap_or_default());
+    let mut icon_name = use_signal(|| item.as_ref().map(|i| i.icon.clone()).unwrap_or_default());
+    let mut route = use_signal(|| item.as_ref().map(|i| i.route.clone()).unwrap_or_default());
+    let mut parent_id = use_signal(|| item.as_ref().and_then(|i| i.parent_id));
+    let mut required_permission = use_signal(|| {
+        item.as_ref()
+            .and_then(|i| i.required_permission.clone())
+            .unwrap_or_default()
+    });
+    let mut required_action = use_signal(|| {
+        item.as_ref()
+            .and_then(|i| i.required_action.clone())
+            .unwrap_or_default()
+    });
+    let mut position = use_signal(|| item.as_ref().map_or(0, |i| i.position));
+    let mut names = use_signal(|| item.as_ref().map(|i| i.names.clone()).unwrap_or_default());
+    let mut languages = use_signal(Vec::<Language>::new);
+    let item_id = item.as_ref().map(|i| i.id);
+
+    use_effect(move || {
+        spawn(async move {
+            let query = ListQuery::default().with_per_page(ListQuery::MAX_PER_PAGE);
+            let base_url = crate::enviroment::base_url();
+            match call_all_pages::<ListLanguages, _>(base_url, &(), &query).await {
+                Ok(found) => languages.set(found),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    });
+
+    let handle_submit = move |_| {
+        let payload = DashNavItemPayload {
+            parent_id: parent_id(),
+            name: name(),
+            icon: icon_name(),
+            route: route(),
+            required_permission: optional(required_permission()),
+            required_action: optional(required_action()),
+            position: position(),
+            names: names(),
+        };
+        if let Err(e) = payload.validate() {
+            err_msg.set(MsgConfig::with_err(e));
+            return;
+        }
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let result = match item_id {
+                Some(id) => call_endpoint::<UpdateNavItem>(base_url, &id, &payload)
+                    .await
+                    .map(|_| ()),
+                None => call_endpoint::<AddNavItem>(base_url, &(), &payload)
+                    .await
+                    .map(|_| ()),
+            };
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(()) => props.on_close.call(true),
+                Err(e) => {
+                    err_msg.set(MsgConfig::with_err(e));
+                }
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "nav-item-form m-2.5",
+            h2 { class: "text-xl font-bold",
+                if item_id.is_some() {
+                    "Edit nav item"
+                } else {
+                    "New nav item"
+                }
+            }
+            div { class: "flex flex-col gap-4 mt-8",
+                Input {
+                    name: "name",
+                    appearance: InputAppearance::square,
+                    label: "Name",
+                    value: name(),
+                    oninput: move |evt: FormEvent| name.set(evt.value()),
+                }
+                Input {
+                    name: "icon",
+                    appearance: InputAppearance::square,
+                    label: "Icon",
+                    value: icon_name(),
+                    oninput: move |evt: FormEvent| icon_name.set(evt.value()),
+                }
+                Input {
+                    name: "route",
+                    appearance: InputAppearance::square,
+                    label: "Route, e.g. /dashboard/users",
+                    value: route(),
+                    oninput: move |evt: FormEvent| route.set(evt.value()),
+                }
+                select {
+                    class: "border p-2",
+                    onchange: move |evt: FormEvent| parent_id.set(evt.value().parse::<i32>().ok()),
+                    option { value: "", selected: parent_id().is_none(), "Top level" }
+                    for other in props.items.iter().filter(|other| Some(other.id) != item_id) {
+                        option {
+                            key: "{other.id}",
+                            value: "{other.id}",
+                            selected: parent_id() == Some(other.id),
+                            "Under {other.name}"
+                        }
+                    }
+                }
+                Input {
+                    name: "required_permission",
+                    appearance: InputAppearance::square,
+                    label: "Permission a role must grant, e.g. user:manage",
+                    value: required_permission(),
+                    oninput: move |evt: FormEvent| required_permission.set(evt.value()),
+                }
+                Input {
+                    name: "required_action",
+                    appearance: InputAppearance::square,
+                    label: "Action the policies must allow, e.g. listing:view",
+                    value: required_action(),
+                    oninput: move |evt: FormEvent| required_action.set(evt.value()),
+                }
+                Input {
+                    name: "position",
+                    appearance: InputAppearance::square,
+                    label: "Position",
+                    value: position().to_string(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<i32>() {
+                            position.set(value);
+                        }
+                    },
+                }
+                for language in languages() {
+                    Input {
+                        key: "{language.id}",
+                        name: "name_{language.code}",
+                        appearance: InputAppearance::square,
+                        label: "Name in {language.name}",
+                        value: names()
+                            .iter()
+                            .find(|n| n.language_id == language.id)
+                            .map(|n| n.name.clone())
+                            .unwrap_or_default(),
+                        oninput: move |evt: FormEvent| {
+                            names.with_mut(|names| {
+                                names.retain(|n| n.language_id != language.id);
+                                names.push(DashNavItemName {
+                                    language_id: language.id,
+                                    name: evt.value(),
+                                });
+                            });
+                        },
+                    }
+                }
+            }
+            div { class: "flex justify-end gap-2 mt-6",
+                button {
+                    class: "btn-sec",
+                    onclick: move |_| props.on_close.call(false),
+                    "Cancel"
+                }
+                button { class: "btn", onclick: handle_submit, "Save" }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
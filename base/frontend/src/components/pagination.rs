@@ -0,0 +1,189 @@
+use dioxus::prelude::*;
+use sam_util::call_list_endpoint;
+use shared::{
+    api::Endpoint,
+    list::{FilterOp, ListQuery, Page, SortDirection},
+};
+
+/// # A paged list endpoint
+/// Holds the `ListQuery`, refetches whenever it changes and pages forward by cursor,
+/// keeping the cursors behind so `previous` can go back.
+pub struct UsePage<T: 'static> {
+    pub query: Signal<ListQuery>,
+    pub page: Signal<Option<Page<T>>>,
+    pub error: Signal<Option<String>>,
+    /// The cursors of the pages before the current one, `None` for the first
+    history: Signal<Vec<Option<String>>>,
+    reloads: Signal<u32>,
+}
+
+impl<T: 'static> Clone for UsePage<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for UsePage<T> {}
+
+impl<T: Clone + 'static> UsePage<T> {
+    /// The rows of the current page, empty while loading
+    pub fn items(&self) -> Vec<T> {
+        self.page
+            .read()
+            .as_ref()
+            .map(|page| page.items.clone())
+            .unwrap_or_default()
+    }
+
+    /// The current page, from 1
+    pub fn number(&self) -> usize {
+        self.history.read().len() + 1
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page.read().as_ref().is_some_and(Page::has_next)
+    }
+
+    pub fn has_previous(&self) -> bool {
+        !self.history.read().is_empty()
+    }
+
+    pub fn next(&mut self) {
+        let Some(cursor) = self
+            .page
+            .read()
+            .as_ref()
+            .and_then(|page| page.next_cursor.clone())
+        else {
+            return;
+        };
+        let current = self.query.read().cursor.clone();
+        self.history.write().push(current);
+        self.query.write().cursor = Some(cursor);
+    }
+
+    pub fn previous(&mut self) {
+        let Some(cursor) = self.history.write().pop() else {
+            return;
+        };
+        self.query.write().cursor = cursor;
+    }
+
+    /// Sorts by `field`, ascending first and descending when it is already sorted ascending
+    pub fn sort_by(&mut self, field: &str) {
+        let direction = match self.query.read().sort.first() {
+            Some(key) if key.field == field && key.direction == SortDirection::Asc => {
+                SortDirection::Desc
+            }
+            _ => SortDirection::Asc,
+        };
+        self.update(|query| {
+            query.sort.clear();
+            *query = std::mem::take(query).sort_by(field, direction);
+        });
+    }
+
+    /// Replaces the filter on `field` with `op`, an empty value removes it
+    pub fn filter(&mut self, field: &str, op: FilterOp, value: impl Into<String>) {
+        let value = value.into();
+        self.update(|query| {
+            query
+                .filters
+                .retain(|filter| filter.field != field || filter.op != op);
+            if !value.trim().is_empty() {
+                *query = std::mem::take(query).filter(field, op, value.trim());
+            }
+        });
+    }
+
+    /// Starts over with another query, e.g. the one of a search form
+    pub fn search(&mut self, query: ListQuery) {
+        self.update(|current| *current = query);
+    }
+
+    /// Fetches the current page again, e.g. after a change
+    pub fn reload(&mut self) {
+        *self.reloads.write() += 1;
+    }
+
+    /// Changes the query and goes back to the first page
+    fn update(&mut self, change: impl FnOnce(&mut ListQuery)) {
+        self.history.write().clear();
+        self.query.with_mut(|query| {
+            change(query);
+            query.cursor = None;
+            query.page = None;
+        });
+    }
+}
+
+/// Reads the list endpoint `E` (e.g. `ListLanguages`) page by page with `query` as the first
+/// query
+pub fn use_page<E, T>(query: impl FnOnce() -> ListQuery) -> UsePage<T>
+where
+    E: Endpoint<Params = (), Request = ListQuery, Response = Page<T>> + 'static,
+    T: Clone + 'static,
+{
+    let query = use_signal(query);
+    let mut page: Signal<Option<Page<T>>> = use_signal(|| None);
+    let mut error = use_signal(|| None::<String>);
+    let history = use_signal(Vec::new);
+    let reloads = use_signal(|| 0);
+
+    use_effect(move || {
+        // Subscribes the effect to `reload`
+        reloads.read();
+        let query = query();
+        spawn(async move {
+            match call_list_endpoint::<E>(crate::enviroment::base_url(), &(), &query).await {
+                Ok(rows) => {
+                    error.set(None);
+                    page.set(Some(rows));
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    });
+
+    UsePage {
+        query,
+        page,
+        error,
+        history,
+        reloads,
+    }
+}
+
+#[derive(Clone, PartialEq, Props)]
+pub struct PagerProps {
+    pub total: i64,
+    pub number: usize,
+    pub has_previous: bool,
+    pub has_next: bool,
+    pub on_previous: EventHandler<()>,
+    pub on_next: EventHandler<()>,
+}
+
+/// The total and the previous and next buttons under a paged list
+#[component]
+pub fn Pager(props: PagerProps) -> Element {
+    rsx! {
+        div { class: "flex justify-between items-center mt-4",
+            span { class: "text-sm text-gray-500", "{props.total} rows, page {props.number}" }
+            div { class: "flex gap-2",
+                button {
+                    class: "btn-sec",
+                    disabled: !props.has_previous,
+                    onclick: move |_| props.on_previous.call(()),
+                    "Previous"
+                }
+                button {
+                    class: "btn-sec",
+                    disabled: !props.has_next,
+                    onclick: move |_| props.on_next.call(()),
+                    "Next"
+                }
+            }
+        }
+    }
+}
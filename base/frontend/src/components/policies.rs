@@ -0,0 +1,688 @@
+use dioxus::prelude::*;
+use sam_ui::{
+    input::{Input, InputAppearance},
+    popup::{Msg, MsgConfig, Popup, PopupState, Spinner, Toast},
+    Menu, MenuItem,
+};
+use sam_util::{call_endpoint, endpoint_url, post_text};
+use serde_json::Value;
+use shared::{
+    attribute_schema::{AttributeSchema, AttributeSchemaFilter, ListAttributeSchemas},
+    policy::{
+        AddPolicy, Decision, DeletePolicy, Explanation, FixtureReport, ListPolicies, Policy,
+        PolicyPayload, RunPolicyFixture, SimulatePolicies, SimulatedContext, SimulatedResource,
+        SimulatedUser, SimulationRequest, UpdatePolicy,
+    },
+    user::{UserResponse, UserRole},
+};
+
+use sam_icon::icon;
+use wasm_bindgen::JsCast;
+
+const ROLES: [UserRole; 3] = [UserRole::SuperAdmin, UserRole::Admin, UserRole::User];
+
+const NEW_DOCUMENT: &str = r#"{
+    "effect": "permit",
+    "resource_type": "listing",
+    "actions": ["edit"],
+    "roles": ["User"],
+    "condition": { "op": "owner", "attribute": "owner_id" }
+}"#;
+
+const FIXTURE_EXAMPLE: &str = r#"cases:
+  - name: users edit their own listings
+    user: { id: u1, role: User }
+    resource: { type: listing, id: l1, attributes: { owner_id: u1 } }
+    action: edit
+    expect: allow
+"#;
+
+fn role_from_str(value: &str) -> Option<UserRole> {
+    ROLES
+        .iter()
+        .find(|role| format!("{:?}", role) == value)
+        .cloned()
+}
+
+/// Empty text is an empty object
+fn parse_object(text: &str, what: &str) -> Result<Value, String> {
+    if text.trim().is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    match serde_json::from_str::<Value>(text) {
+        Ok(value) if value.is_object() => Ok(value),
+        _ => Err(format!("{} must be a valid JSON object", what)),
+    }
+}
+
+fn decision_class(decision: &Decision) -> &'static str {
+    match decision {
+        Decision::Permit => "text-green-700",
+        Decision::Deny => "text-red-700",
+        Decision::NotApplicable => "text-gray-500",
+    }
+}
+
+fn decided_by(explanation: &Explanation) -> &str {
+    explanation.decided_by.as_deref().unwrap_or("no policy")
+}
+
+/// The operand reading the attribute in a policy condition
+fn operand(schema: &AttributeSchema) -> String {
+    let source = if schema.resource_type == "user" {
+        "user"
+    } else {
+        "resource"
+    };
+    format!(r#"{{ "{}": "{}" }}"#, source, schema.name)
+}
+
+/// Inserts the text at the cursor of the document editor, at the end when it has no focus
+fn insert_at_cursor(document: &str, text: &str) -> String {
+    let cursor = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|doc| doc.get_element_by_id("policy-document"))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlTextAreaElement>().ok())
+        .and_then(|textarea| textarea.selection_start().ok().flatten())
+        .map(|cursor| cursor as usize)
+        .unwrap_or(document.len());
+    // The cursor counts UTF-16 units
+    let index = document
+        .char_indices()
+        .scan(0, |units, (index, c)| {
+            let at = *units;
+            *units += c.len_utf16();
+            Some((at, index))
+        })
+        .find(|(units, _)| *units >= cursor)
+        .map_or(document.len(), |(_, index)| index);
+    format!("{}{}{}", &document[..index], text, &document[index..])
+}
+
+/// Reads the `json` of a response, or its message as the error
+pub(crate) async fn read_json<T: serde::de::DeserializeOwned>(
+    result: Result<gloo_net::http::Response, String>,
+) -> Result<T, String> {
+    let res = result?;
+    let user_res = res
+        .json::<UserResponse>()
+        .await
+        .map_err(|e| e.to_string())?;
+    match user_res.json() {
+        Some(json) if res.ok() => serde_json::from_value::<T>(json).map_err(|e| e.to_string()),
+        _ => Err(user_res.message()),
+    }
+}
+
+#[component]
+pub fn Policies() -> Element {
+    let mut policies: Signal<Option<Vec<Policy>>> = use_signal(|| None);
+    let mut reload = use_signal(|| 0);
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut success_msg = use_signal(|| MsgConfig::default());
+    let mut confirm_msg = use_signal(|| MsgConfig::default());
+    let mut show_form = use_signal(|| PopupState::Close);
+    let mut selected_policy = use_signal(|| None::<Policy>);
+    // The form is mounted again for every edit so its fields start from the selected policy
+    let mut editing = use_signal(|| false);
+
+    use_effect(move || {
+        let _ = reload();
+        spawn(async move {
+            match call_endpoint::<ListPolicies>(crate::enviroment::base_url(), &(), &()).await {
+                Ok(list) => policies.set(Some(list)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    });
+
+    let mut handle_delete = move |policy: Policy| {
+        confirm_msg.set(
+            MsgConfig::with_confirm(format!("The policy {} will be deleted.", policy.name))
+                .callback(move || {
+                    spawn(async move {
+                        let base_url = crate::enviroment::base_url();
+                        match call_endpoint::<DeletePolicy>(base_url, &policy.id, &()).await {
+                            Ok(message) => {
+                                success_msg.set(MsgConfig::with_success(message));
+                                reload += 1;
+                            }
+                            Err(e) => err_msg.set(MsgConfig::with_err(e)),
+                        }
+                    });
+                }),
+        );
+    };
+
+    rsx! {
+        div { class: "policies-container p-6",
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-2xl font-bold", "Policies" }
+                button {
+                    class: "btn",
+                    onclick: move |_| {
+                        selected_policy.set(None);
+                        editing.set(true);
+                        show_form.set(PopupState::Open);
+                    },
+                    "New policy"
+                }
+            }
+
+            if let Some(list) = policies() {
+                if list.is_empty() {
+                    div { class: "text-center py-8 text-gray-500", "No policies yet." }
+                } else {
+                    table { class: "table table-bordered w-full",
+                        thead {
+                            tr {
+                                th { class: "text-left p-3", "Position" }
+                                th { class: "text-left p-3", "Name" }
+                                th { class: "text-left p-3", "Effect" }
+                                th { class: "text-left p-3", "Resource" }
+                                th { class: "text-left p-3", "Status" }
+                                th { class: "text-center p-3 w-16", "Actions" }
+                            }
+                        }
+                        tbody {
+                            for policy in list.iter() {
+                                tr { key: "{policy.id}", class: "hover:bg-gray-50",
+                                    td { class: "p-3 border-b", "{policy.position}" }
+                                    td { class: "p-3 border-b",
+                                        div { "{policy.name}" }
+                                        if let Some(description) = &policy.description {
+                                            div { class: "text-sm text-gray-500", "{description}" }
+                                        }
+                                    }
+                                    td { class: "p-3 border-b", "{policy.document.effect:?}" }
+                                    td { class: "p-3 border-b", "{policy.document.resource_type}" }
+                                    td { class: "p-3 border-b",
+                                        if policy.active {
+                                            "Active"
+                                        } else {
+                                            "Inactive"
+                                        }
+                                    }
+                                    td { class: "p-3 border-b text-center relative",
+                                        Menu { custom_class: "dropdown_menu",
+                                            MenuItem {
+                                                trigger: rsx! {
+                                                    {icon!(LdEllipsis, 20)}
+                                                },
+                                                MenuItem {
+                                                    trigger: rsx! { "edit" },
+                                                    action: {
+                                                        let policy = policy.clone();
+                                                        move |_| {
+                                                            selected_policy.set(Some(policy.clone()));
+                                                            editing.set(true);
+                                                            show_form.set(PopupState::Open);
+                                                        }
+                                                    },
+                                                }
+                                                MenuItem {
+                                                    trigger: rsx! { "delete" },
+                                                    action: {
+                                                        let policy = policy.clone();
+                                                        move |_| handle_delete(policy.clone())
+                                                    },
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                div { class: "text-center py-8",
+                    div { class: "loading loading-spinner loading-lg" }
+                    div { class: "mt-2", "Loading policies..." }
+                }
+            }
+
+            Simulator {}
+            FixtureRunner {}
+
+            {Msg(err_msg())}
+            {Msg(confirm_msg())}
+            Popup {
+                state: show_form,
+                enter_anim_class: "animate__animated animate__zoomIn",
+                leave_anim_class: "animate__animated animate__zoomOut",
+                if editing() {
+                    PolicyForm {
+                        policy: selected_policy(),
+                        on_close: move |changed: bool| {
+                            show_form.set(PopupState::CloseWithAnimation);
+                            if changed {
+                                success_msg.set(MsgConfig::with_success("Policy saved successfully!"));
+                                reload += 1;
+                            }
+                            selected_policy.set(None);
+                            editing.set(false);
+                        },
+                    }
+                }
+            }
+            {Toast(success_msg())}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Props)]
+pub struct PolicyFormProps {
+    /// `None` creates a new policy
+    pub policy: Option<Policy>,
+    pub on_close: EventHandler<bool>,
+}
+
+#[component]
+pub fn PolicyForm(props: PolicyFormProps) -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let policy = props.policy.clone();
+    let mut name = use_signal(|| policy.as_ref().map(|p| p.name.clone()).unwrap_or_default());
+    let mut description = use_signal(|| {
+        policy
+            .as_ref()
+            .and_then(|p| p.description.clone())
+            .unwrap_or_default()
+    });
+    let mut position = use_signal(|| policy.as_ref().map_or(0, |p| p.position));
+    let mut active = use_signal(|| policy.as_ref().map_or(true, |p| p.active));
+    let mut document = use_signal(|| match &policy {
+        Some(p) => serde_json::to_string_pretty(&p.document).unwrap_or_default(),
+        None => NEW_DOCUMENT.to_string(),
+    });
+    let policy_id = policy.as_ref().map(|p| p.id);
+    let mut schemas = use_signal(Vec::<AttributeSchema>::new);
+
+    // The known attributes help writing conditions
+    use_effect(move || {
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let filter = AttributeSchemaFilter::default();
+            if let Ok(list) = call_endpoint::<ListAttributeSchemas>(base_url, &(), &filter).await {
+                schemas.set(list);
+            }
+        });
+    });
+
+    let handle_submit = move |_| {
+        let document = match serde_json::from_str(&document()) {
+            Ok(document) => document,
+            Err(e) => {
+                err_msg.set(MsgConfig::with_err(format!("Invalid document: {}", e)));
+                return;
+            }
+        };
+        let payload = PolicyPayload {
+            name: name(),
+            description: Some(description()).filter(|d| !d.trim().is_empty()),
+            document,
+            active: active(),
+            position: position(),
+        };
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let result = match policy_id {
+                Some(id) => call_endpoint::<UpdatePolicy>(base_url, &id, &payload)
+                    .await
+                    .map(|_| ()),
+                None => call_endpoint::<AddPolicy>(base_url, &(), &payload)
+                    .await
+                    .map(|_| ()),
+            };
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(()) => props.on_close.call(true),
+                Err(e) => {
+                    err_msg.set(MsgConfig::with_err(e));
+                }
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "policy-form m-2.5",
+            h2 { class: "text-xl font-bold",
+                if policy_id.is_some() {
+                    "Edit policy"
+                } else {
+                    "New policy"
+                }
+            }
+            div { class: "flex flex-col gap-4 mt-8",
+                Input {
+                    name: "name",
+                    appearance: InputAppearance::square,
+                    label: "Name",
+                    value: name(),
+                    oninput: move |evt: FormEvent| name.set(evt.value()),
+                }
+                Input {
+                    name: "description",
+                    appearance: InputAppearance::square,
+                    label: "Description",
+                    value: description(),
+                    oninput: move |evt: FormEvent| description.set(evt.value()),
+                }
+                Input {
+                    name: "position",
+                    appearance: InputAppearance::square,
+                    label: "Position",
+                    value: position().to_string(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<i32>() {
+                            position.set(value);
+                        }
+                    },
+                }
+                label { class: "flex gap-2 items-center",
+                    input {
+                        r#type: "checkbox",
+                        checked: active(),
+                        onchange: move |evt: FormEvent| active.set(evt.checked()),
+                    }
+                    "Active"
+                }
+                textarea {
+                    id: "policy-document",
+                    class: "border p-2 w-full font-mono",
+                    rows: 14,
+                    value: document(),
+                    oninput: move |evt: FormEvent| document.set(evt.value()),
+                }
+                if !schemas().is_empty() {
+                    div { class: "flex flex-wrap gap-2",
+                        span { class: "text-sm text-gray-500", "Known attributes:" }
+                        for schema in schemas() {
+                            button {
+                                key: "{schema.id}",
+                                class: "btn-sec text-sm",
+                                title: "{schema.resource_type}, {schema.kind}",
+                                onclick: {
+                                    let text = operand(&schema);
+                                    move |_| {
+                                        let updated = insert_at_cursor(&document(), &text);
+                                        document.set(updated);
+                                    }
+                                },
+                                "{schema.resource_type}.{schema.name}"
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "flex justify-end gap-2 mt-6",
+                button {
+                    class: "btn-sec",
+                    onclick: move |_| props.on_close.call(false),
+                    "Cancel"
+                }
+                button { class: "btn", onclick: handle_submit, "Save" }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
+
+/// Explains a request against the stored policies
+#[component]
+pub fn Simulator() -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let mut user_id = use_signal(String::new);
+    let mut role = use_signal(|| None::<UserRole>);
+    let mut extra_roles = use_signal(String::new);
+    let mut user_attributes = use_signal(String::new);
+    let mut resource_type = use_signal(String::new);
+    let mut resource_id = use_signal(String::new);
+    let mut resource_attributes = use_signal(String::new);
+    let mut action = use_signal(String::new);
+    let mut context_attributes = use_signal(String::new);
+    let mut explanation = use_signal(|| None::<Explanation>);
+
+    let handle_simulate = move |_| {
+        let request = (|| {
+            Ok::<_, String>(SimulationRequest {
+                user: SimulatedUser {
+                    id: Some(user_id()).filter(|id| !id.trim().is_empty()),
+                    email: None,
+                    role: role(),
+                    roles: extra_roles()
+                        .split(',')
+                        .map(|role| role.trim().to_string())
+                        .filter(|role| !role.is_empty())
+                        .collect(),
+                    attributes: parse_object(&user_attributes(), "User attributes")?,
+                },
+                resource: SimulatedResource {
+                    resource_type: resource_type(),
+                    id: resource_id(),
+                    attributes: parse_object(&resource_attributes(), "Resource attributes")?,
+                },
+                action: action(),
+                context: SimulatedContext {
+                    ip: None,
+                    attributes: parse_object(&context_attributes(), "Context")?,
+                },
+                policies: None,
+                algorithm: None,
+            })
+        })();
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => {
+                err_msg.set(MsgConfig::with_err(e));
+                return;
+            }
+        };
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let result = call_endpoint::<SimulatePolicies>(base_url, &(), &request).await;
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(result) => explanation.set(Some(result)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "policy-simulator mt-10",
+            h2 { class: "text-xl font-bold mb-4", "Simulator" }
+            div { class: "grid grid-cols-2 gap-4",
+                Input {
+                    name: "user_id",
+                    appearance: InputAppearance::square,
+                    label: "User id (loads the user when no role is chosen)",
+                    value: user_id(),
+                    oninput: move |evt: FormEvent| user_id.set(evt.value()),
+                }
+                select {
+                    class: "border p-2",
+                    onchange: move |evt: FormEvent| role.set(role_from_str(&evt.value())),
+                    option { value: "", "Existing user" }
+                    for r in ROLES.iter() {
+                        option { value: "{r:?}", "{r:?}" }
+                    }
+                }
+                Input {
+                    name: "extra_roles",
+                    appearance: InputAppearance::square,
+                    label: "Extra roles, comma separated",
+                    value: extra_roles(),
+                    oninput: move |evt: FormEvent| extra_roles.set(evt.value()),
+                }
+                Input {
+                    name: "resource_type",
+                    appearance: InputAppearance::square,
+                    label: "Resource type",
+                    value: resource_type(),
+                    oninput: move |evt: FormEvent| resource_type.set(evt.value()),
+                }
+                Input {
+                    name: "resource_id",
+                    appearance: InputAppearance::square,
+                    label: "Resource id",
+                    value: resource_id(),
+                    oninput: move |evt: FormEvent| resource_id.set(evt.value()),
+                }
+                Input {
+                    name: "action",
+                    appearance: InputAppearance::square,
+                    label: "Action",
+                    value: action(),
+                    oninput: move |evt: FormEvent| action.set(evt.value()),
+                }
+            }
+            div { class: "grid grid-cols-3 gap-4 mt-4",
+                textarea {
+                    class: "border p-2 font-mono",
+                    rows: 6,
+                    placeholder: "User attributes (JSON)",
+                    value: user_attributes(),
+                    oninput: move |evt: FormEvent| user_attributes.set(evt.value()),
+                }
+                textarea {
+                    class: "border p-2 font-mono",
+                    rows: 6,
+                    placeholder: "Resource attributes (JSON)",
+                    value: resource_attributes(),
+                    oninput: move |evt: FormEvent| resource_attributes.set(evt.value()),
+                }
+                textarea {
+                    class: "border p-2 font-mono",
+                    rows: 6,
+                    placeholder: "Context (JSON), e.g. {{ \"time\": \"2025-08-18T10:00:00Z\" }}",
+                    value: context_attributes(),
+                    oninput: move |evt: FormEvent| context_attributes.set(evt.value()),
+                }
+            }
+            div { class: "flex justify-end mt-4",
+                button { class: "btn", onclick: handle_simulate, "Simulate" }
+            }
+
+            if let Some(explanation) = explanation() {
+                div { class: "mt-4",
+                    div { class: format!("text-lg font-bold {}", decision_class(&explanation.decision)),
+                        if explanation.allowed {
+                            "Allowed"
+                        } else {
+                            "Denied"
+                        }
+                    }
+                    div { class: "text-sm text-gray-500",
+                        "{explanation.algorithm}, decided by {decided_by(&explanation)}"
+                    }
+                    ExplanationTable { explanation: explanation.clone() }
+                }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
+
+#[component]
+fn ExplanationTable(explanation: Explanation) -> Element {
+    rsx! {
+        table { class: "table table-bordered w-full mt-2",
+            thead {
+                tr {
+                    th { class: "text-left p-3", "Policy" }
+                    th { class: "text-left p-3", "Decision" }
+                    th { class: "text-left p-3", "Reason" }
+                }
+            }
+            tbody {
+                for result in explanation.results.iter() {
+                    tr {
+                        td { class: "p-3 border-b", "{result.policy}" }
+                        td { class: format!("p-3 border-b {}", decision_class(&result.decision)),
+                            "{result.decision:?}"
+                        }
+                        td { class: "p-3 border-b", "{result.reason}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Runs a YAML or JSON fixture of expected decisions
+#[component]
+pub fn FixtureRunner() -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let mut fixture = use_signal(|| FIXTURE_EXAMPLE.to_string());
+    let mut content_type = use_signal(|| "application/yaml".to_string());
+    let mut report = use_signal(|| None::<FixtureReport>);
+
+    let handle_run = move |_| {
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let url = endpoint_url::<RunPolicyFixture>(crate::enviroment::base_url(), &());
+            let result =
+                read_json::<FixtureReport>(post_text(&url, &content_type(), &fixture()).await)
+                    .await;
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(result) => report.set(Some(result)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "policy-fixtures mt-10",
+            div { class: "flex justify-between items-center mb-4",
+                h2 { class: "text-xl font-bold", "Fixtures" }
+                select {
+                    class: "border p-2",
+                    onchange: move |evt: FormEvent| content_type.set(evt.value()),
+                    option { value: "application/yaml", "YAML" }
+                    option { value: "application/json", "JSON" }
+                }
+            }
+            textarea {
+                class: "border p-2 w-full font-mono",
+                rows: 14,
+                value: fixture(),
+                oninput: move |evt: FormEvent| fixture.set(evt.value()),
+            }
+            div { class: "flex justify-end mt-4",
+                button { class: "btn", onclick: handle_run, "Run" }
+            }
+
+            if let Some(report) = report() {
+                div { class: "mt-4",
+                    div { class: "text-lg font-bold",
+                        "{report.passed} passed, {report.failed} failed"
+                    }
+                    for result in report.results.iter() {
+                        details { class: "border-b p-2",
+                            summary { class: if result.passed { "text-green-700" } else { "text-red-700" },
+                                "{result.name}: expected {result.expected:?}, "
+                                if result.explanation.allowed {
+                                    "allowed"
+                                } else {
+                                    "denied"
+                                }
+                            }
+                            ExplanationTable { explanation: result.explanation.clone() }
+                        }
+                    }
+                }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
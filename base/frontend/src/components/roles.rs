@@ -0,0 +1,295 @@
+use dioxus::prelude::*;
+use sam_ui::{
+    input::{Input, InputAppearance},
+    popup::{Msg, MsgConfig, Popup, PopupState, Spinner, Toast},
+    Menu, MenuItem,
+};
+use sam_util::call_endpoint;
+use shared::role::{AddRole, DeleteRole, ListRoles, Role, RolePayload, UpdateRole};
+
+use sam_icon::icon;
+
+#[component]
+pub fn Roles() -> Element {
+    let mut roles: Signal<Option<Vec<Role>>> = use_signal(|| None);
+    let mut reload = use_signal(|| 0);
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut success_msg = use_signal(|| MsgConfig::default());
+    let mut confirm_msg = use_signal(|| MsgConfig::default());
+    let mut show_form = use_signal(|| PopupState::Close);
+    let mut selected_role = use_signal(|| None::<Role>);
+    // The form is mounted again for every edit so its fields start from the selected role
+    let mut editing = use_signal(|| false);
+
+    use_effect(move || {
+        let _ = reload();
+        spawn(async move {
+            match call_endpoint::<ListRoles>(crate::enviroment::base_url(), &(), &()).await {
+                Ok(list) => roles.set(Some(list)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    });
+
+    let mut handle_delete = move |role: Role| {
+        confirm_msg.set(
+            MsgConfig::with_confirm(format!(
+                "The role {} will be deleted and taken from its users.",
+                role.name
+            ))
+            .callback(move || {
+                spawn(async move {
+                    let base_url = crate::enviroment::base_url();
+                    match call_endpoint::<DeleteRole>(base_url, &role.id, &()).await {
+                        Ok(message) => {
+                            success_msg.set(MsgConfig::with_success(message));
+                            reload += 1;
+                        }
+                        Err(e) => err_msg.set(MsgConfig::with_err(e)),
+                    }
+                });
+            }),
+        );
+    };
+
+    rsx! {
+        div { class: "roles-container p-6",
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-2xl font-bold", "Roles" }
+                button {
+                    class: "btn",
+                    onclick: move |_| {
+                        selected_role.set(None);
+                        editing.set(true);
+                        show_form.set(PopupState::Open);
+                    },
+                    "New role"
+                }
+            }
+
+            if let Some(list) = roles() {
+                table { class: "table table-bordered w-full",
+                    thead {
+                        tr {
+                            th { class: "text-left p-3", "Name" }
+                            th { class: "text-left p-3", "Permissions" }
+                            th { class: "text-left p-3", "Inherits from" }
+                            th { class: "text-center p-3 w-16", "Actions" }
+                        }
+                    }
+                    tbody {
+                        for role in list.iter() {
+                            tr { key: "{role.id}", class: "hover:bg-gray-50",
+                                td { class: "p-3 border-b",
+                                    div { "{role.name}" }
+                                    if let Some(description) = &role.description {
+                                        div { class: "text-sm text-gray-500", "{description}" }
+                                    }
+                                }
+                                td { class: "p-3 border-b font-mono", {role.permissions.join(", ")} }
+                                td { class: "p-3 border-b", {role.parents.join(", ")} }
+                                td { class: "p-3 border-b text-center relative",
+                                    Menu { custom_class: "dropdown_menu",
+                                        MenuItem {
+                                            trigger: rsx! {
+                                                {icon!(LdEllipsis, 20)}
+                                            },
+                                            MenuItem {
+                                                trigger: rsx! { "edit" },
+                                                action: {
+                                                    let role = role.clone();
+                                                    move |_| {
+                                                        selected_role.set(Some(role.clone()));
+                                                        editing.set(true);
+                                                        show_form.set(PopupState::Open);
+                                                    }
+                                                },
+                                            }
+                                            // Built-in roles back the role of every user
+                                            if !role.is_built_in() {
+                                                MenuItem {
+                                                    trigger: rsx! { "delete" },
+                                                    action: {
+                                                        let role = role.clone();
+                                                        move |_| handle_delete(role.clone())
+                                                    },
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                div { class: "text-center py-8",
+                    div { class: "loading loading-spinner loading-lg" }
+                    div { class: "mt-2", "Loading roles..." }
+                }
+            }
+
+            {Msg(err_msg())}
+            {Msg(confirm_msg())}
+            Popup {
+                state: show_form,
+                enter_anim_class: "animate__animated animate__zoomIn",
+                leave_anim_class: "animate__animated animate__zoomOut",
+                if editing() {
+                    RoleForm {
+                        role: selected_role(),
+                        roles: roles().unwrap_or_default(),
+                        on_close: move |changed: bool| {
+                            show_form.set(PopupState::CloseWithAnimation);
+                            if changed {
+                                success_msg.set(MsgConfig::with_success("Role saved successfully!"));
+                                reload += 1;
+                            }
+                            selected_role.set(None);
+                            editing.set(false);
+                        },
+                    }
+                }
+            }
+            {Toast(success_msg())}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Props)]
+pub struct RoleFormProps {
+    /// `None` creates a new role
+    pub role: Option<Role>,
+    /// Every role, to choose the parents from
+    pub roles: Vec<Role>,
+    pub on_close: EventHandler<bool>,
+}
+
+#[component]
+pub fn RoleForm(props: RoleFormProps) -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let role = props.role.clone();
+    let mut name = use_signal(|| role.as_ref().map(|r| r.name.clone()).unwrap_or_default());
+    let mut description = use_signal(|| {
+        role.as_ref()
+            .and_then(|r| r.description.clone())
+            .unwrap_or_default()
+    });
+    let mut permissions = use_signal(|| {
+        role.as_ref()
+            .map(|r| r.permissions.join(", "))
+            .unwrap_or_default()
+    });
+    let mut parents = use_signal(|| role.as_ref().map(|r| r.parents.clone()).unwrap_or_default());
+    let role_id = role.as_ref().map(|r| r.id);
+    let built_in = role.as_ref().is_some_and(|r| r.is_built_in());
+
+    let handle_submit = move |_| {
+        let payload = RolePayload {
+            name: name(),
+            description: Some(description()).filter(|d| !d.trim().is_empty()),
+            permissions: permissions()
+                .split(',')
+                .map(|permission| permission.trim().to_string())
+                .filter(|permission| !permission.is_empty())
+                .collect(),
+            parents: parents(),
+        };
+        if let Err(e) = payload.validate() {
+            err_msg.set(MsgConfig::with_err(e));
+            return;
+        }
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let result = match role_id {
+                Some(id) => call_endpoint::<UpdateRole>(base_url, &id, &payload)
+                    .await
+                    .map(|_| ()),
+                None => call_endpoint::<AddRole>(base_url, &(), &payload)
+                    .await
+                    .map(|_| ()),
+            };
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(()) => props.on_close.call(true),
+                Err(e) => {
+                    err_msg.set(MsgConfig::with_err(e));
+                }
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "role-form m-2.5",
+            h2 { class: "text-xl font-bold",
+                if role_id.is_some() {
+                    "Edit role"
+                } else {
+                    "New role"
+                }
+            }
+            div { class: "flex flex-col gap-4 mt-8",
+                // Built-in roles are named after the role of the users
+                if !built_in {
+                    Input {
+                        name: "name",
+                        appearance: InputAppearance::square,
+                        label: "Name",
+                        value: name(),
+                        oninput: move |evt: FormEvent| name.set(evt.value()),
+                    }
+                }
+                Input {
+                    name: "description",
+                    appearance: InputAppearance::square,
+                    label: "Description",
+                    value: description(),
+                    oninput: move |evt: FormEvent| description.set(evt.value()),
+                }
+                Input {
+                    name: "permissions",
+                    appearance: InputAppearance::square,
+                    label: "Permissions, e.g. category:write, user:*",
+                    value: permissions(),
+                    oninput: move |evt: FormEvent| permissions.set(evt.value()),
+                }
+                div { class: "flex flex-col gap-2",
+                    span { class: "text-sm text-gray-500", "Inherits from" }
+                    for other in props.roles.iter().filter(|other| Some(other.id) != role_id) {
+                        label { key: "{other.id}", class: "flex gap-2 items-center",
+                            input {
+                                r#type: "checkbox",
+                                checked: parents().contains(&other.name),
+                                onchange: {
+                                    let other_name = other.name.clone();
+                                    move |evt: FormEvent| {
+                                        let other_name = other_name.clone();
+                                        parents.with_mut(|parents| {
+                                            parents.retain(|parent| *parent != other_name);
+                                            if evt.checked() {
+                                                parents.push(other_name);
+                                            }
+                                        });
+                                    }
+                                },
+                            }
+                            "{other.name}"
+                        }
+                    }
+                }
+            }
+            div { class: "flex justify-end gap-2 mt-6",
+                button {
+                    class: "btn-sec",
+                    onclick: move |_| props.on_close.call(false),
+                    "Cancel"
+                }
+                button { class: "btn", onclick: handle_submit, "Save" }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
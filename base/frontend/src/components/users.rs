@@ -0,0 +1,567 @@
+use dioxus::{logger::tracing::info, prelude::*};
+use sam_ui::{
+    input::{Input, InputAppearance},
+    popup::{Msg, MsgConfig, Popup, PopupState, Spinner, Toast},
+    Menu, MenuItem,
+};
+use sam_util::{call_endpoint, set_session_storage};
+use shared::{
+    role::{ListRoles, Role, UserRolesPayload},
+    user::{
+        ChangeRolePayload, ChangeUserRole, ForcePasswordReset, Impersonate, ListUsers,
+        SetDisabledPayload, SetUserAttributes, SetUserDisabled, SetUserRoles, UserFilter, UserList,
+        UserRole, UserSummary,
+    },
+};
+
+use sam_icon::icon;
+
+const ROLES: [UserRole; 3] = [UserRole::SuperAdmin, UserRole::Admin, UserRole::User];
+
+fn role_from_str(value: &str) -> Option<UserRole> {
+    ROLES
+        .iter()
+        .find(|role| format!("{:?}", role) == value)
+        .cloned()
+}
+
+#[component]
+pub fn Users() -> Element {
+    let mut filter = use_signal(UserFilter::default);
+    let mut search = use_signal(String::new);
+    let mut users_resource: Signal<Option<UserList>> = use_signal(|| None);
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut success_msg = use_signal(|| MsgConfig::default());
+    let mut confirm_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let mut show_role_form = use_signal(|| PopupState::Close);
+    let mut show_attributes_form = use_signal(|| PopupState::Close);
+    let mut show_roles_form = use_signal(|| PopupState::Close);
+    let mut selected_user = use_signal(|| None::<UserSummary>);
+
+    // Fetch users whenever the filter changes
+    use_effect(move || {
+        let filter = filter();
+        spawn(async move {
+            match call_endpoint::<ListUsers>(crate::enviroment::base_url(), &(), &filter).await {
+                Ok(list) => users_resource.set(Some(list)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    });
+
+    let mut refresh = move || {
+        // Writing the same filter triggers a refetch
+        let current = filter();
+        filter.set(current);
+    };
+
+    // Show the server message of an action
+    let mut show_result = move |result: Result<String, String>| {
+        spinner_state.set(PopupState::Close);
+        match result {
+            Ok(message) => {
+                success_msg.set(MsgConfig::with_success(message));
+                refresh();
+            }
+            Err(e) => err_msg.set(MsgConfig::with_err(e)),
+        }
+    };
+
+    let mut handle_toggle_disabled = move |user: UserSummary| {
+        let payload = SetDisabledPayload {
+            disabled: !user.disabled,
+        };
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            show_result(call_endpoint::<SetUserDisabled>(base_url, &user.id, &payload).await);
+        });
+    };
+
+    let mut handle_force_reset = move |user: UserSummary| {
+        confirm_msg.set(
+            MsgConfig::with_confirm(format!(
+                "{} will be logged out and has to reset the password.",
+                user.email
+            ))
+            .callback(move || {
+                spinner_state.set(PopupState::Open);
+                spawn(async move {
+                    let base_url = crate::enviroment::base_url();
+                    show_result(call_endpoint::<ForcePasswordReset>(base_url, &user.id, &()).await);
+                });
+            }),
+        );
+    };
+
+    let mut handle_impersonate = move |user: UserSummary| {
+        confirm_msg.set(
+            MsgConfig::with_confirm(format!("You will be logged in as {}.", user.email)).callback(
+                move || {
+                    let user = user.clone();
+                    spawn(async move {
+                        let base_url = crate::enviroment::base_url();
+                        match call_endpoint::<Impersonate>(base_url, &user.id, &()).await {
+                            Ok(_) => {
+                                // The dashboard shows a banner to go back to the admin session
+                                set_session_storage("impersonating", user.email.as_str());
+                                let nav = use_navigator();
+                                nav.push("/");
+                            }
+                            Err(e) => {
+                                err_msg.set(MsgConfig::with_err(e));
+                            }
+                        }
+                    });
+                },
+            ),
+        );
+    };
+
+    let mut set_page = move |page: i64| {
+        filter.with_mut(|f| f.page = Some(page));
+    };
+
+    rsx! {
+        div { class: "users-container p-6",
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-2xl font-bold", "Users" }
+            }
+
+            // Filters
+            div { class: "flex gap-4 items-end mb-6",
+                Input {
+                    name: "search",
+                    appearance: InputAppearance::square,
+                    label: "Search by email",
+                    value: search(),
+                    oninput: move |evt: FormEvent| {
+                        search.set(evt.value());
+                        filter.with_mut(|f| {
+                            f.search = Some(evt.value());
+                            f.page = Some(1);
+                        });
+                    },
+                }
+                select {
+                    class: "border p-2",
+                    onchange: move |evt: FormEvent| {
+                        filter.with_mut(|f| {
+                            f.role = role_from_str(&evt.value());
+                            f.page = Some(1);
+                        });
+                    },
+                    option { value: "", "All roles" }
+                    for role in ROLES.iter() {
+                        option { value: "{role:?}", "{role:?}" }
+                    }
+                }
+                select {
+                    class: "border p-2",
+                    onchange: move |evt: FormEvent| {
+                        filter.with_mut(|f| {
+                            f.verified = evt.value().parse::<bool>().ok();
+                            f.page = Some(1);
+                        });
+                    },
+                    option { value: "", "Any verification state" }
+                    option { value: "true", "Verified" }
+                    option { value: "false", "Not verified" }
+                }
+            }
+
+            if let Some(list) = users_resource() {
+                if list.users.is_empty() {
+                    div { class: "text-center py-8 text-gray-500", "No users found." }
+                } else {
+                    table { class: "table table-bordered w-full",
+                        thead {
+                            tr {
+                                th { class: "text-left p-3", "Email" }
+                                th { class: "text-left p-3", "Role" }
+                                th { class: "text-left p-3", "Extra roles" }
+                                th { class: "text-left p-3", "Verified" }
+                                th { class: "text-left p-3", "Status" }
+                                th { class: "text-center p-3 w-16", "Actions" }
+                            }
+                        }
+                        tbody {
+                            for user in list.users.iter() {
+                                tr { key: "{user.id}", class: "hover:bg-gray-50",
+                                    td { class: "p-3 border-b", "{user.email}" }
+                                    td { class: "p-3 border-b",
+                                        if let Some(role) = &user.role {
+                                            "{role:?}"
+                                        } else {
+                                            "-"
+                                        }
+                                    }
+                                    td { class: "p-3 border-b", {user.roles.join(", ")} }
+                                    td { class: "p-3 border-b",
+                                        if user.verified {
+                                            "Yes"
+                                        } else {
+                                            "No"
+                                        }
+                                    }
+                                    td { class: "p-3 border-b",
+                                        if user.disabled {
+                                            "Disabled"
+                                        } else {
+                                            "Active"
+                                        }
+                                    }
+                                    td { class: "p-3 border-b text-center relative",
+                                        // Pending users can't be managed until they verify their email
+                                        if user.verified {
+                                            Menu { custom_class: "dropdown_menu",
+                                                MenuItem {
+                                                    trigger: rsx! {
+                                                        {icon!(LdEllipsis, 20)}
+                                                    },
+                                                    MenuItem {
+                                                        trigger: rsx! { "change role" },
+                                                        action: {
+                                                            let user = user.clone();
+                                                            move |_| {
+                                                                selected_user.set(Some(user.clone()));
+                                                                show_role_form.set(PopupState::Open);
+                                                            }
+                                                        },
+                                                    }
+                                                    MenuItem {
+                                                        trigger: rsx! { "edit roles" },
+                                                        action: {
+                                                            let user = user.clone();
+                                                            move |_| {
+                                                                selected_user.set(Some(user.clone()));
+                                                                show_roles_form.set(PopupState::Open);
+                                                            }
+                                                        },
+                                                    }
+                                                    MenuItem {
+                                                        trigger: rsx! { "edit attributes" },
+                                                        action: {
+                                                            let user = user.clone();
+                                                            move |_| {
+                                                                selected_user.set(Some(user.clone()));
+                                                                show_attributes_form.set(PopupState::Open);
+                                                            }
+                                                        },
+                                                    }
+                                                    MenuItem {
+                                                        trigger: rsx! {
+                                                            if user.disabled {
+                                                                "enable"
+                                                            } else {
+                                                                "disable"
+                                                            }
+                                                        },
+                                                        action: {
+                                                            let user = user.clone();
+                                                            move |_| handle_toggle_disabled(user.clone())
+                                                        },
+                                                    }
+                                                    MenuItem {
+                                                        trigger: rsx! { "force password reset" },
+                                                        action: {
+                                                            let user = user.clone();
+                                                            move |_| handle_force_reset(user.clone())
+                                                        },
+                                                    }
+                                                    MenuItem {
+                                                        trigger: rsx! { "impersonate" },
+                                                        action: {
+                                                            let user = user.clone();
+                                                            move |_| handle_impersonate(user.clone())
+                                                        },
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Pagination
+                div { class: "flex justify-between items-center mt-4",
+                    span { "{list.total} users" }
+                    div { class: "flex gap-2",
+                        button {
+                            class: "btn-sec",
+                            disabled: list.page <= 1,
+                            onclick: move |_| set_page(list.page - 1),
+                            "Previous"
+                        }
+                        span { class: "p-2", "Page {list.page}" }
+                        button {
+                            class: "btn-sec",
+                            disabled: list.page * list.per_page >= list.total,
+                            onclick: move |_| set_page(list.page + 1),
+                            "Next"
+                        }
+                    }
+                }
+            } else {
+                div { class: "text-center py-8",
+                    div { class: "loading loading-spinner loading-lg" }
+                    div { class: "mt-2", "Loading users..." }
+                }
+            }
+
+            {Msg(err_msg())}
+            {Msg(confirm_msg())}
+            Popup {
+                state: show_role_form,
+                enter_anim_class: "animate__animated animate__zoomIn",
+                leave_anim_class: "animate__animated animate__zoomOut",
+                if let Some(user) = selected_user() {
+                    ChangeRoleForm {
+                        user,
+                        on_close: move |changed: bool| {
+                            show_role_form.set(PopupState::CloseWithAnimation);
+                            if changed {
+                                success_msg.set(MsgConfig::with_success("Role changed successfully!"));
+                                refresh();
+                            }
+                            selected_user.set(None);
+                        },
+                    }
+                }
+            }
+            Popup {
+                state: show_roles_form,
+                enter_anim_class: "animate__animated animate__zoomIn",
+                leave_anim_class: "animate__animated animate__zoomOut",
+                if let Some(user) = selected_user() {
+                    RolesForm {
+                        user,
+                        on_close: move |changed: bool| {
+                            show_roles_form.set(PopupState::CloseWithAnimation);
+                            if changed {
+                                success_msg.set(MsgConfig::with_success("Roles updated successfully!"));
+                                refresh();
+                            }
+                            selected_user.set(None);
+                        },
+                    }
+                }
+            }
+            Popup {
+                state: show_attributes_form,
+                enter_anim_class: "animate__animated animate__zoomIn",
+                leave_anim_class: "animate__animated animate__zoomOut",
+                if let Some(user) = selected_user() {
+                    AttributesForm {
+                        user,
+                        on_close: move |changed: bool| {
+                            show_attributes_form.set(PopupState::CloseWithAnimation);
+                            if changed {
+                                success_msg.set(MsgConfig::with_success("Attributes updated successfully!"));
+                                refresh();
+                            }
+                            selected_user.set(None);
+                        },
+                    }
+                }
+            }
+            {Toast(success_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Props)]
+pub struct UserFormProps {
+    pub user: UserSummary,
+    pub on_close: EventHandler<bool>,
+}
+
+#[component]
+pub fn ChangeRoleForm(props: UserFormProps) -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let mut role = use_signal(|| props.user.role.clone().unwrap_or(UserRole::User));
+    let user_id = props.user.id;
+
+    let handle_submit = move |_| {
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let payload = ChangeRolePayload { role: role() };
+            let result =
+                call_endpoint::<ChangeUserRole>(crate::enviroment::base_url(), &user_id, &payload)
+                    .await;
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(_) => props.on_close.call(true),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "user-role-form m-2.5",
+            h2 { class: "text-xl font-bold", "Change role of {props.user.email}" }
+            div { class: "flex flex-col gap-4 mt-8",
+                select {
+                    class: "border p-2",
+                    onchange: move |evt: FormEvent| {
+                        if let Some(r) = role_from_str(&evt.value()) {
+                            role.set(r);
+                        }
+                    },
+                    for r in ROLES.iter() {
+                        option { value: "{r:?}", selected: *r == role(), "{r:?}" }
+                    }
+                }
+            }
+            div { class: "flex justify-end gap-2 mt-6",
+                button {
+                    class: "btn-sec",
+                    onclick: move |_| props.on_close.call(false),
+                    "Cancel"
+                }
+                button { class: "btn", onclick: handle_submit, "Save" }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
+
+/// Roles assigned on top of the built-in one, which the change role form sets
+#[component]
+pub fn RolesForm(props: UserFormProps) -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let mut all_roles = use_signal(Vec::<Role>::new);
+    let mut roles = use_signal(|| props.user.roles.clone());
+    let user_id = props.user.id;
+
+    use_effect(move || {
+        spawn(async move {
+            match call_endpoint::<ListRoles>(crate::enviroment::base_url(), &(), &()).await {
+                Ok(list) => all_roles.set(list),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    });
+
+    let handle_submit = move |_| {
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let payload = UserRolesPayload { roles: roles() };
+            let result =
+                call_endpoint::<SetUserRoles>(crate::enviroment::base_url(), &user_id, &payload)
+                    .await;
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(_) => props.on_close.call(true),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "user-roles-form m-2.5",
+            h2 { class: "text-xl font-bold", "Roles of {props.user.email}" }
+            div { class: "flex flex-col gap-2 mt-8",
+                for role in all_roles().into_iter().filter(|role| !role.is_built_in()) {
+                    label { key: "{role.id}", class: "flex gap-2 items-center",
+                        input {
+                            r#type: "checkbox",
+                            checked: roles().contains(&role.name),
+                            onchange: {
+                                let name = role.name.clone();
+                                move |evt: FormEvent| {
+                                    let name = name.clone();
+                                    roles.with_mut(|roles| {
+                                        roles.retain(|role| *role != name);
+                                        if evt.checked() {
+                                            roles.push(name);
+                                        }
+                                    });
+                                }
+                            },
+                        }
+                        "{role.name}"
+                        if let Some(description) = &role.description {
+                            span { class: "text-gray-500", "{description}" }
+                        }
+                    }
+                }
+            }
+            div { class: "flex justify-end gap-2 mt-6",
+                button {
+                    class: "btn-sec",
+                    onclick: move |_| props.on_close.call(false),
+                    "Cancel"
+                }
+                button { class: "btn", onclick: handle_submit, "Save" }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
+
+#[component]
+pub fn AttributesForm(props: UserFormProps) -> Element {
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let mut attributes =
+        use_signal(|| serde_json::to_string_pretty(&props.user.attributes).unwrap_or_default());
+    let user_id = props.user.id;
+
+    let handle_submit = move |_| {
+        let value = match serde_json::from_str::<serde_json::Value>(&attributes()) {
+            Ok(value) if value.is_object() => value,
+            _ => {
+                err_msg.set(MsgConfig::with_err(
+                    "Attributes must be a valid JSON object",
+                ));
+                return;
+            }
+        };
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let result =
+                call_endpoint::<SetUserAttributes>(crate::enviroment::base_url(), &user_id, &value)
+                    .await;
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(_) => props.on_close.call(true),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "user-attributes-form m-2.5",
+            h2 { class: "text-xl font-bold", "Attributes of {props.user.email}" }
+            textarea {
+                class: "border p-2 mt-8 w-full font-mono",
+                rows: 12,
+                value: attributes(),
+                oninput: move |evt: FormEvent| {
+                    info!("attributes: {}", evt.value());
+                    attributes.set(evt.value());
+                },
+            }
+            div { class: "flex justify-end gap-2 mt-6",
+                button {
+                    class: "btn-sec",
+                    onclick: move |_| props.on_close.call(false),
+                    "Cancel"
+                }
+                button { class: "btn", onclick: handle_submit, "Save" }
+            }
+            {Msg(err_msg())}
+            Spinner { state: spinner_state }
+        }
+    }
+}
@@ -1,7 +1,39 @@
-pub const BASE_URL: &str = if cfg!(debug_assertions) {
-    "https://localhost:3000"
-    // "https://127.0.0.1:3000"
-    // "http://127.0.0.1:3000"
-} else {
-    "https://some-domain"
+// Where the API is, read once when the app starts:
+// 1. `window.API_BASE_URL`, set by the `/env.js` of the backend before the app loads
+// 2. the `API_BASE_URL` environment variable when the app was built
+// 3. the backend of `dx serve` in debug builds, the origin of the page otherwise
+
+use std::sync::OnceLock;
+
+const DEFAULT_BASE_URL: &str = match option_env!("API_BASE_URL") {
+    Some(url) => url,
+    None if cfg!(debug_assertions) => "https://localhost:3000",
+    // The URLs stay relative to the page
+    None => "",
 };
+
+static BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// The URL of the API without a trailing `/`, e.g. `https://localhost:3000`
+pub fn base_url() -> &'static str {
+    BASE_URL.get_or_init(|| {
+        runtime_base_url()
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn runtime_base_url() -> Option<String> {
+    let window = web_sys::window()?;
+    js_sys::Reflect::get(&window, &"API_BASE_URL".into())
+        .ok()?
+        .as_string()
+}
+
+// Rendered on the server, there is no window
+#[cfg(not(target_arch = "wasm32"))]
+fn runtime_base_url() -> Option<String> {
+    None
+}
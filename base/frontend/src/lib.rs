@@ -1,10 +1,10 @@
 use dioxus::{logger::tracing::info, prelude::*};
 use input::*;
 use route::Route;
-use sam_util::fetch_data;
+use sam_util::call_endpoint;
 use shared::{
     dashboard::DashNavItemInfo,
-    user::{SharedUserState, UserInfo, UserResponse, UserState},
+    user::{CheckAuth, SharedUserState, UserInfo, UserState},
 };
 use std::{cell::RefCell, rc::Rc};
 
@@ -32,20 +32,9 @@ const TAILWIND: Asset = asset!("/assets/tailwind.css");
 pub fn App() -> Element {
     // Check auth and initialize the shared user state
     let user_resource: Resource<Option<UserInfo>> = use_resource(move || async move {
-        let url = format!("{}/users/check-auth", crate::enviroment::BASE_URL);
-        match fetch_data(&url).await {
-            Ok(res) => match res.json::<UserResponse>().await {
-                Ok(user_res) => {
-                    if user_res.has_json() {
-                        Some(serde_json::from_value::<UserInfo>(user_res.json().unwrap()).unwrap())
-                    } else {
-                        None
-                    }
-                }
-                Err(_) => None,
-            },
-            Err(_) => None,
-        }
+        call_endpoint::<CheckAuth>(crate::enviroment::base_url(), &(), &())
+            .await
+            .ok()
     });
 
     let mut user_state: Signal<Rc<RefCell<UserState>>> = use_signal(|| {
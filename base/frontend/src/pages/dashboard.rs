@@ -4,11 +4,10 @@ use std::{cell::RefCell, rc::Rc};
 use crate::{components::Guard, route::Route};
 use dioxus::{logger::tracing::info, prelude::*};
 use dioxus_html::nav;
-use sam_util::fetch_data;
-use shared::user::UserResponse;
+use sam_util::call_endpoint;
 use shared::{
-    dashboard::{DashNavItem, DashNavItemInfo},
-    user::UserRole,
+    dashboard::{DashNavItemInfo, DashboardNavItems},
+    user::{StopImpersonation, UserRole},
 };
 
 #[component]
@@ -29,7 +28,38 @@ pub fn DashboardPage() -> Element {
             div { class: "dashboard flex",
                 // Pass the nav items as props to avoid re-fetching
                 DashboardNavbar { nav_items: nav_items.read().borrow().clone() }
-                div { class: "content", Outlet::<Route> {} }
+                div { class: "content",
+                    ImpersonationBanner {}
+                    Outlet::<Route> {}
+                }
+            }
+        }
+    }
+}
+
+/// Shown while a super admin is logged in as another user
+#[component]
+pub fn ImpersonationBanner() -> Element {
+    let mut impersonating = use_signal(|| get_session_storage("impersonating"));
+
+    let handle_stop = move |_| {
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let stopped = call_endpoint::<StopImpersonation>(base_url, &(), &()).await;
+            if stopped.is_ok() {
+                remove_session_storage("impersonating");
+                impersonating.set(None);
+                let nav = use_navigator();
+                nav.push(Route::DashboardMiddleware {});
+            }
+        });
+    };
+
+    rsx! {
+        if let Some(email) = impersonating() {
+            div { class: "impersonation-banner flex justify-between items-center bg-yellow-200 p-2",
+                span { "You are logged in as {email}" }
+                button { class: "btn-sec", onclick: handle_stop, "Stop impersonation" }
             }
         }
     }
@@ -48,24 +78,9 @@ pub fn DashboardMiddleware() -> Element {
 
     // Move the resource here so it's only created once for the entire dashboard
     let dash_nav_items: Resource<Vec<DashNavItemInfo>> = use_resource(move || async move {
-        let url = format!("{}/users/dashboard/nav-items", crate::enviroment::BASE_URL);
-        match fetch_data(&url).await {
-            Ok(res) => match res.json::<UserResponse>().await {
-                Ok(user_res) => {
-                    if user_res.has_json() {
-                        let items = serde_json::from_value::<Vec<DashNavItemInfo>>(
-                            user_res.json().unwrap(),
-                        )
-                        .unwrap_or_default();
-                        items
-                    } else {
-                        vec![]
-                    }
-                }
-                Err(_) => vec![],
-            },
-            Err(_) => vec![],
-        }
+        call_endpoint::<DashboardNavItems>(crate::enviroment::base_url(), &(), &())
+            .await
+            .unwrap_or_default()
     });
 
     use_effect(move || {
@@ -101,15 +116,28 @@ pub fn DashboardMiddleware() -> Element {
 pub fn DashboardNavbar(nav_items: Vec<DashNavItemInfo>) -> Element {
     rsx! {
         nav { class: "dashboard-navbar w-64 bg-gray-800 min-h-screen",
-            ul {
-                for item in nav_items.iter() {
-                    li {
-                        key: "{item.route}",
-                        class: "nav-item bg-primary-500 text-white p-2",
-                        Link {
-                            to: "{item.route}",
-                            class: "nav-link block w-full text-left",
-                            "{item.name}"
+            NavItemList { nav_items }
+        }
+    }
+}
+
+/// Nested items are listed under their parent
+#[component]
+fn NavItemList(nav_items: Vec<DashNavItemInfo>) -> Element {
+    rsx! {
+        ul {
+            for item in nav_items.iter() {
+                li {
+                    key: "{item.route}",
+                    class: "nav-item bg-primary-500 text-white p-2",
+                    Link {
+                        to: "{item.route}",
+                        class: "nav-link block w-full text-left",
+                        "{item.name}"
+                    }
+                    if !item.sub_items.is_empty() {
+                        div { class: "pl-4",
+                            NavItemList { nav_items: item.sub_items.clone() }
                         }
                     }
                 }
@@ -3,7 +3,7 @@ use std::{cell::RefCell, rc::Rc};
 use dioxus::{logger::tracing::info, prelude::*};
 use sam_ui::{input::*, popup::*, spinner::SpinnerConfig};
 use sam_util::{
-    fetch_data, post_json,
+    call_endpoint, fetch_data,
     validators::{validate_email, validate_password},
 };
 use serde::{Deserialize, Serialize};
@@ -40,32 +40,27 @@ pub fn LoginPage() -> Element {
                 return;
             }
 
-            let url = format!("{}/users/login", crate::enviroment::BASE_URL);
-            match post_json(&url, &user).await {
-                Ok(res) => {
+            let base_url = crate::enviroment::base_url();
+            match call_endpoint::<Login>(base_url, &(), &user).await {
+                Ok(message) => {
                     spinner_state.set(PopupState::Close);
-                    let json: UserResponse = res.json().await.unwrap();
-                    if res.ok() {
-                        let user_state_rc = user_state();
-                        let redirect_to = user_state_rc.borrow().redirect_to.clone();
-                        let mut user_state_mut = user_state_rc.borrow_mut();
-                        user_state_mut.email = Some(user.email.clone());
-                        success_msg.set(MsgConfig::with_success(json.message()));
-
-                        let nav = use_navigator();
-                        if let Some(redirect) = redirect_to {
-                            nav.push(redirect);
-                            user_state_mut.redirect_to = None;
-                        } else {
-                            nav.push(Route::DashboardMiddleware {});
-                        }
+                    let user_state_rc = user_state();
+                    let redirect_to = user_state_rc.borrow().redirect_to.clone();
+                    let mut user_state_mut = user_state_rc.borrow_mut();
+                    user_state_mut.email = Some(user.email.clone());
+                    success_msg.set(MsgConfig::with_success(message));
+
+                    let nav = use_navigator();
+                    if let Some(redirect) = redirect_to {
+                        nav.push(redirect);
+                        user_state_mut.redirect_to = None;
                     } else {
-                        msg.set(MsgConfig::with_err(json.message()));
+                        nav.push(Route::DashboardMiddleware {});
                     }
                 }
                 Err(e) => {
                     spinner_state.set(PopupState::Close);
-                    msg.set(MsgConfig::with_err(e.to_string()));
+                    msg.set(MsgConfig::with_err(e));
                 }
             }
         }
@@ -1,7 +1,9 @@
 mod dashboard;
 mod home;
 mod login;
+mod profile;
 
 pub use dashboard::*;
 pub use home::*;
 pub use login::*;
+pub use profile::*;
@@ -0,0 +1,390 @@
+use dioxus::prelude::*;
+use sam_ui::{
+    input::{Input, InputAppearance},
+    popup::{Msg, MsgConfig, PopupState, Spinner, Toast},
+};
+use sam_util::{
+    call_all_pages, call_endpoint, delete_json, endpoint_url, post_form, put_json,
+    validators::{validate_email, PasswordError},
+};
+use shared::{
+    list::ListQuery,
+    user::{
+        ChangeEmail, ChangeEmailPayload, ChangePassword, ChangePasswordPayload, DataExport,
+        DataExportStatus, DeleteAccount, DeleteAccountPayload, DownloadDataExport, GetProfile,
+        ListDataExports, PreferredLanguagePayload, Profile, RequestDataExport,
+        SetPreferredLanguage, SharedUserState, UploadAvatar, UserResponse,
+    },
+    Language, ListLanguages,
+};
+use wasm_bindgen::JsCast;
+
+use crate::{components::Guard, route::Route};
+
+/// Turns an error response into a readable message.
+/// Password policy failures come as a list of rules.
+fn error_message(user_res: &UserResponse) -> String {
+    match user_res
+        .json()
+        .and_then(|json| serde_json::from_value::<Vec<PasswordError>>(json).ok())
+    {
+        Some(errors) => errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => user_res.message(),
+    }
+}
+
+fn form_value(evt: &FormEvent, name: &str) -> String {
+    evt.data()
+        .values()
+        .get(name)
+        .cloned()
+        .unwrap_or_default()
+        .as_value()
+}
+
+/// The file chosen in the avatar input wrapped in a multipart form
+fn avatar_form_data() -> Option<web_sys::FormData> {
+    let input = web_sys::window()?
+        .document()?
+        .get_element_by_id("avatar-input")?
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .ok()?;
+    let file = input.files()?.get(0)?;
+    let form = web_sys::FormData::new().ok()?;
+    form.append_with_blob("avatar", &file).ok()?;
+    Some(form)
+}
+
+#[component]
+pub fn ProfilePage() -> Element {
+    let mut profile: Signal<Option<Profile>> = use_signal(|| None);
+    let mut languages: Signal<Vec<Language>> = use_signal(Vec::new);
+    let mut exports: Signal<Vec<DataExport>> = use_signal(Vec::new);
+    let mut err_msg = use_signal(|| MsgConfig::default());
+    let mut success_msg = use_signal(|| MsgConfig::default());
+    let mut confirm_msg = use_signal(|| MsgConfig::default());
+    let mut spinner_state = use_signal(|| PopupState::Close);
+    let user_state = use_context::<Signal<SharedUserState>>();
+
+    let fetch_profile = move || {
+        spawn(async move {
+            match call_endpoint::<GetProfile>(crate::enviroment::base_url(), &(), &()).await {
+                Ok(p) => profile.set(Some(p)),
+                Err(e) => err_msg.set(MsgConfig::with_err(e)),
+            }
+        });
+    };
+
+    let fetch_exports = move || {
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            if let Ok(list) = call_endpoint::<ListDataExports>(base_url, &(), &()).await {
+                exports.set(list);
+            }
+        });
+    };
+
+    use_effect(move || {
+        fetch_profile();
+        fetch_exports();
+        spawn(async move {
+            let query = ListQuery::default().with_per_page(ListQuery::MAX_PER_PAGE);
+            let base_url = crate::enviroment::base_url();
+            if let Ok(found) = call_all_pages::<ListLanguages, _>(base_url, &(), &query).await {
+                languages.set(found.into_iter().filter(|l| l.active).collect());
+            }
+        });
+    });
+
+    // Shows the server message and refreshes the profile on success
+    let handle_response = move |res: Result<gloo_net::http::Response, String>| {
+        spawn(async move {
+            spinner_state.set(PopupState::Close);
+            match res {
+                Ok(res) => match res.json::<UserResponse>().await {
+                    Ok(user_res) => {
+                        if res.ok() {
+                            success_msg.set(MsgConfig::with_success(user_res.message()));
+                            fetch_profile();
+                        } else {
+                            err_msg.set(MsgConfig::with_err(error_message(&user_res)));
+                        }
+                    }
+                    Err(e) => err_msg.set(MsgConfig::with_err(e.to_string())),
+                },
+                Err(e) => {
+                    err_msg.set(MsgConfig::with_err(e));
+                }
+            }
+        });
+    };
+
+    let handle_change_email = move |evt: FormEvent| {
+        let payload = ChangeEmailPayload {
+            new_email: form_value(&evt, "new_email"),
+            password: form_value(&evt, "email_password"),
+        };
+        if let Err(e) = validate_email(&payload.new_email) {
+            err_msg.set(MsgConfig::with_err(e));
+            return;
+        }
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let url = endpoint_url::<ChangeEmail>(crate::enviroment::base_url(), &());
+            handle_response(put_json(&url, &payload).await);
+        });
+    };
+
+    let handle_change_password = move |evt: FormEvent| {
+        let payload = ChangePasswordPayload {
+            current_password: form_value(&evt, "current_password"),
+            new_password: form_value(&evt, "new_password"),
+        };
+        if payload.new_password != form_value(&evt, "confirm_password") {
+            err_msg.set(MsgConfig::with_err("The passwords don't match"));
+            return;
+        }
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let url = endpoint_url::<ChangePassword>(crate::enviroment::base_url(), &());
+            handle_response(put_json(&url, &payload).await);
+        });
+    };
+
+    let handle_language = move |evt: FormEvent| {
+        let payload = PreferredLanguagePayload {
+            language_id: evt.value().parse::<i32>().ok(),
+        };
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let url = endpoint_url::<SetPreferredLanguage>(crate::enviroment::base_url(), &());
+            handle_response(put_json(&url, &payload).await);
+        });
+    };
+
+    let handle_avatar = move |_| {
+        let Some(form) = avatar_form_data() else {
+            err_msg.set(MsgConfig::with_err("Choose an image first"));
+            return;
+        };
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let url = endpoint_url::<UploadAvatar>(crate::enviroment::base_url(), &());
+            handle_response(post_form(&url, form).await);
+        });
+    };
+
+    let handle_export = move |_| {
+        spinner_state.set(PopupState::Open);
+        spawn(async move {
+            let base_url = crate::enviroment::base_url();
+            let result = call_endpoint::<RequestDataExport>(base_url, &(), &()).await;
+            spinner_state.set(PopupState::Close);
+            match result {
+                Ok(_) => {
+                    success_msg.set(MsgConfig::with_success(
+                        "We will email you when your data is ready",
+                    ));
+                    fetch_exports();
+                }
+                Err(e) => {
+                    err_msg.set(MsgConfig::with_err(e));
+                }
+            }
+        });
+    };
+
+    let handle_delete = move |evt: FormEvent| {
+        let payload = DeleteAccountPayload {
+            password: form_value(&evt, "delete_password"),
+        };
+        confirm_msg.set(
+            MsgConfig::with_confirm("Your account and personal data will be deleted permanently.")
+                .callback(move || {
+                    let payload = payload.clone();
+                    spinner_state.set(PopupState::Open);
+                    spawn(async move {
+                        let url = endpoint_url::<DeleteAccount>(crate::enviroment::base_url(), &());
+                        match delete_json(&url, &payload).await {
+                            Ok(res) => {
+                                spinner_state.set(PopupState::Close);
+                                if res.ok() {
+                                    // The server removed the session cookie
+                                    user_state().borrow_mut().email = None;
+                                    let nav = use_navigator();
+                                    nav.push(Route::HomePage {});
+                                } else {
+                                    let user_res: UserResponse = res.json().await.unwrap();
+                                    err_msg.set(MsgConfig::with_err(user_res.message()));
+                                }
+                            }
+                            Err(e) => {
+                                spinner_state.set(PopupState::Close);
+                                err_msg.set(MsgConfig::with_err(e));
+                            }
+                        }
+                    });
+                }),
+        );
+    };
+
+    rsx! {
+        Guard { redirect_to: "/profile".to_string(),
+            div { class: "profile-page p-6 flex flex-col gap-8 max-w-xl",
+                h1 { class: "text-2xl font-bold", "Profile" }
+
+                if let Some(p) = profile() {
+                    // Avatar
+                    section { class: "flex items-center gap-4",
+                        if let Some(avatar) = &p.avatar {
+                            img {
+                                class: "w-20 h-20 rounded-full object-cover",
+                                src: "{crate::enviroment::base_url()}{avatar}",
+                            }
+                        }
+                        input {
+                            id: "avatar-input",
+                            r#type: "file",
+                            accept: "image/png,image/jpeg,image/gif,image/webp",
+                        }
+                        button { class: "btn-sec", onclick: handle_avatar, "Upload" }
+                    }
+
+                    // Email
+                    section {
+                        h2 { class: "text-xl font-bold", "Email" }
+                        p { "{p.email}" }
+                        if let Some(pending) = &p.pending_email {
+                            p { class: "text-gray-500",
+                                "Waiting for confirmation of {pending}"
+                            }
+                        }
+                        form {
+                            class: "flex flex-col gap-4 mt-4",
+                            onsubmit: handle_change_email,
+                            Input {
+                                name: "new_email",
+                                appearance: InputAppearance::square,
+                                label: "New email",
+                            }
+                            Input {
+                                name: "email_password",
+                                appearance: InputAppearance::square,
+                                r#type: "password",
+                                label: "Password",
+                            }
+                            button { class: "btn", r#type: "submit", "Change email" }
+                        }
+                    }
+
+                    // Password
+                    section {
+                        h2 { class: "text-xl font-bold", "Password" }
+                        form {
+                            class: "flex flex-col gap-4 mt-4",
+                            onsubmit: handle_change_password,
+                            Input {
+                                name: "current_password",
+                                appearance: InputAppearance::square,
+                                r#type: "password",
+                                label: "Current password",
+                            }
+                            Input {
+                                name: "new_password",
+                                appearance: InputAppearance::square,
+                                r#type: "password",
+                                label: "New password",
+                            }
+                            Input {
+                                name: "confirm_password",
+                                appearance: InputAppearance::square,
+                                r#type: "password",
+                                label: "Confirm new password",
+                            }
+                            button { class: "btn", r#type: "submit", "Change password" }
+                        }
+                    }
+
+                    // Language
+                    section {
+                        h2 { class: "text-xl font-bold", "Language" }
+                        select { class: "border p-2 mt-4", onchange: handle_language,
+                            option {
+                                value: "",
+                                selected: p.preferred_language_id.is_none(),
+                                "Browser default"
+                            }
+                            for lang in languages().iter() {
+                                option {
+                                    value: "{lang.id}",
+                                    selected: p.preferred_language_id == Some(lang.id),
+                                    "{lang.flag} {lang.name}"
+                                }
+                            }
+                        }
+                    }
+
+                    // GDPR export
+                    section {
+                        h2 { class: "text-xl font-bold", "Your data" }
+                        p { class: "text-gray-500",
+                            "Download everything we store about you as a ZIP archive."
+                        }
+                        button { class: "btn-sec mt-4", onclick: handle_export, "Export my data" }
+                        ul { class: "mt-4",
+                            for export in exports().iter() {
+                                li { key: "{export.id}", class: "flex justify-between py-1",
+                                    span { "{export.created_at.date()}" }
+                                    match export.status {
+                                        DataExportStatus::Pending | DataExportStatus::Running => rsx! {
+                                            span { "Preparing..." }
+                                        },
+                                        DataExportStatus::Ready => rsx! {
+                                            a {
+                                                class: "underline",
+                                                href: endpoint_url::<DownloadDataExport>(crate::enviroment::base_url(), &export.id),
+                                                "Download"
+                                            }
+                                        },
+                                        DataExportStatus::Failed => rsx! {
+                                            span { class: "text-red-500", "Failed" }
+                                        },
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Delete account
+                    section {
+                        h2 { class: "text-xl font-bold text-red-500", "Delete account" }
+                        form {
+                            class: "flex flex-col gap-4 mt-4",
+                            onsubmit: handle_delete,
+                            Input {
+                                name: "delete_password",
+                                appearance: InputAppearance::square,
+                                r#type: "password",
+                                label: "Password",
+                            }
+                            button { class: "btn", r#type: "submit", "Delete my account" }
+                        }
+                    }
+                } else {
+                    div { class: "text-center py-8",
+                        div { class: "loading loading-spinner loading-lg" }
+                    }
+                }
+
+                {Msg(err_msg())}
+                {Msg(confirm_msg())}
+                {Toast(success_msg())}
+                Spinner { state: spinner_state }
+            }
+        }
+    }
+}
@@ -18,9 +18,23 @@ pub enum Route {
             Languages {},
             #[route("/fields")]
             Field {},
+            #[route("/users")]
+            Users {},
+            #[route("/policies")]
+            Policies {},
+            #[route("/roles")]
+            Roles {},
+            #[route("/nav-items")]
+            NavItems {},
+            #[route("/audit-log")]
+            AuditLogViewer {},
+            #[route("/errors")]
+            ErrorGroups {},
          #[end_layout]
     #[end_nest]
     #[route("/login")]
     LoginPage {},
+    #[route("/profile")]
+    ProfilePage {},
 
 }
@@ -1,21 +1,48 @@
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
 use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 
+/// Key of the JSON object holding a decimal, e.g. `{ "$decimal": "12.50" }`
+pub const DECIMAL_KEY: &str = "$decimal";
+
+/// # A value of a user, resource, action or context attribute
+/// Converts to and from JSON (e.g. the `users.attributes` column) without losing anything:
+///
+/// | JSON | AttributeValue |
+/// |------|----------------|
+/// | `null` | `Null` |
+/// | `true` | `Bool` |
+/// | `10` | `Integer` |
+/// | `{ "$decimal": "12.50" }` | `Decimal` |
+/// | `"2025-08-18T10:00:00Z"` | `DateTime` |
+/// | `"text"` | `String` |
+/// | `[...]` | `List` |
+/// | `{...}` | `Map` |
+///
+/// Decimals are written as strings so no precision goes through `f64`.
+/// JSON numbers that aren't integers are read as decimals,
+/// and strings are timestamps when formatting the timestamp gives the same string back.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AttributeValue {
+    Null,
     String(String),
     Integer(i64),
     Decimal(Decimal),
     Bool(bool),
     List(Vec<AttributeValue>),
+    Map(BTreeMap<String, AttributeValue>),
     DateTime(OffsetDateTime),
 }
 
 impl AttributeValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, AttributeValue::Null)
+    }
+
     pub fn as_string(&self) -> Option<&String> {
         match self {
             AttributeValue::String(s) => Some(s),
@@ -57,34 +84,57 @@ impl AttributeValue {
             _ => None,
         }
     }
+
+    pub fn as_map(&self) -> Option<&BTreeMap<String, AttributeValue>> {
+        match self {
+            AttributeValue::Map(m) => Some(m),
+            _ => None,
+        }
+    }
+}
+
+/// The timestamp of a string, only when it is written the way `DateTime` is written back
+fn parse_date_time(s: &str) -> Option<OffsetDateTime> {
+    let dt = OffsetDateTime::parse(s, &Rfc3339).ok()?;
+    (dt.format(&Rfc3339).ok()? == s).then_some(dt)
+}
+
+/// `{ "$decimal": "12.50" }`
+fn parse_decimal(obj: &serde_json::Map<String, Value>) -> Option<Decimal> {
+    match (obj.len(), obj.get(DECIMAL_KEY)) {
+        (1, Some(Value::String(s))) => Decimal::from_str(s).ok(),
+        _ => None,
+    }
 }
 
 impl From<Value> for AttributeValue {
     fn from(value: Value) -> Self {
         match value {
-            Value::String(s) => {
-                if let Ok(dt) =
-                    OffsetDateTime::parse(&s, &time::format_description::well_known::Rfc3339)
-                {
-                    AttributeValue::DateTime(dt)
-                } else {
-                    AttributeValue::String(s)
-                }
-            }
-            Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    AttributeValue::Integer(i)
-                } else if let Some(d) = n.as_f64() {
-                    AttributeValue::Decimal(Decimal::from_f64_retain(d).unwrap_or(Decimal::ZERO))
-                } else {
-                    AttributeValue::String(n.to_string())
-                }
-            }
+            Value::Null => AttributeValue::Null,
             Value::Bool(b) => AttributeValue::Bool(b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => AttributeValue::Integer(i),
+                // The text of the number, not its f64 value
+                None => Decimal::from_str(&n.to_string())
+                    .or_else(|_| Decimal::from_scientific(&n.to_string()))
+                    .map(AttributeValue::Decimal)
+                    .unwrap_or_else(|_| AttributeValue::String(n.to_string())),
+            },
+            Value::String(s) => match parse_date_time(&s) {
+                Some(dt) => AttributeValue::DateTime(dt),
+                None => AttributeValue::String(s),
+            },
             Value::Array(arr) => {
                 AttributeValue::List(arr.into_iter().map(AttributeValue::from).collect())
             }
-            _ => AttributeValue::String(value.to_string()),
+            Value::Object(obj) => match parse_decimal(&obj) {
+                Some(d) => AttributeValue::Decimal(d),
+                None => AttributeValue::Map(
+                    obj.into_iter()
+                        .map(|(k, v)| (k, AttributeValue::from(v)))
+                        .collect(),
+                ),
+            },
         }
     }
 }
@@ -92,115 +142,77 @@ impl From<Value> for AttributeValue {
 impl From<AttributeValue> for Value {
     fn from(val: AttributeValue) -> Self {
         match val {
+            AttributeValue::Null => Value::Null,
             AttributeValue::String(s) => Value::String(s),
             AttributeValue::Integer(n) => Value::Number(n.into()),
             AttributeValue::Decimal(d) => {
-                // Convert Decimal to f64, then to serde_json::Number
-                serde_json::Number::from_f64(d.to_f64().unwrap_or(0.0))
-                    .map(Value::Number)
-                    .unwrap_or(Value::Null)
+                let mut obj = serde_json::Map::new();
+                obj.insert(DECIMAL_KEY.to_string(), Value::String(d.to_string()));
+                Value::Object(obj)
             }
             AttributeValue::Bool(b) => Value::Bool(b),
             AttributeValue::List(l) => Value::Array(l.into_iter().map(Value::from).collect()),
-            AttributeValue::DateTime(dt) => {
-                // Convert DateTime to RFC3339 string
-                Value::String(
-                    dt.format(&time::format_description::well_known::Rfc3339)
-                        .unwrap_or_default(),
-                )
+            AttributeValue::Map(m) => {
+                Value::Object(m.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
             }
+            AttributeValue::DateTime(dt) => Value::String(dt.format(&Rfc3339).unwrap_or_default()),
         }
     }
 }
 
+/// # The attributes of a user, e.g. the `users.attributes` column
+/// Every attribute map survives the trip to JSON and back:
+///
+/// ```
+/// use serde_json::{json, Value};
+/// use shared::{AttributeMap, AttributeValue};
+///
+/// let json = json!({
+///     "department": "sales",
+///     "level": 3,
+///     "budget": { "$decimal": "1250.10" },
+///     "hired_at": "2024-03-01T09:00:00Z",
+///     "manager": null,
+///     "tags": ["eu", "b2b"],
+///     "address": { "city": "Berlin", "zip": "10115" }
+/// });
+/// let map = AttributeMap::from(json.clone());
+/// assert_eq!(map.0["budget"], AttributeValue::Decimal("1250.10".parse().unwrap()));
+/// assert!(matches!(map.0["hired_at"], AttributeValue::DateTime(_)));
+/// assert!(map.0["manager"].is_null());
+/// assert_eq!(Value::from(map.clone()), json);
+/// assert_eq!(AttributeMap::from(Value::from(map.clone())), map);
+///
+/// // Numbers that aren't integers are read as decimals, not floats
+/// let map = AttributeMap::from(json!({ "rate": 0.1 }));
+/// assert_eq!(map.0["rate"], AttributeValue::Decimal("0.1".parse().unwrap()));
+/// assert_eq!(Value::from(map), json!({ "rate": { "$decimal": "0.1" } }));
+/// ```
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct AttributeMap(pub HashMap<String, AttributeValue>);
 
+/// Anything but an object gives an empty map
 impl From<Value> for AttributeMap {
     fn from(value: Value) -> Self {
-        let mut map = HashMap::new();
-
-        if let Value::Object(obj) = value {
-            for (k, v) in obj {
-                match v {
-                    Value::String(s) => {
-                        // Check if the string can be parsed as a DateTime
-                        if let Ok(dt) = OffsetDateTime::parse(
-                            &s,
-                            &time::format_description::well_known::Rfc3339,
-                        ) {
-                            map.insert(k.clone(), AttributeValue::DateTime(dt));
-                        } else {
-                            map.insert(k.clone(), AttributeValue::String(s.clone()));
-                        }
-                        map.insert(k, AttributeValue::String(s));
-                    }
-                    Value::Number(n) => {
-                        if let Some(num) = n.as_i64() {
-                            map.insert(k, AttributeValue::Integer(num));
-                        } else if let Some(num) = n.as_f64() {
-                            map.insert(
-                                k,
-                                AttributeValue::Decimal(
-                                    Decimal::from_f64_retain(num).unwrap_or(Decimal::ZERO),
-                                ),
-                            );
-                        } else {
-                            map.insert(k, AttributeValue::String(n.to_string()));
-                        }
-                    }
-                    Value::Bool(b) => {
-                        map.insert(k, AttributeValue::Bool(b));
-                    }
-                    Value::Array(arr) => {
-                        let list = arr.into_iter().map(AttributeValue::from).collect();
-                        map.insert(k, AttributeValue::List(list));
-                    }
-                    _ => {} // Skip other JSON types
-                }
-            }
+        match value {
+            Value::Object(obj) => AttributeMap(
+                obj.into_iter()
+                    .map(|(k, v)| (k, AttributeValue::from(v)))
+                    .collect(),
+            ),
+            _ => AttributeMap::default(),
         }
-
-        AttributeMap(map)
     }
 }
 
 impl From<AttributeMap> for Value {
     fn from(map: AttributeMap) -> Value {
-        let mut json_map = serde_json::Map::new();
-
-        for (k, v) in map.0 {
-            match v {
-                AttributeValue::String(s) => {
-                    json_map.insert(k, serde_json::Value::String(s));
-                }
-                AttributeValue::Integer(n) => {
-                    json_map.insert(k, serde_json::Value::Number(n.into()));
-                }
-                AttributeValue::Decimal(d) => {
-                    let n = serde_json::Number::from_f64(d.to_f64().unwrap_or(0.0))
-                        .map(Value::Number)
-                        .unwrap_or(Value::Null);
-                    json_map.insert(k, n);
-                }
-                AttributeValue::Bool(b) => {
-                    json_map.insert(k, serde_json::Value::Bool(b));
-                }
-                AttributeValue::List(l) => {
-                    let arr: Vec<Value> = l.into_iter().map(Value::from).collect();
-                    json_map.insert(k, serde_json::Value::Array(arr));
-                }
-                AttributeValue::DateTime(dt) => {
-                    // Convert DateTime to RFC3339 string
-                    let dt_str = dt
-                        .format(&time::format_description::well_known::Rfc3339)
-                        .unwrap_or_default();
-                    json_map.insert(k, serde_json::Value::String(dt_str));
-                }
-            }
-        }
-
-        serde_json::Value::Object(json_map)
+        Value::Object(
+            map.0
+                .into_iter()
+                .map(|(k, v)| (k, Value::from(v)))
+                .collect(),
+        )
     }
 }
 
@@ -220,14 +232,52 @@ pub struct Resource {
     pub attributes: HashMap<String, AttributeValue>,
 }
 
+impl Resource {
+    pub fn new(resource_type: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            resource_type: resource_type.into(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    pub fn with_attribute(mut self, key: impl Into<String>, value: AttributeValue) -> Self {
+        self.attributes.insert(key.into(), value);
+        self
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Action {
     pub name: String,
     pub attributes: HashMap<String, AttributeValue>,
 }
 
+impl Action {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            attributes: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Context {
     pub ip_address: String,
     pub attributes: HashMap<String, AttributeValue>,
 }
+
+impl Context {
+    pub fn new(ip_address: impl Into<String>) -> Self {
+        Self {
+            ip_address: ip_address.into(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    pub fn with_attribute(mut self, key: impl Into<String>, value: AttributeValue) -> Self {
+        self.attributes.insert(key.into(), value);
+        self
+    }
+}
@@ -0,0 +1,177 @@
+//! # The endpoints of the backend
+//! Each one is a type naming its method, path, request and response, so the backend router
+//! and the frontend client are built from the same definition.
+//! `GET` and `DELETE` send the request in the query string, `POST` and `PUT` as the JSON body.
+
+use serde::{Serialize, de::DeserializeOwned};
+use uuid::Uuid;
+
+use crate::user::percent_encode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+        }
+    }
+
+    /// Whether the request goes in the body rather than the query string
+    pub fn has_body(&self) -> bool {
+        matches!(self, Self::Post | Self::Put)
+    }
+}
+
+pub trait Endpoint {
+    const METHOD: Method;
+    /// The axum path, its `{..}` segments are the `Params` in order
+    const PATH: &'static str;
+    type Params: PathParams;
+    /// `()` when there is nothing to send
+    type Request: Serialize + DeserializeOwned;
+    /// `String` for the endpoints answering with a message
+    type Response: Serialize + DeserializeOwned;
+
+    /// The path with the `{..}` segments filled in. The values are percent-encoded,
+    /// so one can't add segments or a query string to the path.
+    ///
+    /// ```
+    /// use shared::{GetCategory, api::Endpoint, error_log::ErrorOccurrences};
+    ///
+    /// assert_eq!(ErrorOccurrences::path(&7), "/errors/7/occurrences");
+    /// assert_eq!(GetCategory::path(&"a/b c".to_string()), "/categories/a%2Fb%20c");
+    /// ```
+    fn path(params: &Self::Params) -> String {
+        let mut values = params.values().into_iter();
+        let mut path = String::with_capacity(Self::PATH.len());
+        let mut rest = Self::PATH;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            path.push_str(&rest[..start]);
+            path.push_str(&percent_encode(&values.next().unwrap_or_default()));
+            rest = &rest[start + end + 1..];
+        }
+        path.push_str(rest);
+        path
+    }
+}
+
+/// The values of the `{..}` segments of a path
+pub trait PathParams {
+    fn values(&self) -> Vec<String>;
+}
+
+impl PathParams for () {
+    fn values(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+macro_rules! path_param {
+    ($($ty:ty),*) => {
+        $(impl PathParams for $ty {
+            fn values(&self) -> Vec<String> {
+                vec![self.to_string()]
+            }
+        })*
+    };
+}
+
+path_param!(i32, i64, u32, u64, String, Uuid);
+
+impl<A: PathParams, B: PathParams> PathParams for (A, B) {
+    fn values(&self) -> Vec<String> {
+        let mut values = self.0.values();
+        values.extend(self.1.values());
+        values
+    }
+}
+
+/// # Declares endpoints
+/// `Name: Method "path" (params), request => response;`, the params and the request are
+/// optional and default to `()`.
+///
+/// ```
+/// use shared::{api::{Endpoint, Method}, endpoint, Language};
+///
+/// endpoint! {
+///     /// Every language
+///     pub ListLanguages: Get "/languages" => Vec<Language>;
+///     pub DeleteLanguage: Delete "/languages/{id}" (i32) => String;
+/// }
+///
+/// assert_eq!(DeleteLanguage::METHOD, Method::Delete);
+/// assert_eq!(DeleteLanguage::path(&3), "/languages/3");
+/// ```
+#[macro_export]
+macro_rules! endpoint {
+    ($(
+        $(#[$meta:meta])*
+        $vis:vis $name:ident: $method:ident $path:literal $(($params:ty))? $(, $request:ty)?
+            => $response:ty;
+    )*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Clone, Copy)]
+            $vis struct $name;
+
+            impl $crate::api::Endpoint for $name {
+                const METHOD: $crate::api::Method = $crate::api::Method::$method;
+                const PATH: &'static str = $path;
+                type Params = $crate::endpoint!(@or_unit $($params)?);
+                type Request = $crate::endpoint!(@or_unit $($request)?);
+                type Response = $response;
+            }
+        )*
+    };
+    (@or_unit) => { () };
+    (@or_unit $ty:ty) => { $ty };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::endpoint! {
+        Single: Get "/items/{id}" (String) => String;
+        Pair: Put "/items/{id}/names/{lang}" ((String, String)) => String;
+        Number: Delete "/items/{id}" (i32) => String;
+    }
+
+    #[test]
+    fn plain_values_are_kept() {
+        assert_eq!(Single::path(&"abc-1.2_x~".to_string()), "/items/abc-1.2_x~");
+        assert_eq!(Number::path(&-3), "/items/-3");
+    }
+
+    #[test]
+    fn reserved_characters_are_encoded() {
+        assert_eq!(Single::path(&"a/b".to_string()), "/items/a%2Fb");
+        assert_eq!(Single::path(&"../admin".to_string()), "/items/..%2Fadmin");
+        assert_eq!(Single::path(&"a?b=1#c".to_string()), "/items/a%3Fb%3D1%23c");
+        assert_eq!(Single::path(&"50% off".to_string()), "/items/50%25%20off");
+    }
+
+    #[test]
+    fn non_ascii_is_encoded_as_utf8() {
+        assert_eq!(Single::path(&"café".to_string()), "/items/caf%C3%A9");
+    }
+
+    #[test]
+    fn every_segment_is_encoded() {
+        let params = ("a b".to_string(), "en/US".to_string());
+        assert_eq!(Pair::path(&params), "/items/a%20b/names/en%2FUS");
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{AttributeMap, AttributeValue};
+
+/// The kind of value an attribute holds, see `AttributeValue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeKind {
+    String,
+    Integer,
+    /// Integers are decimals too
+    Decimal,
+    Bool,
+    DateTime,
+    List,
+    Map,
+}
+
+impl AttributeKind {
+    pub fn matches(&self, value: &AttributeValue) -> bool {
+        matches!(
+            (self, value),
+            (AttributeKind::String, AttributeValue::String(_))
+                | (AttributeKind::Integer, AttributeValue::Integer(_))
+                | (
+                    AttributeKind::Decimal,
+                    AttributeValue::Decimal(_) | AttributeValue::Integer(_)
+                )
+                | (AttributeKind::Bool, AttributeValue::Bool(_))
+                | (AttributeKind::DateTime, AttributeValue::DateTime(_))
+                | (AttributeKind::List, AttributeValue::List(_))
+                | (AttributeKind::Map, AttributeValue::Map(_))
+        )
+    }
+}
+
+impl fmt::Display for AttributeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AttributeKind::String => "string",
+            AttributeKind::Integer => "integer",
+            AttributeKind::Decimal => "decimal",
+            AttributeKind::Bool => "bool",
+            AttributeKind::DateTime => "date_time",
+            AttributeKind::List => "list",
+            AttributeKind::Map => "map",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// # An attribute of a resource type
+/// A row of the `attribute_schemas` table. Once a resource type has schemas,
+/// its attributes must be declared, e.g. every key of `users.attributes` for the `user` type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeSchema {
+    pub id: i32,
+    pub resource_type: String,
+    pub name: String,
+    pub kind: AttributeKind,
+    pub required: bool,
+    /// Set when the attribute is left out, JSON as stored in `users.attributes`
+    pub default_value: Option<Value>,
+    /// Empty allows every value of the kind
+    pub allowed_values: Vec<Value>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeSchemaPayload {
+    pub resource_type: String,
+    pub name: String,
+    pub kind: AttributeKind,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub default_value: Option<Value>,
+    #[serde(default)]
+    pub allowed_values: Vec<Value>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl AttributeSchemaPayload {
+    /// The default and the allowed values must be of the kind, and the default one of the allowed values
+    pub fn validate(&self) -> Result<(), String> {
+        if self.resource_type.trim().is_empty() || self.name.trim().is_empty() {
+            return Err("resource_type and name can't be empty".to_string());
+        }
+        let allowed: Vec<AttributeValue> = self
+            .allowed_values
+            .iter()
+            .cloned()
+            .map(AttributeValue::from)
+            .collect();
+        if let Some((value, _)) = self
+            .allowed_values
+            .iter()
+            .zip(&allowed)
+            .find(|(_, allowed)| !self.kind.matches(allowed))
+        {
+            return Err(format!("{} is not a {}", value, self.kind));
+        }
+        if let Some(value) = &self.default_value {
+            let default = AttributeValue::from(value.clone());
+            if !self.kind.matches(&default) {
+                return Err(format!("the default {} is not a {}", value, self.kind));
+            }
+            if !allowed.is_empty() && !allowed.contains(&default) {
+                return Err("the default must be one of the allowed values".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AttributeSchema {
+    fn check(&self, value: &AttributeValue) -> Result<(), String> {
+        if value.is_null() {
+            if self.required {
+                return Err(format!("{} is required", self.name));
+            }
+            return Ok(());
+        }
+        if !self.kind.matches(value) {
+            return Err(format!("{} must be a {}", self.name, self.kind));
+        }
+        if !self.allowed_values.is_empty()
+            && !self
+                .allowed_values
+                .iter()
+                .any(|allowed| AttributeValue::from(allowed.clone()) == *value)
+        {
+            return Err(format!("{} is not one of the allowed values", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// # Check attributes against the schemas of their resource type
+/// Fills in the defaults of the missing attributes and returns every problem found.
+/// Resource types without schemas accept anything.
+pub fn validate_attributes(
+    schemas: &[AttributeSchema],
+    attributes: &mut AttributeMap,
+) -> Result<(), Vec<String>> {
+    if schemas.is_empty() {
+        return Ok(());
+    }
+
+    let mut errors: Vec<String> = attributes
+        .0
+        .keys()
+        .filter(|name| !schemas.iter().any(|schema| &schema.name == *name))
+        .map(|name| format!("{} is not a known attribute", name))
+        .collect();
+
+    for schema in schemas {
+        if !attributes.0.contains_key(&schema.name)
+            && let Some(default) = &schema.default_value
+        {
+            attributes
+                .0
+                .insert(schema.name.clone(), AttributeValue::from(default.clone()));
+        }
+        let value = attributes
+            .0
+            .get(&schema.name)
+            .cloned()
+            .unwrap_or(AttributeValue::Null);
+        if let Err(err) = schema.check(&value) {
+            errors.push(err);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Query of `GET /attribute-schemas`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttributeSchemaFilter {
+    /// The schemas of every resource type when left out
+    pub resource_type: Option<String>,
+}
+
+crate::endpoint! {
+    pub ListAttributeSchemas: Get "/attribute-schemas", AttributeSchemaFilter
+        => Vec<AttributeSchema>;
+    /// Answers `{ "id": .. }` of the new schema
+    pub AddAttributeSchema: Post "/attribute-schemas", AttributeSchemaPayload => Value;
+    pub UpdateAttributeSchema: Put "/attribute-schemas/{id}" (i32), AttributeSchemaPayload
+        => String;
+    pub DeleteAttributeSchema: Delete "/attribute-schemas/{id}" (i32) => String;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema(name: &str, kind: AttributeKind) -> AttributeSchema {
+        AttributeSchema {
+            id: 1,
+            resource_type: "user".to_string(),
+            name: name.to_string(),
+            kind,
+            required: false,
+            default_value: None,
+            allowed_values: Vec::new(),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn no_schemas_accept_anything() {
+        let mut attributes = AttributeMap::from(json!({ "anything": [1, 2] }));
+        assert_eq!(validate_attributes(&[], &mut attributes), Ok(()));
+    }
+
+    #[test]
+    fn unknown_and_mistyped_attributes_are_reported() {
+        let schemas = [schema("level", AttributeKind::Integer)];
+        let mut attributes = AttributeMap::from(json!({ "level": "high", "team": "a" }));
+        let errors = validate_attributes(&schemas, &mut attributes).unwrap_err();
+        assert!(errors.contains(&"team is not a known attribute".to_string()));
+        assert!(errors.contains(&"level must be a integer".to_string()));
+    }
+
+    #[test]
+    fn missing_attributes_get_their_default() {
+        let schemas = [AttributeSchema {
+            required: true,
+            default_value: Some(json!("eu")),
+            ..schema("region", AttributeKind::String)
+        }];
+        let mut attributes = AttributeMap::default();
+        assert_eq!(validate_attributes(&schemas, &mut attributes), Ok(()));
+        assert_eq!(
+            attributes.0.get("region"),
+            Some(&AttributeValue::String("eu".to_string()))
+        );
+    }
+
+    #[test]
+    fn required_attributes_without_default_are_reported() {
+        let schemas = [AttributeSchema {
+            required: true,
+            ..schema("region", AttributeKind::String)
+        }];
+        let mut attributes = AttributeMap::default();
+        assert_eq!(
+            validate_attributes(&schemas, &mut attributes),
+            Err(vec!["region is required".to_string()])
+        );
+    }
+
+    #[test]
+    fn values_must_be_allowed() {
+        let schemas = [AttributeSchema {
+            allowed_values: vec![json!("eu"), json!("us")],
+            ..schema("region", AttributeKind::String)
+        }];
+        let mut allowed = AttributeMap::from(json!({ "region": "us" }));
+        assert_eq!(validate_attributes(&schemas, &mut allowed), Ok(()));
+        let mut other = AttributeMap::from(json!({ "region": "asia" }));
+        assert_eq!(
+            validate_attributes(&schemas, &mut other),
+            Err(vec!["region is not one of the allowed values".to_string()])
+        );
+    }
+
+    #[test]
+    fn integers_are_decimals() {
+        let schemas = [schema("score", AttributeKind::Decimal)];
+        let mut attributes = AttributeMap::from(json!({ "score": 3 }));
+        assert_eq!(validate_attributes(&schemas, &mut attributes), Ok(()));
+    }
+
+    #[test]
+    fn payload_default_must_be_allowed() {
+        let payload = AttributeSchemaPayload {
+            resource_type: "user".to_string(),
+            name: "region".to_string(),
+            kind: AttributeKind::String,
+            required: false,
+            default_value: Some(json!("asia")),
+            allowed_values: vec![json!("eu")],
+            description: None,
+        };
+        assert!(payload.validate().is_err());
+        let payload = AttributeSchemaPayload {
+            default_value: Some(json!("eu")),
+            ..payload
+        };
+        assert_eq!(payload.validate(), Ok(()));
+        let payload = AttributeSchemaPayload {
+            allowed_values: vec![json!(1)],
+            default_value: None,
+            ..payload
+        };
+        assert_eq!(payload.validate(), Err("1 is not a string".to_string()));
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    list::{FilterOp, ListQuery, Page, SortDirection},
+    user::percent_encode,
+};
+
+/// # A row of the `audit_log` table
+/// Written in the transaction of the mutation it records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor_id: Option<Uuid>,
+    /// Kept when the actor is deleted
+    pub actor_email: Option<String>,
+    /// e.g. `create`, `update`, `delete`, `change_role`
+    pub action: String,
+    pub resource_type: String,
+    pub resource_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    /// The changed keys, see `json_diff`
+    pub diff: Value,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+/// # The filter of the audit log viewer
+/// The query of `GET /audit-log/export`, `to_list_query` gives the `GET /audit-log` one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditFilter {
+    /// Part of the email of the actor
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    /// `YYYY-MM-DD`, included
+    pub from: Option<String>,
+    /// `YYYY-MM-DD`, included
+    pub to: Option<String>,
+}
+
+impl AuditFilter {
+    /// Builds the query string (without the leading `?`), empty values are left out
+    pub fn to_query_string(&self) -> String {
+        let mut params = Vec::new();
+        let fields = [
+            ("actor", &self.actor),
+            ("action", &self.action),
+            ("resource_type", &self.resource_type),
+            ("resource_id", &self.resource_id),
+            ("from", &self.from),
+            ("to", &self.to),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value.as_ref().filter(|v| !v.trim().is_empty()) {
+                params.push(format!("{}={}", name, percent_encode(value.trim())));
+            }
+        }
+        params.join("&")
+    }
+
+    /// The first page of the matching entries, newest first
+    ///
+    /// ```
+    /// use shared::audit::AuditFilter;
+    ///
+    /// let filter = AuditFilter {
+    ///     actor: Some("admin@".to_string()),
+    ///     from: Some("2025-01-01".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     filter.to_list_query().to_query_string(),
+    ///     "per_page=50&sort=-created_at,-id&actor_email.contains=admin%40&created_on.gte=2025-01-01"
+    /// );
+    /// ```
+    pub fn to_list_query(&self) -> ListQuery {
+        let fields = [
+            ("actor_email", FilterOp::Contains, &self.actor),
+            ("action", FilterOp::Eq, &self.action),
+            ("resource_type", FilterOp::Eq, &self.resource_type),
+            ("resource_id", FilterOp::Eq, &self.resource_id),
+            ("created_on", FilterOp::Gte, &self.from),
+            ("created_on", FilterOp::Lte, &self.to),
+        ];
+        let query = ListQuery::default()
+            .sort_by("created_at", SortDirection::Desc)
+            .sort_by("id", SortDirection::Desc);
+        fields
+            .into_iter()
+            .filter_map(|(field, op, value)| {
+                let value = value.as_ref()?.trim();
+                (!value.is_empty()).then_some((field, op, value))
+            })
+            .fold(query, |query, (field, op, value)| {
+                query.filter(field, op, value)
+            })
+    }
+}
+
+/// # The keys that changed between two versions of a resource
+/// `{"name": {"before": "Old", "after": "New"}}`, a missing version counts as an empty object.
+///
+/// ```
+/// use serde_json::json;
+/// use shared::audit::json_diff;
+///
+/// let before = json!({ "name": "Old", "active": true });
+/// let after = json!({ "name": "New", "active": true });
+/// assert_eq!(
+///     json_diff(Some(&before), Some(&after)),
+///     json!({ "name": { "before": "Old", "after": "New" } })
+/// );
+/// ```
+pub fn json_diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut diff = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let (old, new) = (before.get(key), after.get(key));
+        if old != new && !diff.contains_key(key) {
+            diff.insert(key.clone(), json!({ "before": old, "after": new }));
+        }
+    }
+    Value::Object(diff)
+}
+
+crate::endpoint! {
+    /// A page of the entries, filtered on `actor_email`, `action`, `resource_type`,
+    /// `resource_id` and `created_on` (the date of `created_at`)
+    pub ListAuditLog: Get "/audit-log", ListQuery => Page<AuditEntry>;
+    /// Every matching entry as a file, `format=csv` (default) or `json` after the filter
+    pub ExportAuditLog: Get "/audit-log/export", AuditFilter => String;
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::list::{ListQuery, Page};
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-// #[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
 pub struct Category {
     pub id: String,
     pub parent_id: Option<String>,
@@ -15,6 +17,7 @@ impl Category {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
 pub struct CategoryName {
     pub id: Option<i32>,
     pub name: String,
@@ -43,10 +46,39 @@ pub struct CategoryWithNames {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "backend",
+    derive(sqlx::FromRow, sam_proc_macros::Repository),
+    table = "languages"
+)]
 pub struct Language {
+    #[cfg_attr(feature = "backend", repository(generated))]
     pub id: i32,
     pub code: String,
     pub name: String,
     pub flag: String,
     pub active: bool,
 }
+
+crate::endpoint! {
+    /// A page of the categories, the ones the user may view
+    pub ListCategories: Get "/categories", ListQuery => Page<Category>;
+    /// A page of the category names
+    pub ListCategoryNames: Get "/categories/names", ListQuery => Page<CategoryName>;
+    pub GetCategory: Get "/categories/{id}" (String) => Category;
+    /// A category with a name per language
+    pub AddCategory: Post "/categories", CategoryWithNames => String;
+    /// Set the parent and replace the names
+    pub UpdateCategory: Put "/categories/{id}" (String), CategoryWithNames => String;
+    pub DeleteCategory: Delete "/categories/{id}" (String) => String;
+}
+
+crate::endpoint! {
+    /// A page of the languages
+    pub ListLanguages: Get "/languages", ListQuery => Page<Language>;
+    /// A language by its code, the segment is named like the one of `DeleteLanguage`
+    pub GetLanguage: Get "/languages/{id}" (String) => Language;
+    pub AddLanguage: Post "/languages", Language => String;
+    pub UpdateLanguage: Put "/languages", Language => String;
+    pub DeleteLanguage: Delete "/languages/{id}" (i32) => String;
+}
@@ -1,39 +1,86 @@
-use crate::user::UserRole;
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::role::parse_permission;
+
+/// # An entry of the dashboard menu
+/// A row of the `dash_nav_items` table, shown to the users passing both of its checks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DashNavItem {
+    pub id: i32,
+    /// Items with a parent are nested under it and hidden with it
+    pub parent_id: Option<i32>,
+    /// The label when there is none in the language of the user
     pub name: String,
     pub icon: String,
     pub route: String,
-    pub required_roles: Vec<UserRole>,
+    /// `resource_type:action` one of the roles of the user must grant, e.g. `category:write`
+    pub required_permission: Option<String>,
+    /// `resource_type:action` the policies must allow, e.g. `listing:view`
+    pub required_action: Option<String>,
+    pub position: i32,
+    pub names: Vec<DashNavItemName>,
 }
 
-#[derive(Debug, Clone, PartialEq, Props, Serialize, Deserialize)]
-pub struct DashNavItemInfo {
+/// The label of a nav item in a language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashNavItemName {
+    pub language_id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashNavItemPayload {
+    pub parent_id: Option<i32>,
     pub name: String,
     pub icon: String,
     pub route: String,
+    #[serde(default)]
+    pub required_permission: Option<String>,
+    #[serde(default)]
+    pub required_action: Option<String>,
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default)]
+    pub names: Vec<DashNavItemName>,
 }
 
-impl From<DashNavItem> for DashNavItemInfo {
-    fn from(item: DashNavItem) -> Self {
-        Self {
-            name: item.name,
-            icon: item.icon,
-            route: item.route,
+impl DashNavItemPayload {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.route.trim().is_empty() {
+            return Err("name and route can't be empty".to_string());
+        }
+        for value in [&self.required_permission, &self.required_action]
+            .into_iter()
+            .flatten()
+        {
+            if parse_permission(value).is_none() {
+                return Err(format!("{} is not resource_type:action", value));
+            }
         }
+        Ok(())
     }
 }
 
-impl DashNavItem {
-    pub fn new(name: String, icon: String, route: String, required_roles: Vec<UserRole>) -> Self {
-        Self {
-            name,
-            icon,
-            route,
-            required_roles,
-        }
-    }
+/// # A nav item as the user sees it
+/// The label is in the language of the user and `sub_items` are the visible children.
+#[derive(Debug, Clone, PartialEq, Props, Serialize, Deserialize)]
+pub struct DashNavItemInfo {
+    pub name: String,
+    pub icon: String,
+    pub route: String,
+    #[serde(default)]
+    pub sub_items: Vec<DashNavItemInfo>,
+}
+
+crate::endpoint! {
+    /// Every nav item with its labels, for the menu editor
+    pub ListNavItems: Get "/nav-items" => Vec<DashNavItem>;
+    /// Answers `{ "id": .. }` of the new item
+    pub AddNavItem: Post "/nav-items", DashNavItemPayload => serde_json::Value;
+    pub UpdateNavItem: Put "/nav-items/{id}" (i32), DashNavItemPayload => String;
+    /// The children are deleted too
+    pub DeleteNavItem: Delete "/nav-items/{id}" (i32) => String;
+    /// The menu of the logged in user, in their language
+    pub DashboardNavItems: Get "/users/dashboard/nav-items" => Vec<DashNavItemInfo>;
 }
@@ -0,0 +1,106 @@
+//! # The codes of `UserResponse::code`
+//! Stable, so clients can match on them and show their own messages
+//! (see `UserResponse::localized_message`). They mirror `SamError::code` in `sam_error`.
+
+macro_rules! error_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:literal;)*) => {
+        $(
+            $(#[$meta])*
+            pub const $name: &str = $code;
+        )*
+
+        /// Every code, e.g. for the OpenAPI document
+        pub const ALL: &[&str] = &[$($name),*];
+    };
+}
+
+error_codes! {
+    DATABASE_ERROR = "database_error";
+    INVALID_JSON = "invalid_json";
+    LOGIN_FAILED = "login_failed";
+    REGISTRATION_FAILED = "registration_failed";
+    NOT_AUTHORIZED = "not_authorized";
+    INVALID_TOKEN = "invalid_token";
+    EXPIRED_TOKEN = "expired_token";
+    MISSING_ENVIRONMENT_VARIABLE = "missing_environment_variable";
+    INVALID_CONFIG = "invalid_config";
+    EMAIL_SEND_FAILED = "email_send_failed";
+    EMAIL_NOT_FOUND = "email_not_found";
+    INVALID_EMAIL = "invalid_email";
+    INVALID_PASSWORD = "invalid_password";
+    FORBIDDEN = "forbidden";
+    POLICY_DENIED = "policy_denied";
+    NOT_FOUND = "not_found";
+    EMAIL_TAKEN = "email_taken";
+    INVALID_UPLOAD = "invalid_upload";
+    INVALID_POLICY = "invalid_policy";
+    INVALID_ATTRIBUTES = "invalid_attributes";
+    INVALID_ROLE = "invalid_role";
+    INVALID_NAV_ITEM = "invalid_nav_item";
+    /// The messages are in `UserResponse::field_errors`
+    VALIDATION_FAILED = "validation_failed";
+    ACCOUNT_DISABLED = "account_disabled";
+    PASSWORD_RESET_REQUIRED = "password_reset_required";
+    /// A panic or an error that is only logged, the details are in the error dashboard
+    INTERNAL_ERROR = "internal_error";
+    ERROR = "error";
+
+    // Errors that don't come from `SamError`, by their status code
+    BAD_REQUEST = "bad_request";
+    METHOD_NOT_ALLOWED = "method_not_allowed";
+    PAYLOAD_TOO_LARGE = "payload_too_large";
+    UNSUPPORTED_MEDIA_TYPE = "unsupported_media_type";
+    UNPROCESSABLE_ENTITY = "unprocessable_entity";
+    TOO_MANY_REQUESTS = "too_many_requests";
+}
+
+/// The code of an error response without one
+pub fn from_status(status_code: u16) -> &'static str {
+    match status_code {
+        400 => BAD_REQUEST,
+        401 => NOT_AUTHORIZED,
+        403 => FORBIDDEN,
+        404 => NOT_FOUND,
+        405 => METHOD_NOT_ALLOWED,
+        413 => PAYLOAD_TOO_LARGE,
+        415 => UNSUPPORTED_MEDIA_TYPE,
+        422 => UNPROCESSABLE_ENTITY,
+        429 => TOO_MANY_REQUESTS,
+        _ if (400..500).contains(&status_code) => BAD_REQUEST,
+        _ => INTERNAL_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_statuses_have_their_code() {
+        assert_eq!(from_status(400), BAD_REQUEST);
+        assert_eq!(from_status(401), NOT_AUTHORIZED);
+        assert_eq!(from_status(404), NOT_FOUND);
+        assert_eq!(from_status(429), TOO_MANY_REQUESTS);
+    }
+
+    #[test]
+    fn other_client_errors_are_bad_requests() {
+        assert_eq!(from_status(409), BAD_REQUEST);
+        assert_eq!(from_status(499), BAD_REQUEST);
+    }
+
+    #[test]
+    fn anything_else_is_internal() {
+        assert_eq!(from_status(500), INTERNAL_ERROR);
+        assert_eq!(from_status(503), INTERNAL_ERROR);
+        assert_eq!(from_status(200), INTERNAL_ERROR);
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        let mut codes = ALL.to_vec();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), ALL.len());
+    }
+}
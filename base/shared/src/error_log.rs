@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::list::{ListQuery, Page};
+
+/// Where an error group is in the admin workflow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "backend", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "backend",
+    sqlx(type_name = "error_status", rename_all = "snake_case")
+)]
+pub enum ErrorStatus {
+    Open,
+    /// Opened again when the error comes back
+    Resolved,
+    /// Keeps counting but stays out of the open list
+    Ignored,
+}
+
+impl ErrorStatus {
+    pub const ALL: [ErrorStatus; 3] = [Self::Open, Self::Resolved, Self::Ignored];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Resolved => "resolved",
+            Self::Ignored => "ignored",
+        }
+    }
+}
+
+/// # A row of the `errors` table
+/// Every occurrence of the same error (same place, same message up to the numbers in it)
+/// is counted in one group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+pub struct ErrorGroup {
+    pub id: i32,
+    pub fingerprint: String,
+    pub module: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<i32>,
+    /// The message of the last occurrence
+    pub message: Option<String>,
+    pub occurrences: i32,
+    pub status: ErrorStatus,
+    /// First seen
+    pub created_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
+    pub resolved_at: Option<OffsetDateTime>,
+    pub resolved_by: Option<Uuid>,
+}
+
+/// One time an error happened, with the request it happened in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorOccurrence {
+    pub id: i64,
+    pub request_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub method: Option<String>,
+    pub route: Option<String>,
+    pub message: String,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorStatusPayload {
+    pub status: ErrorStatus,
+}
+
+crate::endpoint! {
+    /// A page of the error groups, filtered on `status` and sorted by `last_seen_at`,
+    /// `occurrences` or `created_at`
+    pub ListErrors: Get "/errors", ListQuery => Page<ErrorGroup>;
+    /// The latest occurrences of a group
+    pub ErrorOccurrences: Get "/errors/{id}/occurrences" (i32) => Vec<ErrorOccurrence>;
+    /// Resolve, ignore or open a group again
+    pub SetErrorStatus: Put "/errors/{id}/status" (i32), ErrorStatusPayload => String;
+}
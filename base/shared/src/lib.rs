@@ -1,9 +1,17 @@
 mod abac;
 pub mod accounting;
+pub mod api;
+pub mod attribute_schema;
+pub mod audit;
 mod category;
 pub mod dashboard;
+pub mod error_code;
+pub mod error_log;
 mod field;
+pub mod list;
 mod misc;
+pub mod policy;
+pub mod role;
 pub mod user;
 
 pub use abac::*;
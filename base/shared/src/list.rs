@@ -0,0 +1,429 @@
+//! # Pagination, sorting and filtering of the list endpoints
+//! The query string of a list, e.g. `?per_page=20&sort=name,-id&active=true&name.contains=en`:
+//!
+//! - `page` and `per_page` page through the rows by offset, `cursor` continues after the
+//!   `next_cursor` of the previous page instead of `page`.
+//! - `sort` is a comma separated list of fields, `-` in front sorts descending.
+//! - Every other key filters on a field, `field=value` or `field.op=value` with `op` one of
+//!   `eq`, `ne`, `lt`, `lte`, `gt`, `gte` and `contains`.
+
+use std::{fmt::Write, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    /// Case insensitive substring
+    Contains,
+}
+
+impl FilterOp {
+    pub const ALL: [FilterOp; 7] = [
+        Self::Eq,
+        Self::Ne,
+        Self::Lt,
+        Self::Lte,
+        Self::Gt,
+        Self::Gte,
+        Self::Contains,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Eq => "eq",
+            Self::Ne => "ne",
+            Self::Lt => "lt",
+            Self::Lte => "lte",
+            Self::Gt => "gt",
+            Self::Gte => "gte",
+            Self::Contains => "contains",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldFilter {
+    pub field: String,
+    pub op: FilterOp,
+    pub value: String,
+}
+
+/// # The query of a list endpoint
+///
+/// ```
+/// use shared::list::{FilterOp, ListQuery, SortDirection};
+///
+/// let query: ListQuery = "page=2&sort=name,-id&name.contains=en%20gl".parse().unwrap();
+/// assert_eq!(query.page(), 2);
+/// assert_eq!(query.sort[1].direction, SortDirection::Desc);
+/// assert_eq!(query.filters[0].op, FilterOp::Contains);
+/// assert_eq!(query.filters[0].value, "en gl");
+///
+/// let query = ListQuery::default()
+///     .sort_by("code", SortDirection::Asc)
+///     .filter("active", FilterOp::Eq, "true");
+/// assert_eq!(query.to_query_string(), "per_page=50&sort=code&active=true");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// The `next_cursor` of the previous page, `page` is ignored with it
+    pub cursor: Option<String>,
+    pub sort: Vec<SortKey>,
+    pub filters: Vec<FieldFilter>,
+}
+
+impl ListQuery {
+    pub const DEFAULT_PER_PAGE: i64 = 50;
+    pub const MAX_PER_PAGE: i64 = 200;
+
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page
+            .unwrap_or(Self::DEFAULT_PER_PAGE)
+            .clamp(1, Self::MAX_PER_PAGE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1) * self.per_page()
+    }
+
+    pub fn with_page(mut self, page: i64) -> Self {
+        self.page = Some(page);
+        self.cursor = None;
+        self
+    }
+
+    pub fn with_per_page(mut self, per_page: i64) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Adds a sort key after the current ones
+    pub fn sort_by(mut self, field: impl Into<String>, direction: SortDirection) -> Self {
+        self.sort.push(SortKey {
+            field: field.into(),
+            direction,
+        });
+        self
+    }
+
+    pub fn filter(
+        mut self,
+        field: impl Into<String>,
+        op: FilterOp,
+        value: impl Into<String>,
+    ) -> Self {
+        self.filters.push(FieldFilter {
+            field: field.into(),
+            op,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Builds the query string (without the leading `?`)
+    pub fn to_query_string(&self) -> String {
+        let mut query = String::new();
+        match &self.cursor {
+            Some(cursor) => {
+                let _ = write!(query, "cursor={}", encode(cursor));
+            }
+            None if self.page.is_some() => {
+                let _ = write!(query, "page={}", self.page());
+            }
+            None => {}
+        }
+        push_separator(&mut query);
+        let _ = write!(query, "per_page={}", self.per_page());
+        if !self.sort.is_empty() {
+            let sort = self
+                .sort
+                .iter()
+                .map(|key| match key.direction {
+                    SortDirection::Asc => encode(&key.field),
+                    SortDirection::Desc => format!("-{}", encode(&key.field)),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            let _ = write!(query, "&sort={}", sort);
+        }
+        for filter in &self.filters {
+            let _ = match filter.op {
+                FilterOp::Eq => write!(
+                    query,
+                    "&{}={}",
+                    encode(&filter.field),
+                    encode(&filter.value)
+                ),
+                op => write!(
+                    query,
+                    "&{}.{}={}",
+                    encode(&filter.field),
+                    op.as_str(),
+                    encode(&filter.value)
+                ),
+            };
+        }
+        query
+    }
+}
+
+fn push_separator(query: &mut String) {
+    if !query.is_empty() {
+        query.push('&');
+    }
+}
+
+fn valid_field(field: &str) -> bool {
+    !field.is_empty() && field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl FromStr for ListQuery {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut list = ListQuery::default();
+        for pair in query.trim_start_matches('?').split('&') {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let (key, value) = (decode(key)?, decode(value)?);
+            match key.as_str() {
+                "page" => {
+                    list.page = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid page: {}", value))?,
+                    )
+                }
+                "per_page" => {
+                    list.per_page = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid per_page: {}", value))?,
+                    )
+                }
+                "cursor" => list.cursor = Some(value).filter(|cursor| !cursor.is_empty()),
+                "sort" => {
+                    for field in value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|field| !field.is_empty())
+                    {
+                        let (field, direction) = match field.strip_prefix('-') {
+                            Some(field) => (field, SortDirection::Desc),
+                            None => (field.trim_start_matches('+'), SortDirection::Asc),
+                        };
+                        if !valid_field(field) {
+                            return Err(format!("Invalid sort field: {}", field));
+                        }
+                        list = list.sort_by(field, direction);
+                    }
+                }
+                _ => {
+                    let (field, op) = match key.split_once('.') {
+                        Some((field, op)) => {
+                            let op = FilterOp::ALL
+                                .into_iter()
+                                .find(|known| known.as_str() == op)
+                                .ok_or_else(|| format!("Invalid filter operator: {}", op))?;
+                            (field, op)
+                        }
+                        None => (key.as_str(), FilterOp::Eq),
+                    };
+                    if !valid_field(field) {
+                        return Err(format!("Invalid filter field: {}", field));
+                    }
+                    list = list.filter(field, op, value);
+                }
+            }
+        }
+        Ok(list)
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters
+fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+fn decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = text
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("Invalid escape in the query: {}", text))?;
+                decoded.push(hex);
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| format!("Invalid UTF-8 in the query: {}", text))
+}
+
+/// # A page of a list endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The rows matching the filters, on every page
+    pub total: i64,
+    pub per_page: i64,
+    /// The page number, `None` when the page was read by cursor
+    pub page: Option<i64>,
+    /// Pass it as `cursor` to read the rows after this page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn has_next(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.page.is_some_and(|page| page > 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_is_the_first_page() {
+        let query: ListQuery = "".parse().unwrap();
+        assert_eq!(query, ListQuery::default());
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.per_page(), ListQuery::DEFAULT_PER_PAGE);
+        assert_eq!(query.offset(), 0);
+    }
+
+    #[test]
+    fn paging_is_clamped() {
+        let query: ListQuery = "?page=0&per_page=1000".parse().unwrap();
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.per_page(), ListQuery::MAX_PER_PAGE);
+        let query: ListQuery = "page=3&per_page=20".parse().unwrap();
+        assert_eq!(query.offset(), 40);
+    }
+
+    #[test]
+    fn sort_and_filters_are_parsed() {
+        let query: ListQuery = "sort=+code,-id&active=true&name.contains=a+b&id.gte=3"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.sort,
+            [
+                SortKey {
+                    field: "code".to_string(),
+                    direction: SortDirection::Asc
+                },
+                SortKey {
+                    field: "id".to_string(),
+                    direction: SortDirection::Desc
+                },
+            ]
+        );
+        let filters: Vec<_> = query
+            .filters
+            .iter()
+            .map(|filter| (filter.field.as_str(), filter.op, filter.value.as_str()))
+            .collect();
+        assert_eq!(
+            filters,
+            [
+                ("active", FilterOp::Eq, "true"),
+                ("name", FilterOp::Contains, "a b"),
+                ("id", FilterOp::Gte, "3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        assert!("page=two".parse::<ListQuery>().is_err());
+        assert!("per_page=".parse::<ListQuery>().is_err());
+        assert!("name.like=a".parse::<ListQuery>().is_err());
+        assert!("sort=name;drop".parse::<ListQuery>().is_err());
+        assert!("na%2Ame=a".parse::<ListQuery>().is_err());
+        assert!("name=%zz".parse::<ListQuery>().is_err());
+        assert!("name=%FF".parse::<ListQuery>().is_err());
+    }
+
+    #[test]
+    fn empty_cursor_is_ignored() {
+        let query: ListQuery = "cursor=&page=2".parse().unwrap();
+        assert_eq!(query.cursor, None);
+        assert_eq!(query.page(), 2);
+    }
+
+    #[test]
+    fn query_strings_round_trip() {
+        let query = ListQuery::default()
+            .with_cursor("7b22")
+            .with_per_page(10)
+            .sort_by("name", SortDirection::Desc)
+            .filter("name", FilterOp::Contains, "a&b=c %")
+            .filter("active", FilterOp::Eq, "true");
+        let string = query.to_query_string();
+        assert_eq!(
+            string,
+            "cursor=7b22&per_page=10&sort=-name&name.contains=a%26b%3Dc%20%25&active=true"
+        );
+        assert_eq!(string.parse::<ListQuery>(), Ok(query));
+    }
+
+    #[test]
+    fn a_page_clears_the_cursor() {
+        let query = ListQuery::default().with_cursor("7b22").with_page(2);
+        assert_eq!(query.to_query_string(), "page=2&per_page=50");
+    }
+}
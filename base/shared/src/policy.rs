@@ -0,0 +1,397 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::{OffsetDateTime, Time};
+
+use crate::user::UserRole;
+
+/// # A declarative ABAC policy
+/// Stored as JSON in the `policies` table. When the policy targets the request
+/// (resource type, action and role) and the condition holds, its effect is the decision,
+/// otherwise the policy is not applicable.
+///
+/// Users can only edit their own listings, during office hours:
+/// ```json
+/// {
+///     "effect": "deny",
+///     "resource_type": "listing",
+///     "actions": ["edit", "delete"],
+///     "roles": ["User"],
+///     "condition": { "op": "not", "condition": { "op": "all", "conditions": [
+///         { "op": "owner", "attribute": "owner_id" },
+///         { "op": "time_window", "from": "08:00", "to": "18:00", "days": [1, 2, 3, 4, 5] }
+///     ]}}
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyDocument {
+    #[serde(default)]
+    pub effect: Effect,
+    /// `*` matches every resource type
+    pub resource_type: String,
+    /// Empty matches every action
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Empty matches every role. Custom roles match too,
+    /// and a role matches the users of every role inheriting from it.
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Condition {
+    All {
+        conditions: Vec<Condition>,
+    },
+    Any {
+        conditions: Vec<Condition>,
+    },
+    Not {
+        condition: Box<Condition>,
+    },
+    Eq {
+        left: Operand,
+        right: Operand,
+    },
+    Ne {
+        left: Operand,
+        right: Operand,
+    },
+    Lt {
+        left: Operand,
+        right: Operand,
+    },
+    Le {
+        left: Operand,
+        right: Operand,
+    },
+    Gt {
+        left: Operand,
+        right: Operand,
+    },
+    Ge {
+        left: Operand,
+        right: Operand,
+    },
+    /// The value is one of the elements of the set (a list)
+    In {
+        value: Operand,
+        set: Operand,
+    },
+    /// The request time (the `time` context attribute) is between `from` and `to` ("HH:MM", UTC).
+    /// `days` restricts the weekdays, 1 is Monday and 7 is Sunday.
+    TimeWindow {
+        from: String,
+        to: String,
+        #[serde(default)]
+        days: Vec<u8>,
+    },
+    /// The resource attribute holds the id of the user
+    Owner {
+        attribute: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    #[default]
+    Permit,
+    Deny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Permit,
+    Deny,
+    NotApplicable,
+}
+
+impl From<Effect> for Decision {
+    fn from(effect: Effect) -> Self {
+        match effect {
+            Effect::Permit => Decision::Permit,
+            Effect::Deny => Decision::Deny,
+        }
+    }
+}
+
+/// # How the decisions of the policies are combined
+/// - `deny_overrides`: a single deny wins
+/// - `permit_overrides`: a single permit wins
+/// - `first_applicable`: the first policy that applies decides (built-in policies come first,
+///   then the stored ones by position)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombiningAlgorithm {
+    #[default]
+    DenyOverrides,
+    PermitOverrides,
+    FirstApplicable,
+}
+
+impl fmt::Display for CombiningAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CombiningAlgorithm::DenyOverrides => "deny_overrides",
+            CombiningAlgorithm::PermitOverrides => "permit_overrides",
+            CombiningAlgorithm::FirstApplicable => "first_applicable",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for CombiningAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "deny_overrides" => Ok(CombiningAlgorithm::DenyOverrides),
+            "permit_overrides" => Ok(CombiningAlgorithm::PermitOverrides),
+            "first_applicable" => Ok(CombiningAlgorithm::FirstApplicable),
+            _ => Err(format!("unknown combining algorithm: {}", value)),
+        }
+    }
+}
+
+/// What one policy decided and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyResult {
+    pub policy: String,
+    pub decision: Decision,
+    pub reason: String,
+}
+
+/// # Why a request was allowed or denied
+/// Requests no policy applies to are allowed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub allowed: bool,
+    pub decision: Decision,
+    pub algorithm: CombiningAlgorithm,
+    /// The policy whose result is the decision, `None` when no policy applies
+    pub decided_by: Option<String>,
+    /// Every policy in evaluation order
+    pub results: Vec<PolicyResult>,
+}
+
+/// Where a value comes from, e.g. `{ "user": "role" }` or `{ "value": 10 }`.
+///
+/// - `user`: `id`, `email`, `role` or one of the user attributes
+/// - `resource`: `id`, `type` or one of the resource attributes
+/// - `action`: `name` or one of the action attributes
+/// - `context`: `ip` or one of the context attributes (`method`, `path`, `time`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operand {
+    User(String),
+    Resource(String),
+    Action(String),
+    Context(String),
+    Value(Value),
+}
+
+/// A row of the `policies` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub document: PolicyDocument,
+    pub active: bool,
+    /// Order of evaluation, matters for `first_applicable`
+    pub position: i32,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyPayload {
+    pub name: String,
+    pub description: Option<String>,
+    pub document: PolicyDocument,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default)]
+    pub position: i32,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// # The user of a simulated request
+/// `id` alone loads the user from the database,
+/// with a `role` the user is made up from the given values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedUser {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub role: Option<UserRole>,
+    /// Roles assigned on top of `role`
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// JSON object of user attributes
+    #[serde(default)]
+    pub attributes: Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedResource {
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub attributes: Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedContext {
+    #[serde(default)]
+    pub ip: Option<String>,
+    /// e.g. `{ "time": "2025-08-18T10:00:00Z" }`, the current time when left out
+    #[serde(default)]
+    pub attributes: Value,
+}
+
+/// # Evaluate a request without performing it
+/// `policies` replaces the stored policies, so changes can be checked before saving them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationRequest {
+    pub user: SimulatedUser,
+    pub resource: SimulatedResource,
+    pub action: String,
+    #[serde(default)]
+    pub context: SimulatedContext,
+    #[serde(default)]
+    pub policies: Option<Vec<PolicyPayload>>,
+    #[serde(default)]
+    pub algorithm: Option<CombiningAlgorithm>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expectation {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyCase {
+    pub name: String,
+    pub user: SimulatedUser,
+    pub resource: SimulatedResource,
+    pub action: String,
+    #[serde(default)]
+    pub context: SimulatedContext,
+    pub expect: Expectation,
+}
+
+/// # Expected decisions against a policy set
+/// Written in YAML or JSON, the stored policies are used when `policies` is left out.
+///
+/// ```yaml
+/// policies:
+///   - name: owners-only
+///     document:
+///       effect: deny
+///       resource_type: listing
+///       actions: [edit]
+///       roles: [User]
+///       condition: { op: not, condition: { op: owner, attribute: owner_id } }
+/// cases:
+///   - name: users edit their own listings
+///     user: { id: u1, role: User }
+///     resource: { type: listing, id: l1, attributes: { owner_id: u1 } }
+///     action: edit
+///     expect: allow
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyFixture {
+    #[serde(default)]
+    pub algorithm: Option<CombiningAlgorithm>,
+    #[serde(default)]
+    pub policies: Option<Vec<PolicyPayload>>,
+    pub cases: Vec<PolicyCase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub expected: Expectation,
+    pub passed: bool,
+    pub explanation: Explanation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<CaseResult>,
+}
+
+crate::endpoint! {
+    /// Every policy, active or not
+    pub ListPolicies: Get "/policies" => Vec<Policy>;
+    /// Answers `{ "id": .. }` of the new policy
+    pub AddPolicy: Post "/policies", PolicyPayload => Value;
+    pub UpdatePolicy: Put "/policies/{id}" (i32), PolicyPayload => String;
+    pub DeletePolicy: Delete "/policies/{id}" (i32) => String;
+    /// Explain a request without performing it
+    pub SimulatePolicies: Post "/policies/simulate", SimulationRequest => Explanation;
+    /// The body is the YAML or JSON text of a `PolicyFixture`, sent with its content type
+    pub RunPolicyFixture: Post "/policies/test", String => FixtureReport;
+}
+
+/// Parses the "HH:MM" times of `TimeWindow`
+pub fn parse_clock(value: &str) -> Option<Time> {
+    let (hour, minute) = value.split_once(':')?;
+    Time::from_hms(hour.trim().parse().ok()?, minute.trim().parse().ok()?, 0).ok()
+}
+
+impl PolicyDocument {
+    /// Checks what serde can't, so broken policies are refused before being saved
+    pub fn validate(&self) -> Result<(), String> {
+        if self.resource_type.trim().is_empty() {
+            return Err("resource_type can't be empty".to_string());
+        }
+        self.condition.validate()
+    }
+}
+
+impl Condition {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                if conditions.is_empty() {
+                    return Err("all/any need at least one condition".to_string());
+                }
+                conditions.iter().try_for_each(Condition::validate)
+            }
+            Condition::Not { condition } => condition.validate(),
+            Condition::TimeWindow { from, to, days } => {
+                for time in [from, to] {
+                    if parse_clock(time).is_none() {
+                        return Err(format!("{} is not a HH:MM time", time));
+                    }
+                }
+                match days.iter().find(|day| !(1..=7).contains(*day)) {
+                    Some(day) => Err(format!("{} is not a weekday (1 to 7)", day)),
+                    None => Ok(()),
+                }
+            }
+            Condition::Owner { attribute } if attribute.trim().is_empty() => {
+                Err("owner needs an attribute".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
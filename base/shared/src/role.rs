@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Roles of the `UserRole` enum, they can't be renamed or deleted
+pub const BUILT_IN_ROLES: [&str; 3] = ["SuperAdmin", "Admin", "User"];
+
+/// # A role of the `roles` table
+/// A user has the permissions of their roles and of every role those inherit from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    /// `resource_type:action`, e.g. `category:write`, `user:*` or `*`
+    pub permissions: Vec<String>,
+    /// Names of the roles this one inherits from
+    pub parents: Vec<String>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl Role {
+    pub fn is_built_in(&self) -> bool {
+        BUILT_IN_ROLES.contains(&self.name.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolePayload {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub parents: Vec<String>,
+}
+
+impl RolePayload {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("the name can't be empty".to_string());
+        }
+        if self.parents.contains(&self.name) {
+            return Err("a role can't inherit from itself".to_string());
+        }
+        match self
+            .permissions
+            .iter()
+            .find(|permission| parse_permission(permission).is_none())
+        {
+            Some(permission) => Err(format!(
+                "{} is not a permission (resource_type:action or *)",
+                permission
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The roles assigned to a user on top of their built-in role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRolesPayload {
+    pub roles: Vec<String>,
+}
+
+/// `resource_type:action` as a pair, `*` alone is `*:*`
+pub fn parse_permission(permission: &str) -> Option<(&str, &str)> {
+    if permission == "*" {
+        return Some(("*", "*"));
+    }
+    let (resource_type, action) = permission.split_once(':')?;
+    if resource_type.is_empty() || action.is_empty() || action.contains(':') {
+        return None;
+    }
+    Some((resource_type, action))
+}
+
+/// The permission grants the action on the resource type
+pub fn permission_matches(permission: &str, resource_type: &str, action: &str) -> bool {
+    match parse_permission(permission) {
+        Some((granted_type, granted_action)) => {
+            (granted_type == "*" || granted_type == resource_type)
+                && (granted_action == "*" || granted_action == action)
+        }
+        None => false,
+    }
+}
+
+crate::endpoint! {
+    /// Every role with its permissions and parents
+    pub ListRoles: Get "/roles" => Vec<Role>;
+    /// Answers `{ "id": .. }` of the new role
+    pub AddRole: Post "/roles", RolePayload => serde_json::Value;
+    pub UpdateRole: Put "/roles/{id}" (i32), RolePayload => String;
+    /// Built-in roles can't be deleted
+    pub DeleteRole: Delete "/roles/{id}" (i32) => String;
+}
@@ -1,10 +1,17 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
 
 use serde::{Deserialize, Serialize};
 use serde_json::to_value;
 use time::OffsetDateTime;
 
-use crate::AttributeMap;
+use crate::{
+    AttributeMap,
+    role::{UserRolesPayload, permission_matches},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "backend", derive(sqlx::Type))]
@@ -18,6 +25,17 @@ pub enum UserRole {
     User,
 }
 
+impl UserRole {
+    /// The name of the matching row of the `roles` table
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserRole::SuperAdmin => "SuperAdmin",
+            UserRole::Admin => "Admin",
+            UserRole::User => "User",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: String,
@@ -34,7 +52,27 @@ pub struct UserInfo {
     pub email: String,
     pub role: UserRole,
     pub attributes: AttributeMap,
+    pub disabled: bool,
+    pub password_reset_required: bool,
     pub created_at: OffsetDateTime,
+    /// Every role of the user, the built-in one, the assigned ones and the inherited ones
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// The permissions of those roles
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl UserInfo {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|name| name == role)
+    }
+
+    pub fn has_permission(&self, resource_type: &str, action: &str) -> bool {
+        self.permissions
+            .iter()
+            .any(|permission| permission_matches(permission, resource_type, action))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +88,213 @@ pub struct LoginUser {
     pub password: String,
 }
 
+/// A row of the admin users table.
+/// Unverified users come from `pending_users` and have no role yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserSummary {
+    pub id: uuid::Uuid,
+    pub email: String,
+    pub role: Option<UserRole>,
+    /// Roles assigned on top of the built-in one
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub verified: bool,
+    pub disabled: bool,
+    pub attributes: serde_json::Value,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserFilter {
+    pub search: Option<String>,
+    pub role: Option<UserRole>,
+    pub verified: Option<bool>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl UserFilter {
+    pub const DEFAULT_PER_PAGE: i64 = 20;
+    pub const MAX_PER_PAGE: i64 = 100;
+
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page
+            .unwrap_or(Self::DEFAULT_PER_PAGE)
+            .clamp(1, Self::MAX_PER_PAGE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1) * self.per_page()
+    }
+
+    /// Builds the query string (without the leading `?`) for `GET /admin/users`
+    pub fn to_query_string(&self) -> String {
+        let mut params = vec![
+            format!("page={}", self.page()),
+            format!("per_page={}", self.per_page()),
+        ];
+        if let Some(search) = self.search.as_ref().filter(|s| !s.trim().is_empty()) {
+            params.push(format!("search={}", percent_encode(search.trim())));
+        }
+        if let Some(role) = &self.role {
+            params.push(format!("role={:?}", role));
+        }
+        if let Some(verified) = self.verified {
+            params.push(format!("verified={}", verified));
+        }
+        params.join("&")
+    }
+}
+
+pub(crate) fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserList {
+    pub users: Vec<UserSummary>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminAction {
+    pub id: i32,
+    pub admin_id: Option<uuid::Uuid>,
+    pub user_id: uuid::Uuid,
+    pub action: String,
+    pub details: serde_json::Value,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeRolePayload {
+    pub role: UserRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetDisabledPayload {
+    pub disabled: bool,
+}
+
+crate::endpoint! {
+    /// A page of the users, with the search, role and verification filters
+    pub ListUsers: Get "/admin/users", UserFilter => UserList;
+    /// Change the role (super admin only)
+    pub ChangeUserRole: Put "/admin/users/{id}/role" (uuid::Uuid), ChangeRolePayload => String;
+    /// Replace the roles assigned on top of the built-in one (super admin only)
+    pub SetUserRoles: Put "/admin/users/{id}/roles" (uuid::Uuid), UserRolesPayload => String;
+    /// Replace the attributes, a JSON object checked against the `user` schemas
+    pub SetUserAttributes: Put "/admin/users/{id}/attributes" (uuid::Uuid), serde_json::Value
+        => String;
+    /// Disable or enable the account
+    pub SetUserDisabled: Put "/admin/users/{id}/disabled" (uuid::Uuid), SetDisabledPayload
+        => String;
+    /// Block the account until the password is reset
+    pub ForcePasswordReset: Post "/admin/users/{id}/force-password-reset" (uuid::Uuid) => String;
+    /// Log in as the user (super admin only)
+    pub Impersonate: Post "/admin/users/{id}/impersonate" (uuid::Uuid) => String;
+}
+
+crate::endpoint! {
+    /// Sets the session cookie
+    pub Login: Post "/users/login", LoginUser => String;
+    /// The logged in user
+    pub CheckAuth: Get "/users/check-auth" => UserInfo;
+    /// Back to the session of the super admin
+    pub StopImpersonation: Post "/users/stop-impersonation" => String;
+}
+
+/// The account of the logged in user as shown on the profile page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: uuid::Uuid,
+    pub email: String,
+    pub role: UserRole,
+    pub preferred_language_id: Option<i32>,
+    /// Public path of the avatar image, e.g. `/uploads/avatars/<id>.png`
+    pub avatar: Option<String>,
+    /// The new email waiting for confirmation, if any
+    pub pending_email: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEmailPayload {
+    pub new_email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangePasswordPayload {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreferredLanguagePayload {
+    pub language_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteAccountPayload {
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "backend",
+    sqlx(type_name = "data_export_status", rename_all = "snake_case")
+)]
+pub enum DataExportStatus {
+    Pending,
+    Running,
+    Ready,
+    Failed,
+}
+
+/// A GDPR export of everything stored about a user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataExport {
+    pub id: uuid::Uuid,
+    pub status: DataExportStatus,
+    /// The archive can't be downloaded after this date
+    pub expires_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+    pub completed_at: Option<OffsetDateTime>,
+}
+
+crate::endpoint! {
+    pub GetProfile: Get "/users/profile" => Profile;
+    /// The new email is confirmed from the link sent to it
+    pub ChangeEmail: Put "/users/profile/email", ChangeEmailPayload => String;
+    pub ChangePassword: Put "/users/profile/password", ChangePasswordPayload => String;
+    pub SetPreferredLanguage: Put "/users/profile/language", PreferredLanguagePayload => String;
+    /// A multipart form with the image in the `avatar` field, answers the public path
+    pub UploadAvatar: Post "/users/profile/avatar" => String;
+    /// The password goes in the JSON body, unlike the other `DELETE` endpoints
+    pub DeleteAccount: Delete "/users/profile", DeleteAccountPayload => String;
+    /// Starts an export, or answers the one still running
+    pub RequestDataExport: Post "/users/profile/exports" => DataExport;
+    pub ListDataExports: Get "/users/profile/exports" => Vec<DataExport>;
+    /// The ZIP archive, a link rather than a call
+    pub DownloadDataExport: Get "/users/profile/exports/{id}/download" (uuid::Uuid) => String;
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct UserState {
     pub email: Option<String>,
@@ -82,6 +327,15 @@ pub struct UserResponse {
     pub success: bool,
     pub data: UserResponseData,
     pub status_code: u16,
+    /// Set on errors, one of `crate::error_code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Messages by field name for the validation errors
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_errors: BTreeMap<String, Vec<String>>,
+    /// The id of the request, also in the `x-request-id` header and the error dashboard
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -96,9 +350,27 @@ impl UserResponse {
             success,
             data,
             status_code,
+            code: None,
+            field_errors: BTreeMap::new(),
+            correlation_id: None,
         }
     }
 
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn field_errors(mut self, field_errors: BTreeMap<String, Vec<String>>) -> Self {
+        self.field_errors = field_errors;
+        self
+    }
+
+    pub fn correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
     pub fn with_json(data: impl Serialize) -> Self {
         let json = to_value(data);
         match json {
@@ -135,6 +407,14 @@ impl UserResponse {
         Self::new(false, UserResponseData::String(msg.into()), status_code)
     }
 
+    /// Error response carrying structured details (e.g. a list of failed validation rules)
+    pub fn with_error_json_and_code(data: impl Serialize, status_code: u16) -> Self {
+        match to_value(data) {
+            Ok(value) => Self::new(false, UserResponseData::Json(value), status_code),
+            Err(e) => Self::with_error(e.to_string()),
+        }
+    }
+
     pub fn message(&self) -> String {
         match &self.data {
             UserResponseData::String(msg) => msg.clone(),
@@ -142,6 +422,24 @@ impl UserResponse {
         }
     }
 
+    /// # The message in the language of the user
+    /// `translate` maps a code to its translation, the message is kept when it has none.
+    ///
+    /// ```
+    /// use shared::{error_code, user::UserResponse};
+    ///
+    /// let res = UserResponse::with_error_and_code("Role not found.", 404).code(error_code::NOT_FOUND);
+    /// let german = |code: &str| (code == error_code::NOT_FOUND).then(|| "Nicht gefunden".to_string());
+    /// assert_eq!(res.localized_message(german), "Nicht gefunden");
+    /// assert_eq!(res.localized_message(|_| None), "Role not found.");
+    /// ```
+    pub fn localized_message(&self, translate: impl Fn(&str) -> Option<String>) -> String {
+        self.code
+            .as_deref()
+            .and_then(translate)
+            .unwrap_or_else(|| self.message())
+    }
+
     pub fn json(&self) -> Option<serde_json::Value> {
         match &self.data {
             UserResponseData::Json(json) => Some(json.clone()),
@@ -1,10 +1,14 @@
 use axum::{
     extract::rejection::JsonRejection,
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
+use std::{collections::BTreeMap, sync::OnceLock};
 use thiserror::Error;
 
+/// Messages by field name, e.g. `{"password": ["Too short"]}`
+pub type FieldErrors = BTreeMap<String, Vec<String>>;
+
 #[derive(Error, Debug)]
 pub enum SamError {
     // #[error("{0}")]
@@ -25,6 +29,9 @@ pub enum SamError {
     ExpiredToken(String),
     #[error("Missing Enviroment Variable: {0}")]
     MissingEnviromentVariable(String),
+    /// The settings of the app are missing or invalid, it doesn't start
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
     #[error("Email Send Failed.")]
     EmailSendFailed,
     #[error("Email Not Found")]
@@ -33,26 +40,145 @@ pub enum SamError {
     InvalidEmail,
     #[error("Invalid Password: {0}")]
     InvalidPassword(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    /// `body` replaces the message when set, e.g. with the policy explanation in development
+    #[error("Forbidden: {reason}")]
+    PolicyDenied {
+        reason: String,
+        body: Option<String>,
+    },
+    #[error("{0} not found.")]
+    NotFound(String),
+    #[error("This email is already in use.")]
+    EmailTaken,
+    #[error("Invalid upload: {0}")]
+    InvalidUpload(String),
+    #[error("Invalid policy: {0}")]
+    InvalidPolicy(String),
+    #[error("Invalid attributes: {0}")]
+    InvalidAttributes(String),
+    #[error("Invalid role: {0}")]
+    InvalidRole(String),
+    #[error("Invalid nav item: {0}")]
+    InvalidNavItem(String),
+    /// Answered with the messages of every field that failed
+    #[error("Validation failed: {}", field_errors_message(.0))]
+    Validation(FieldErrors),
+    #[error("This account has been disabled.")]
+    AccountDisabled,
+    #[error("A password reset is required. Please check your email.")]
+    PasswordResetRequired,
     #[error("Something went wrong")]
     Any,
     #[error("{0}")]
     Err(String),
 }
 
-impl IntoResponse for SamError {
-    fn into_response(self) -> Response {
-        let status = match self {
+fn field_errors_message(errors: &FieldErrors) -> String {
+    errors
+        .iter()
+        .map(|(field, messages)| format!("{}: {}", field, messages.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// # What the client gets to know about an error
+/// `SamError` puts it in the extensions of its response,
+/// the backend builds the `UserResponse` body from it.
+#[derive(Debug, Clone)]
+pub struct ErrorDetails {
+    pub code: &'static str,
+    pub message: String,
+    pub field_errors: FieldErrors,
+}
+
+impl SamError {
+    /// # A stable code for clients to match on
+    /// Never change a code once released, `shared::error_code` lists them for the frontend.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SamError::Database(_) => "database_error",
+            SamError::InvalidJson(_) => "invalid_json",
+            SamError::LoginFailed => "login_failed",
+            SamError::RegistrationFailed => "registration_failed",
+            SamError::NotAuthorized => "not_authorized",
+            SamError::InvalidToken => "invalid_token",
+            SamError::ExpiredToken(_) => "expired_token",
+            SamError::MissingEnviromentVariable(_) => "missing_environment_variable",
+            SamError::InvalidConfig(_) => "invalid_config",
+            SamError::EmailSendFailed => "email_send_failed",
+            SamError::EmailNotFound => "email_not_found",
+            SamError::InvalidEmail => "invalid_email",
+            SamError::InvalidPassword(_) => "invalid_password",
+            SamError::Forbidden(_) => "forbidden",
+            SamError::PolicyDenied { .. } => "policy_denied",
+            SamError::NotFound(_) => "not_found",
+            SamError::EmailTaken => "email_taken",
+            SamError::InvalidUpload(_) => "invalid_upload",
+            SamError::InvalidPolicy(_) => "invalid_policy",
+            SamError::InvalidAttributes(_) => "invalid_attributes",
+            SamError::InvalidRole(_) => "invalid_role",
+            SamError::InvalidNavItem(_) => "invalid_nav_item",
+            SamError::Validation(_) => "validation_failed",
+            SamError::AccountDisabled => "account_disabled",
+            SamError::PasswordResetRequired => "password_reset_required",
+            SamError::Any => "internal_error",
+            SamError::Err(_) => "error",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
             SamError::LoginFailed | SamError::NotAuthorized => StatusCode::UNAUTHORIZED,
+            SamError::Forbidden(_)
+            | SamError::PolicyDenied { .. }
+            | SamError::AccountDisabled
+            | SamError::PasswordResetRequired => StatusCode::FORBIDDEN,
+            SamError::NotFound(_) => StatusCode::NOT_FOUND,
+            SamError::EmailTaken => StatusCode::CONFLICT,
             SamError::InvalidJson(_)
             | SamError::RegistrationFailed
             | SamError::InvalidToken
             | SamError::InvalidEmail
             | SamError::InvalidPassword(_)
+            | SamError::InvalidUpload(_)
+            | SamError::InvalidPolicy(_)
+            | SamError::InvalidAttributes(_)
+            | SamError::InvalidRole(_)
+            | SamError::InvalidNavItem(_)
+            | SamError::Validation(_)
             | SamError::ExpiredToken(_) => StatusCode::BAD_REQUEST,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        }
+    }
+}
 
-        (status, self.to_string()).into_response()
+impl IntoResponse for SamError {
+    fn into_response(self) -> Response {
+        if let SamError::PolicyDenied {
+            body: Some(body), ..
+        } = self
+        {
+            return (
+                StatusCode::FORBIDDEN,
+                [(header::CONTENT_TYPE, "application/json")],
+                body,
+            )
+                .into_response();
+        }
+
+        let details = ErrorDetails {
+            code: self.code(),
+            message: self.to_string(),
+            field_errors: match &self {
+                SamError::Validation(errors) => errors.clone(),
+                _ => FieldErrors::new(),
+            },
+        };
+        let mut res = (self.status_code(), details.message.clone()).into_response();
+        res.extensions_mut().insert(details);
+        res
     }
 }
 
@@ -80,15 +206,123 @@ impl From<sqlx::Error> for SamError {
     }
 }
 
+/// # Where an error happened and what it said
+/// Handed to the reporter set with `set_error_reporter`.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub module: String,
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+}
+
+type ErrorReporter = Box<dyn Fn(ErrorReport) + Send + Sync>;
+
+static ERROR_REPORTER: OnceLock<ErrorReporter> = OnceLock::new();
+
+/// # Send the errors of `any_with_log!` somewhere, e.g. to the database
+/// Only the first reporter is kept. The reporter runs on the task of the error so it must not block.
+pub fn set_error_reporter(reporter: impl Fn(ErrorReport) + Send + Sync + 'static) {
+    let _ = ERROR_REPORTER.set(Box::new(reporter));
+}
+
+/// Hands the report to the reporter, if there is one
+pub fn report_error(report: ErrorReport) {
+    if let Some(reporter) = ERROR_REPORTER.get() {
+        reporter(report);
+    }
+}
+
 /// # Log error and return general error
-/// This macro return SamError::Any after logging the origin error   
+/// This macro return SamError::Any after logging and reporting the origin error   
 /// We send SamError::Any to a client for avoiding misuse
 #[macro_export]
 macro_rules! any_with_log {
     ($err:expr) => {
         {
-            tracing::error!("\nModule: {}\nFile: {}\nLine: {}\n🚨Error Message: \n-------------\n{}\n-------------", module_path!(),file!(),line!(),$err);
+            let message = $err.to_string();
+            tracing::error!("\nModule: {}\nFile: {}\nLine: {}\n🚨Error Message: \n-------------\n{}\n-------------", module_path!(),file!(),line!(),message);
+            $crate::report_error($crate::ErrorReport {
+                module: module_path!().to_string(),
+                file: file!().to_string(),
+                line: line!(),
+                message,
+            });
             $crate::SamError::Any
         }
     };
 }
+
+/// Used by the code `sam_proc_macros::catch_error` expands to
+#[doc(hidden)]
+pub mod __private {
+    use std::fmt::{Debug, Display};
+
+    /// An argument recorded in the span, `Debug` when it can be
+    ///
+    /// `(&Arg(&x)).arg_text()` picks `DebugArg` when `x` is `Debug`: its impl on `Arg` takes
+    /// the `&Arg` receiver as is, `OpaqueArg` on `&Arg` is only tried with one more `&`.
+    pub struct Arg<'a, T: ?Sized>(pub &'a T);
+
+    pub trait DebugArg {
+        fn arg_text(&self) -> String;
+    }
+
+    impl<T: Debug + ?Sized> DebugArg for Arg<'_, T> {
+        fn arg_text(&self) -> String {
+            format!("{:?}", self.0)
+        }
+    }
+
+    pub trait OpaqueArg {
+        fn arg_text(&self) -> String;
+    }
+
+    impl<T: ?Sized> OpaqueArg for &Arg<'_, T> {
+        fn arg_text(&self) -> String {
+            "_".to_string()
+        }
+    }
+
+    /// The message of a failed result
+    pub trait Failure {
+        fn failure(&self) -> Option<String>;
+    }
+
+    impl<T, E: Display> Failure for Result<T, E> {
+        fn failure(&self) -> Option<String> {
+            self.as_ref().err().map(|err| err.to_string())
+        }
+    }
+
+    impl<T> Failure for Option<T> {
+        fn failure(&self) -> Option<String> {
+            self.is_none().then(|| "returned None".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::__private::{Arg, DebugArg as _, OpaqueArg as _};
+
+    struct NoDebug;
+
+    // Called the way `catch_error` expands, the borrow picks the impl
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn debug_arguments_are_printed() {
+        let id = 42;
+        let email = "a@b.c";
+        assert_eq!((&Arg(&id)).arg_text(), "42");
+        assert_eq!((&Arg(&email)).arg_text(), "\"a@b.c\"");
+        assert_eq!((&Arg(&Some(id))).arg_text(), "Some(42)");
+    }
+
+    #[test]
+    fn other_arguments_are_opaque() {
+        let value = NoDebug;
+        assert_eq!((&Arg(&value)).arg_text(), "_");
+        assert_eq!((&Arg(&&value)).arg_text(), "_");
+    }
+}
@@ -1,53 +1,210 @@
 use proc_macro::TokenStream;
+use proc_macro2::Span;
 use quote::quote;
-use syn::{parse_macro_input, Expr, ItemFn};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    DeriveInput, FnArg, Ident, ItemFn, LitStr, Pat, ReturnType, Token, Type,
+};
 
+mod repository;
+mod rest;
+
+/// The options of `#[catch_error(...)]`
+struct CatchErrorArgs {
+    /// Arguments left out of the span
+    skip: Vec<Ident>,
+    skip_all: bool,
+    /// The `tracing::Level` constant of the failure event
+    level: Ident,
+    /// The `tracing::Level` constant of the span around the body
+    span_level: Ident,
+    persist: bool,
+}
+
+impl Parse for CatchErrorArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = CatchErrorArgs {
+            skip: Vec::new(),
+            skip_all: false,
+            level: Ident::new("ERROR", Span::call_site()),
+            span_level: Ident::new("INFO", Span::call_site()),
+            persist: false,
+        };
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            match option.to_string().as_str() {
+                "skip" => {
+                    let content;
+                    parenthesized!(content in input);
+                    args.skip
+                        .extend(Punctuated::<Ident, Token![,]>::parse_terminated(&content)?);
+                }
+                "skip_all" => args.skip_all = true,
+                "persist" => args.persist = true,
+                "level" => args.level = parse_level(input)?,
+                "span_level" => args.span_level = parse_level(input)?,
+                _ => return Err(syn::Error::new(
+                    option.span(),
+                    "expected skip(..), skip_all, level = \"..\", span_level = \"..\" or persist",
+                )),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// `= "warn"` as a `tracing::Level` constant
+fn parse_level(input: ParseStream) -> syn::Result<Ident> {
+    input.parse::<Token![=]>()?;
+    let level: LitStr = input.parse()?;
+    let constant = match level.value().to_lowercase().as_str() {
+        "trace" => "TRACE",
+        "debug" => "DEBUG",
+        "info" => "INFO",
+        "warn" => "WARN",
+        "error" => "ERROR",
+        _ => {
+            return Err(syn::Error::new(
+                level.span(),
+                "expected trace, debug, info, warn or error",
+            ))
+        }
+    };
+    Ok(Ident::new(constant, level.span()))
+}
+
+/// # Log the failures of a function
+/// Works for `async` and plain functions returning a `Result` (the error must be `Display`)
+/// or an `Option` (`None` is the failure). The body runs in a `tracing` span named after the
+/// function, with the arguments as fields and the duration as `elapsed_ms`.
+///
+/// Options:
+/// - `skip(password, token)` leaves arguments out of the span, `skip_all` leaves out all of them.
+///   Arguments without `Debug` are recorded as `_`.
+/// - `level = "warn"` logs the failure at another level than `error`.
+/// - `span_level = "debug"` opens the span at another level than `info`.
+/// - `persist` also hands the failure to `sam_error::report_error`, e.g. for the error dashboard.
+///
+/// ```
+/// use sam_proc_macros::catch_error;
+///
+/// #[catch_error(skip(password), level = "warn", persist)]
+/// fn check_password(email: &str, password: &str) -> Result<bool, String> {
+///     if email.is_empty() {
+///         return Err("No email".to_string());
+///     }
+///     Ok(password.len() > 8)
+/// }
+///
+/// assert_eq!(check_password("a@b.c", "correct horse"), Ok(true));
+/// assert!(check_password("", "correct horse").is_err());
+/// ```
+///
+/// The expanded code uses `tracing` and `sam_error`, the crate must depend on both.
 #[proc_macro_attribute]
-pub fn catch_error(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn catch_error(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as CatchErrorArgs);
     // Parse the input function
     let input_fn = parse_macro_input!(item as ItemFn);
 
     // Extract the function signature and body
+    let fn_attrs = &input_fn.attrs;
     let fn_vis = &input_fn.vis; // e.g., `pub`
     let fn_sig = &input_fn.sig; // e.g., `async fn add_user_handler(...) -> Result<Response>`
     let fn_name = &input_fn.sig.ident; // Function name
     let fn_block = &input_fn.block; // The function body
+    let level = &args.level;
+    let span_level = &args.span_level;
 
-    // Check if the function is async
-    let is_async = fn_sig.asyncness.is_some();
+    let ReturnType::Type(_, return_type) = &fn_sig.output else {
+        return syn::Error::new_spanned(
+            fn_sig,
+            "catch_error needs a function returning a Result or an Option",
+        )
+        .to_compile_error()
+        .into();
+    };
+    // `impl Trait` can't annotate a variable, the body must tell the type then
+    let result_type = match return_type.as_ref() {
+        Type::ImplTrait(_) => quote! {},
+        return_type => quote! { : #return_type },
+    };
 
-    let match_block = quote! {
-        Ok(res) => Ok(res),
-        Err(err) => {
-            tracing::error!("\nModule: {}\nFile: {}\nLine: {}\nFunction: {}\n🚨Error Message: \n-------------\n{}\n-------------", module_path!(),file!(),line!(),stringify!(#fn_name),err);
-            return Err(err);
-        }
+    // The arguments recorded in the span
+    let fields: Vec<&Ident> = if args.skip_all {
+        Vec::new()
+    } else {
+        fn_sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(arg) => match arg.pat.as_ref() {
+                    Pat::Ident(pat) => Some(&pat.ident),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .filter(|ident| !args.skip.contains(ident))
+            .collect()
     };
-    // Generate the new function body
-    let new_block = if is_async {
+
+    // Check if the function is async
+    let is_async = fn_sig.asyncness.is_some();
+    let run_body = if is_async {
         quote! {
-            {
-                let result = async #fn_block;
-                match result.await {
-                    #match_block
-                }
-            }
+            tracing::Instrument::instrument(async #fn_block, __catch_error_span.clone()).await
         }
     } else {
         quote! {
-            {
-                let result = #fn_block;
-                match result {
-                    #match_block
-                }
-            }
+            __catch_error_span.in_scope(|| #fn_block)
         }
     };
 
+    let persist = if args.persist {
+        quote! {
+            sam_error::report_error(sam_error::ErrorReport {
+                module: module_path!().to_string(),
+                file: file!().to_string(),
+                line: line!(),
+                message: __catch_error_message.clone(),
+            });
+        }
+    } else {
+        quote! {}
+    };
+
     // Generate the new function
     let output = quote! {
+        #(#fn_attrs)*
         #fn_vis #fn_sig {
-            #new_block
+            #[allow(unused_imports)]
+            use sam_error::__private::{DebugArg as _, OpaqueArg as _};
+            let __catch_error_span = tracing::span!(
+                tracing::Level::#span_level,
+                stringify!(#fn_name),
+                #(#fields = %(&sam_error::__private::Arg(&#fields)).arg_text(),)*
+                elapsed_ms = tracing::field::Empty
+            );
+            let __catch_error_start = std::time::Instant::now();
+            let __catch_error_result #result_type = #run_body;
+            __catch_error_span.record(
+                "elapsed_ms",
+                __catch_error_start.elapsed().as_millis() as u64,
+            );
+            if let Some(__catch_error_message) =
+                sam_error::__private::Failure::failure(&__catch_error_result)
+            {
+                let _enter = __catch_error_span.enter();
+                tracing::event!(tracing::Level::#level, "\nModule: {}\nFile: {}\nLine: {}\nFunction: {}\n🚨Error Message: \n-------------\n{}\n-------------", module_path!(),file!(),line!(),stringify!(#fn_name),__catch_error_message);
+                #persist
+            }
+            __catch_error_result
         }
     };
 
@@ -55,6 +212,94 @@ pub fn catch_error(_attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::from(output)
 }
 
+/// # CRUD functions for a table
+/// Generates `list` (paged), `all`, `count`, `get`, `add`, `update` and `delete` (plus `restore`
+/// with soft delete) on the struct, each wrapped in `#[catch_error]` and returning `SamError`s.
+/// Every field is the column of the same name, the struct also needs `sqlx::FromRow`.
+/// They take any executor, a pool or a transaction, and custom queries go in another `impl`.
+///
+/// - `#[table = "languages"]` names the table.
+/// - `#[soft_delete = "deleted_at"]` hides the rows where the column is set, `delete` sets it.
+/// - `#[repository(id)]` marks the key when it isn't the `id` field.
+/// - `#[repository(generated)]` marks the columns the database fills, e.g. a serial id.
+///
+/// ```no_run
+/// # use sam_proc_macros::Repository;
+/// #[derive(sqlx::FromRow, Repository)]
+/// #[table = "languages"]
+/// pub struct Language {
+///     #[repository(generated)]
+///     pub id: i32,
+///     pub code: String,
+/// }
+///
+/// # async fn example(pool: sqlx::PgPool) -> Result<(), sam_error::SamError> {
+/// # let mut tx = pool.begin().await?;
+/// let language = Language::get(&pool, 1).await?;
+/// language.update(&mut *tx).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The expanded code uses `sqlx`, `sam_error`, `sam_proc_macros` and `tracing`.
+#[proc_macro_derive(Repository, attributes(table, soft_delete, repository))]
+pub fn derive_repository(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    repository::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// # REST routes for a resource
+/// Builds `fn name(state) -> Router<State>` with the usual handler shapes: take the state, call
+/// the db function and answer with `UserResponse::with_json` or `with_success`.
+///
+/// - `list` is `GET path`, `get` is `GET path/{id}`, `create` and `update` are `POST` and `PUT path`
+///   with the resource as JSON, `delete` is `DELETE path/{id}`. Only the listed ones are routed.
+/// - `get(String)` sets the type of `{id}`, `i32` by default.
+/// - `list(ListQuery): list_languages` parses the query string (`crate::list_query::ListParams`)
+///   and passes it to the db function, which returns the page to answer with.
+/// - `list: list_languages` names the db function, called with `&PgPool` and the id or the
+///   payload (writes also get the `&AuditActor`). Without one the `Repository` function is
+///   called, which leaves no audit entry.
+/// - `get: get_language => GetLanguage` routes the operation at the path of a
+///   `shared::api::Endpoint` instead, takes `{id}` as its `Params` and answers with
+///   `crate::api::reply`, so a path or type that drifts from the declaration doesn't compile.
+///   The endpoint must use the method of the operation.
+/// - `validate: check_language` runs `fn(&T) -> Result<()>` on the payload before a write.
+/// - Writes need a signed in user, plus `authorize: "language"` checks the ABAC policies for
+///   `write_action` (`"write"` by default) on that resource type. `read_action` guards the reads
+///   the same way, they are public otherwise.
+/// - `read_routes` and `write_routes` add custom handlers behind the same guards.
+///
+/// ```ignore
+/// rest_routes! {
+///     pub fn language_routes(AppState) for Language at "/languages" {
+///         authorize: "language",
+///         validate: validate_language,
+///         list(ListQuery): list_languages => ListLanguages,
+///         get: get_language => GetLanguage,
+///         create: add_language => AddLanguage,
+///         update: update_language => UpdateLanguage,
+///         delete(i32): delete_language,
+///         write_routes: {
+///             "/languages/{id}/default" => put(set_default_language_handler),
+///         },
+///     }
+/// }
+/// ```
+///
+/// The expanded code uses `axum` and the backend's `crate::error`, `crate::response`,
+/// `crate::audit`, `crate::abac`, `crate::list_query`, `crate::user` and, with endpoints,
+/// `crate::api` modules, and a `pool` field on the state.
+#[proc_macro]
+pub fn rest_routes(input: TokenStream) -> TokenStream {
+    let routes = parse_macro_input!(input as rest::RestRoutes);
+    rest::expand(routes)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 // #[proc_macro_attribute]
 // pub fn catch_error(_attr: TokenStream, item: TokenStream) -> TokenStream {
 //     // Parse the input function
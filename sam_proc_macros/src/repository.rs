@@ -0,0 +1,444 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Expr, Fields, Ident, Lit, LitStr, Type};
+
+/// A field of the struct, mapped to the column of the same name
+struct Column {
+    ident: Ident,
+    name: String,
+    ty: Type,
+    id: bool,
+    /// Filled by the database, left out of inserts and updates
+    generated: bool,
+}
+
+/// `#[name = "value"]` on the struct
+fn string_attr(input: &DeriveInput, name: &str) -> syn::Result<Option<LitStr>> {
+    let Some(attr) = input.attrs.iter().find(|attr| attr.path().is_ident(name)) else {
+        return Ok(None);
+    };
+    match &attr.meta.require_name_value()?.value {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Str(value) => Ok(Some(value.clone())),
+            lit => Err(syn::Error::new_spanned(lit, "expected a string")),
+        },
+        value => Err(syn::Error::new_spanned(value, "expected a string")),
+    }
+}
+
+fn columns(input: &DeriveInput) -> syn::Result<Vec<Column>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "Repository needs a struct"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "Repository needs named fields",
+        ));
+    };
+
+    let mut columns = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.clone().expect("named field");
+        let mut column = Column {
+            name: ident.to_string().trim_start_matches("r#").to_string(),
+            id: false,
+            generated: false,
+            ty: field.ty.clone(),
+            ident,
+        };
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("repository"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    column.id = true;
+                    Ok(())
+                } else if meta.path.is_ident("generated") {
+                    column.generated = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected id or generated"))
+                }
+            })?;
+        }
+        columns.push(column);
+    }
+
+    // The field named `id` unless another one is marked
+    if !columns.iter().any(|column| column.id) {
+        match columns.iter_mut().find(|column| column.name == "id") {
+            Some(column) => column.id = true,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "Repository needs an `id` field or a field marked #[repository(id)]",
+                ))
+            }
+        }
+    }
+    Ok(columns)
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let table = string_attr(&input, "table")?
+        .ok_or_else(|| syn::Error::new_spanned(&input, "Repository needs #[table = \"...\"]"))?
+        .value();
+    let soft_delete = string_attr(&input, "soft_delete")?.map(|column| column.value());
+    let columns = columns(&input)?;
+
+    let id = columns.iter().find(|column| column.id).expect("id column");
+    let id_name = &id.name;
+    let id_type = &id.ty;
+    let id_ident = &id.ident;
+    let not_found = name.to_string();
+
+    let column_list = columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Hidden rows are left out of every read
+    let visible = soft_delete
+        .as_ref()
+        .map(|column| format!(" AND {} IS NULL", column))
+        .unwrap_or_default();
+
+    let insert_columns: Vec<&Column> = columns.iter().filter(|column| !column.generated).collect();
+    let insert_sql = format!(
+        "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
+        table,
+        insert_columns
+            .iter()
+            .map(|column| column.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        (1..=insert_columns.len())
+            .map(|i| format!("${}", i))
+            .collect::<Vec<_>>()
+            .join(", "),
+        column_list
+    );
+    let insert_binds = insert_columns.iter().map(|column| &column.ident);
+
+    let update_columns: Vec<&Column> = columns
+        .iter()
+        .filter(|column| !column.generated && !column.id)
+        .collect();
+    let update_sql = format!(
+        "UPDATE {} SET {} WHERE {} = ${}{} RETURNING {}",
+        table,
+        update_columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ${}", column.name, i + 1))
+            .collect::<Vec<_>>()
+            .join(", "),
+        id_name,
+        update_columns.len() + 1,
+        visible,
+        column_list
+    );
+    let update_binds = update_columns.iter().map(|column| &column.ident);
+
+    let list_sql = format!(
+        "SELECT {} FROM {} WHERE TRUE{} ORDER BY {} LIMIT $1 OFFSET $2",
+        column_list, table, visible, id_name
+    );
+    let all_sql = format!(
+        "SELECT {} FROM {} WHERE TRUE{} ORDER BY {}",
+        column_list, table, visible, id_name
+    );
+    let count_sql = format!("SELECT COUNT(*) FROM {} WHERE TRUE{}", table, visible);
+    let get_sql = format!(
+        "SELECT {} FROM {} WHERE {} = $1{}",
+        column_list, table, id_name, visible
+    );
+    let delete_sql = match &soft_delete {
+        Some(column) => format!(
+            "UPDATE {} SET {} = CURRENT_TIMESTAMP WHERE {} = $1 AND {} IS NULL",
+            table, column, id_name, column
+        ),
+        None => format!("DELETE FROM {} WHERE {} = $1", table, id_name),
+    };
+    let restore = soft_delete.as_ref().map(|column| {
+        let restore_sql = format!(
+            "UPDATE {} SET {} = NULL WHERE {} = $1 AND {} IS NOT NULL",
+            table, column, id_name, column
+        );
+        quote! {
+            /// Brings back a soft deleted row
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn restore<'e, E: ::sqlx::PgExecutor<'e>>(
+                executor: E,
+                id: #id_type,
+            ) -> ::std::result::Result<(), ::sam_error::SamError> {
+                let result = ::sqlx::query(#restore_sql).bind(id).execute(executor).await?;
+                if result.rows_affected() == 0 {
+                    return Err(::sam_error::SamError::NotFound(#not_found.to_string()));
+                }
+                Ok(())
+            }
+        }
+    });
+    let soft_delete_const = match &soft_delete {
+        Some(column) => quote! { Some(#column) },
+        None => quote! { None },
+    };
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub const TABLE: &'static str = #table;
+            pub const COLUMNS: &'static str = #column_list;
+            /// The column marking soft deleted rows
+            pub const SOFT_DELETE: Option<&'static str> = #soft_delete_const;
+
+            /// A page of rows ordered by id, `page` starts at 1
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn list<'e, E: ::sqlx::PgExecutor<'e>>(
+                executor: E,
+                page: i64,
+                per_page: i64,
+            ) -> ::std::result::Result<Vec<Self>, ::sam_error::SamError> {
+                let per_page = per_page.max(1);
+                let rows = ::sqlx::query_as::<_, Self>(#list_sql)
+                    .bind(per_page)
+                    .bind((page.max(1) - 1) * per_page)
+                    .fetch_all(executor)
+                    .await?;
+                Ok(rows)
+            }
+
+            /// Every row ordered by id
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn all<'e, E: ::sqlx::PgExecutor<'e>>(
+                executor: E,
+            ) -> ::std::result::Result<Vec<Self>, ::sam_error::SamError> {
+                let rows = ::sqlx::query_as::<_, Self>(#all_sql).fetch_all(executor).await?;
+                Ok(rows)
+            }
+
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn count<'e, E: ::sqlx::PgExecutor<'e>>(
+                executor: E,
+            ) -> ::std::result::Result<i64, ::sam_error::SamError> {
+                let count: i64 = ::sqlx::query_scalar(#count_sql).fetch_one(executor).await?;
+                Ok(count)
+            }
+
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn get<'e, E: ::sqlx::PgExecutor<'e>>(
+                executor: E,
+                id: #id_type,
+            ) -> ::std::result::Result<Self, ::sam_error::SamError> {
+                ::sqlx::query_as::<_, Self>(#get_sql)
+                    .bind(id)
+                    .fetch_optional(executor)
+                    .await?
+                    .ok_or_else(|| ::sam_error::SamError::NotFound(#not_found.to_string()))
+            }
+
+            /// Inserts the row and returns it with the columns the database filled
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn add<'e, E: ::sqlx::PgExecutor<'e>>(
+                &self,
+                executor: E,
+            ) -> ::std::result::Result<Self, ::sam_error::SamError> {
+                let row = ::sqlx::query_as::<_, Self>(#insert_sql)
+                    #(.bind(&self.#insert_binds))*
+                    .fetch_one(executor)
+                    .await?;
+                Ok(row)
+            }
+
+            /// Writes every column but the id and the generated ones
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn update<'e, E: ::sqlx::PgExecutor<'e>>(
+                &self,
+                executor: E,
+            ) -> ::std::result::Result<Self, ::sam_error::SamError> {
+                ::sqlx::query_as::<_, Self>(#update_sql)
+                    #(.bind(&self.#update_binds))*
+                    .bind(&self.#id_ident)
+                    .fetch_optional(executor)
+                    .await?
+                    .ok_or_else(|| ::sam_error::SamError::NotFound(#not_found.to_string()))
+            }
+
+            /// Sets the soft delete column when there is one, removes the row otherwise
+            #[::sam_proc_macros::catch_error(skip(executor))]
+            pub async fn delete<'e, E: ::sqlx::PgExecutor<'e>>(
+                executor: E,
+                id: #id_type,
+            ) -> ::std::result::Result<(), ::sam_error::SamError> {
+                let result = ::sqlx::query(#delete_sql).bind(id).execute(executor).await?;
+                if result.rows_affected() == 0 {
+                    return Err(::sam_error::SamError::NotFound(#not_found.to_string()));
+                }
+                Ok(())
+            }
+
+            #restore
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expanded(input: DeriveInput) -> String {
+        expand(input).expect("the struct expands").to_string()
+    }
+
+    fn error(input: DeriveInput) -> String {
+        expand(input).expect_err("an error").to_string()
+    }
+
+    #[test]
+    fn generated_columns_are_left_out_of_the_writes() {
+        let code = expanded(parse_quote! {
+            #[table = "languages"]
+            struct Language {
+                #[repository(generated)]
+                id: i32,
+                code: String,
+                name: String,
+                #[repository(generated)]
+                created_at: String,
+            }
+        });
+        assert!(code.contains(
+            "\"INSERT INTO languages (code, name) VALUES ($1, $2) \
+             RETURNING id, code, name, created_at\""
+        ));
+        assert!(code.contains(
+            "\"UPDATE languages SET code = $1, name = $2 WHERE id = $3 \
+             RETURNING id, code, name, created_at\""
+        ));
+        assert!(!code.contains("bind (& self . created_at)"));
+        assert_eq!(code.matches("bind (& self . id)").count(), 1);
+    }
+
+    #[test]
+    fn pages_are_read_by_id() {
+        let code = expanded(parse_quote! {
+            #[table = "languages"]
+            struct Language {
+                id: i32,
+                code: String,
+            }
+        });
+        assert!(code.contains(
+            "\"SELECT id, code FROM languages WHERE TRUE ORDER BY id LIMIT $1 OFFSET $2\""
+        ));
+        assert!(code.contains("bind ((page . max (1) - 1) * per_page)"));
+        assert!(code.contains("\"SELECT id, code FROM languages WHERE TRUE ORDER BY id\""));
+        assert!(code.contains("\"SELECT COUNT(*) FROM languages WHERE TRUE\""));
+        // Without a generated column the id is inserted too
+        assert!(code
+            .contains("\"INSERT INTO languages (id, code) VALUES ($1, $2) RETURNING id, code\""));
+        assert!(code.contains("\"DELETE FROM languages WHERE id = $1\""));
+        assert!(!code.contains("fn restore"));
+    }
+
+    #[test]
+    fn soft_deleted_rows_are_hidden() {
+        let code = expanded(parse_quote! {
+            #[table = "categories"]
+            #[soft_delete = "deleted_at"]
+            struct Category {
+                id: String,
+                parent_id: Option<String>,
+            }
+        });
+        assert!(code.contains(
+            "\"SELECT id, parent_id FROM categories WHERE TRUE AND deleted_at IS NULL \
+             ORDER BY id LIMIT $1 OFFSET $2\""
+        ));
+        assert!(code.contains(
+            "\"SELECT id, parent_id FROM categories WHERE id = $1 AND deleted_at IS NULL\""
+        ));
+        assert!(code.contains(
+            "\"UPDATE categories SET deleted_at = CURRENT_TIMESTAMP \
+             WHERE id = $1 AND deleted_at IS NULL\""
+        ));
+        assert!(code.contains(
+            "\"UPDATE categories SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL\""
+        ));
+        assert!(code.contains("fn restore"));
+    }
+
+    #[test]
+    fn the_marked_field_is_the_key() {
+        let code = expanded(parse_quote! {
+            #[table = "settings"]
+            struct Setting {
+                #[repository(id)]
+                key: String,
+                value: String,
+                r#type: String,
+            }
+        });
+        assert!(code.contains("\"SELECT key, value, type FROM settings WHERE key = $1\""));
+        assert!(code.contains(
+            "\"UPDATE settings SET value = $1, type = $2 WHERE key = $3 \
+             RETURNING key, value, type\""
+        ));
+        assert!(code.contains("id : String"));
+    }
+
+    #[test]
+    fn invalid_structs_are_rejected() {
+        assert_eq!(
+            error(parse_quote! {
+                struct Language { id: i32 }
+            }),
+            "Repository needs #[table = \"...\"]"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[table = 1]
+                struct Language { id: i32 }
+            }),
+            "expected a string"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[table = "languages"]
+                enum Language { English }
+            }),
+            "Repository needs a struct"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[table = "languages"]
+                struct Language(i32);
+            }),
+            "Repository needs named fields"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[table = "languages"]
+                struct Language { code: String }
+            }),
+            "Repository needs an `id` field or a field marked #[repository(id)]"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[table = "languages"]
+                struct Language {
+                    #[repository(key)]
+                    id: i32,
+                }
+            }),
+            "expected id or generated"
+        );
+    }
+}
@@ -0,0 +1,723 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Expr, Ident, LitStr, Path, Token, Type, Visibility,
+};
+
+/// One of the generated endpoints, `get(String): get_language` or
+/// `get: get_language => GetLanguage`
+struct Operation {
+    /// The type of the `{id}` segment, or of the query string for `list`
+    id: Option<Type>,
+    /// The db function, the `Repository` function when missing
+    handler: Option<Path>,
+    /// The `shared::api::Endpoint` giving the path, the params and the response
+    endpoint: Option<Path>,
+}
+
+/// `"/languages/{id}/default" => put(set_default_handler)`
+struct ExtraRoute {
+    path: LitStr,
+    method_router: Expr,
+}
+
+impl Parse for ExtraRoute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let method_router = input.parse()?;
+        Ok(ExtraRoute {
+            path,
+            method_router,
+        })
+    }
+}
+
+pub struct RestRoutes {
+    vis: Visibility,
+    name: Ident,
+    state: Type,
+    resource: Type,
+    path: LitStr,
+    authorize: Option<LitStr>,
+    read_action: Option<LitStr>,
+    write_action: Option<LitStr>,
+    validate: Option<Path>,
+    list: Option<Operation>,
+    get: Option<Operation>,
+    create: Option<Operation>,
+    update: Option<Operation>,
+    delete: Option<Operation>,
+    read_routes: Vec<ExtraRoute>,
+    write_routes: Vec<ExtraRoute>,
+}
+
+fn extra_routes(input: ParseStream) -> syn::Result<Vec<ExtraRoute>> {
+    let content;
+    braced!(content in input);
+    let routes = Punctuated::<ExtraRoute, Token![,]>::parse_terminated(&content)?;
+    Ok(routes.into_iter().collect())
+}
+
+impl Parse for RestRoutes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // pub fn language_routes(AppState) for Language at "/languages" { ... }
+        let vis = input.parse()?;
+        input.parse::<Token![fn]>()?;
+        let name = input.parse()?;
+        let state_content;
+        parenthesized!(state_content in input);
+        let state = state_content.parse()?;
+        input.parse::<Token![for]>()?;
+        let resource = input.parse()?;
+        let at: Ident = input.parse()?;
+        if at != "at" {
+            return Err(syn::Error::new_spanned(at, "expected `at \"/path\"`"));
+        }
+        let path = input.parse()?;
+
+        let mut routes = RestRoutes {
+            vis,
+            name,
+            state,
+            resource,
+            path,
+            authorize: None,
+            read_action: None,
+            write_action: None,
+            validate: None,
+            list: None,
+            get: None,
+            create: None,
+            update: None,
+            delete: None,
+            read_routes: Vec::new(),
+            write_routes: Vec::new(),
+        };
+
+        let content;
+        braced!(content in input);
+        while !content.is_empty() {
+            let key: Ident = content.parse()?;
+            let id = if content.peek(syn::token::Paren) {
+                let id_content;
+                parenthesized!(id_content in content);
+                Some(id_content.parse::<Type>()?)
+            } else {
+                None
+            };
+            let has_value = content.parse::<Option<Token![:]>>()?.is_some();
+
+            match key.to_string().as_str() {
+                "authorize" => routes.authorize = Some(content.parse()?),
+                "read_action" => routes.read_action = Some(content.parse()?),
+                "write_action" => routes.write_action = Some(content.parse()?),
+                "validate" => routes.validate = Some(content.parse()?),
+                "read_routes" => routes.read_routes = extra_routes(&content)?,
+                "write_routes" => routes.write_routes = extra_routes(&content)?,
+                "list" | "get" | "create" | "update" | "delete" => {
+                    let handler = if has_value {
+                        Some(content.parse()?)
+                    } else {
+                        None
+                    };
+                    let endpoint = if content.parse::<Option<Token![=>]>>()?.is_some() {
+                        Some(content.parse::<Path>()?)
+                    } else {
+                        None
+                    };
+                    if let (Some(id), Some(_), false) = (&id, &endpoint, key == "list") {
+                        return Err(syn::Error::new_spanned(
+                            id,
+                            "the endpoint gives the type of `{id}`, remove it",
+                        ));
+                    }
+                    let operation = Some(Operation {
+                        id,
+                        handler,
+                        endpoint,
+                    });
+                    match key.to_string().as_str() {
+                        "list" => routes.list = operation,
+                        "get" => routes.get = operation,
+                        "create" => routes.create = operation,
+                        "update" => routes.update = operation,
+                        _ => routes.delete = operation,
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        key,
+                        "expected authorize, read_action, write_action, validate, list, get, \
+                         create, update, delete, read_routes or write_routes",
+                    ))
+                }
+            }
+
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        Ok(routes)
+    }
+}
+
+/// The last segment of the resource type, `Language` for `shared::Language`
+fn resource_name(resource: &Type) -> String {
+    match resource {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        _ => "Resource".to_string(),
+    }
+}
+
+/// The extractor of `{id}`, `Params<E>` with an endpoint
+fn id_extractor(operation: &Operation) -> TokenStream {
+    match (&operation.endpoint, &operation.id) {
+        (Some(endpoint), _) => quote! { crate::api::Params(id): crate::api::Params<#endpoint> },
+        (None, Some(id)) => quote! { ::axum::extract::Path(id): ::axum::extract::Path<#id> },
+        (None, None) => quote! { ::axum::extract::Path(id): ::axum::extract::Path<i32> },
+    }
+}
+
+/// The answer of a handler, `crate::api::reply` with an endpoint so the response type is checked
+fn reply(operation: &Operation, value: TokenStream, message: bool) -> TokenStream {
+    match (&operation.endpoint, message) {
+        (Some(endpoint), false) => quote! { crate::api::reply::<#endpoint>(#value) },
+        (Some(endpoint), true) => quote! { crate::api::reply::<#endpoint>(#value.to_string()) },
+        (None, false) => quote! { Ok(UserResponse::with_json(#value).into_response()) },
+        (None, true) => quote! { Ok(UserResponse::with_success(#value).into_response()) },
+    }
+}
+
+/// `.route(path, method(handler))`, or the handler at the path of the endpoint, which must use
+/// the method of the operation
+fn route(operation: &Operation, path: &str, method: &str, handler: &Ident) -> TokenStream {
+    let method_router = format_ident!("{}", method);
+    let Some(endpoint) = &operation.endpoint else {
+        return quote! { .route(#path, ::axum::routing::#method_router(#handler)) };
+    };
+    let variant = format_ident!("{}{}", method[..1].to_uppercase(), &method[1..]);
+    let message = format!(
+        "`{}` must be a {} endpoint",
+        quote!(#endpoint).to_string().replace(' ', ""),
+        variant
+    );
+    quote! {
+        .endpoint(#endpoint, {
+            const _: () = ::std::assert!(
+                ::std::matches!(
+                    <#endpoint as ::shared::api::Endpoint>::METHOD,
+                    ::shared::api::Method::#variant
+                ),
+                #message
+            );
+            #handler
+        })
+    }
+}
+
+pub fn expand(routes: RestRoutes) -> syn::Result<TokenStream> {
+    let RestRoutes {
+        vis,
+        name,
+        state,
+        resource,
+        path,
+        ..
+    } = &routes;
+    let resource_name = resource_name(resource);
+    let item_path = format!("{}/{{id}}", path.value().trim_end_matches('/'));
+
+    let validate = routes.validate.as_ref().map(|validate| {
+        quote! { #validate(&payload)?; }
+    });
+
+    let mut handlers = Vec::new();
+    let mut read_routes = Vec::new();
+    let mut write_routes = Vec::new();
+
+    if let Some(operation) = &routes.list {
+        // `list(ListQuery): f` reads the query string and answers with the page `f` returns
+        let handler = match (&operation.id, &operation.handler) {
+            (Some(query), Some(handler)) => {
+                let reply = reply(operation, quote! { page }, false);
+                quote! {
+                    async fn list_handler(
+                        ::axum::extract::State(state): ::axum::extract::State<#state>,
+                        crate::list_query::ListParams(query): crate::list_query::ListParams<#query>,
+                    ) -> crate::error::Result<::axum::response::Response> {
+                        let page = #handler(&state.pool, query).await?;
+                        #reply
+                    }
+                }
+            }
+            (Some(query), None) => {
+                return Err(syn::Error::new_spanned(
+                    query,
+                    "a list with a query needs its db function, `list(ListQuery): list_rows`",
+                ))
+            }
+            (None, handler) => {
+                let call = match handler {
+                    Some(handler) => quote! { #handler(&state.pool).await? },
+                    None => quote! { <#resource>::all(&*state.pool).await? },
+                };
+                let reply = reply(operation, quote! { rows }, false);
+                quote! {
+                    async fn list_handler(
+                        ::axum::extract::State(state): ::axum::extract::State<#state>,
+                    ) -> crate::error::Result<::axum::response::Response> {
+                        let rows = #call;
+                        #reply
+                    }
+                }
+            }
+        };
+        handlers.push(handler);
+        read_routes.push(route(
+            operation,
+            &path.value(),
+            "get",
+            &format_ident!("list_handler"),
+        ));
+    }
+
+    if let Some(operation) = &routes.get {
+        let id = id_extractor(operation);
+        let call = match &operation.handler {
+            Some(handler) => quote! { #handler(&state.pool, id).await? },
+            None => quote! { <#resource>::get(&*state.pool, id).await? },
+        };
+        let reply = reply(operation, quote! { row }, false);
+        handlers.push(quote! {
+            async fn get_handler(
+                ::axum::extract::State(state): ::axum::extract::State<#state>,
+                #id,
+            ) -> crate::error::Result<::axum::response::Response> {
+                let row = #call;
+                #reply
+            }
+        });
+        read_routes.push(route(
+            operation,
+            &item_path,
+            "get",
+            &format_ident!("get_handler"),
+        ));
+    }
+
+    // The writes without a db function go straight to the `Repository`, unaudited
+    let mut collection_methods = Vec::new();
+    for (operation, handler_name, verb, method) in [
+        (&routes.create, "create_handler", "Added", "post"),
+        (&routes.update, "update_handler", "Updated", "put"),
+    ] {
+        let Some(operation) = operation else {
+            continue;
+        };
+        let handler_name = format_ident!("{}", handler_name);
+        let message = format!("{} {} Successfully", resource_name, verb);
+        let reply = reply(operation, quote! { #message }, true);
+        let (actor, call) = match (&operation.handler, verb) {
+            (Some(handler), _) => (
+                quote! { actor },
+                quote! { #handler(&state.pool, &actor, payload).await?; },
+            ),
+            (None, "Added") => (
+                quote! { _actor },
+                quote! { payload.add(&*state.pool).await?; },
+            ),
+            (None, _) => (
+                quote! { _actor },
+                quote! { payload.update(&*state.pool).await?; },
+            ),
+        };
+        handlers.push(quote! {
+            async fn #handler_name(
+                ::axum::extract::State(state): ::axum::extract::State<#state>,
+                #actor: crate::audit::AuditActor,
+                payload: ::std::result::Result<
+                    ::axum::Json<#resource>,
+                    ::axum::extract::rejection::JsonRejection,
+                >,
+            ) -> crate::error::Result<::axum::response::Response> {
+                let payload = payload?.0;
+                #validate
+                #call
+                #reply
+            }
+        });
+        // The ones without an endpoint share one route
+        if operation.endpoint.is_some() {
+            write_routes.push(route(operation, &path.value(), method, &handler_name));
+        } else {
+            let method = format_ident!("{}", method);
+            collection_methods.push(quote! { #method(#handler_name) });
+        }
+    }
+    if let Some((first, rest)) = collection_methods.split_first() {
+        write_routes.push(quote! { .route(#path, ::axum::routing::#first #(.#rest)*) });
+    }
+
+    if let Some(operation) = &routes.delete {
+        let id = id_extractor(operation);
+        let message = format!("{} Deleted Successfully", resource_name);
+        let reply = reply(operation, quote! { #message }, true);
+        let (actor, call) = match &operation.handler {
+            Some(handler) => (
+                quote! { actor },
+                quote! { #handler(&state.pool, &actor, id).await?; },
+            ),
+            None => (
+                quote! { _actor },
+                quote! { <#resource>::delete(&*state.pool, id).await?; },
+            ),
+        };
+        handlers.push(quote! {
+            async fn delete_handler(
+                ::axum::extract::State(state): ::axum::extract::State<#state>,
+                #actor: crate::audit::AuditActor,
+                #id,
+            ) -> crate::error::Result<::axum::response::Response> {
+                #call
+                #reply
+            }
+        });
+        write_routes.push(route(
+            operation,
+            &item_path,
+            "delete",
+            &format_ident!("delete_handler"),
+        ));
+    }
+
+    for route in &routes.read_routes {
+        let (route_path, method_router) = (&route.path, &route.method_router);
+        read_routes.push(quote! { .route(#route_path, #method_router) });
+    }
+    for route in &routes.write_routes {
+        let (route_path, method_router) = (&route.path, &route.method_router);
+        write_routes.push(quote! { .route(#route_path, #method_router) });
+    }
+
+    // Signed in users, whose policies allow `action` on the resource type when there is one
+    let guard = |action: Option<&LitStr>| {
+        let authorize = action.map(|action| {
+            let resource_type = routes.authorize.as_ref().expect("checked by the caller");
+            quote! {
+                .route_layer(::axum::middleware::from_fn_with_state(
+                    crate::abac::Authorize::new(&state, #resource_type, #action),
+                    crate::abac::authorize,
+                ))
+            }
+        });
+        quote! {
+            #authorize
+            .route_layer(::axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::user::auth_middleware,
+            ))
+        }
+    };
+    if let (Some(action), None) = (&routes.read_action, &routes.authorize) {
+        return Err(syn::Error::new_spanned(
+            action,
+            "read_action needs `authorize: \"resource_type\"`",
+        ));
+    }
+    // Reads are public unless they have an action
+    let read_guard = routes
+        .read_action
+        .as_ref()
+        .map(|action| guard(Some(action)));
+    let write_action = routes
+        .write_action
+        .clone()
+        .unwrap_or_else(|| LitStr::new("write", path.span()));
+    let write_guard = guard(routes.authorize.as_ref().map(|_| &write_action));
+
+    let read_router = if read_routes.is_empty() {
+        quote! { ::axum::Router::new() }
+    } else {
+        quote! { ::axum::Router::new() #(#read_routes)* #read_guard }
+    };
+    let write_router = if write_routes.is_empty() {
+        quote! { ::axum::Router::new() }
+    } else {
+        quote! { ::axum::Router::new() #(#write_routes)* #write_guard }
+    };
+
+    // Only the guards need the state
+    let unused_state =
+        (read_guard.is_none() && write_routes.is_empty()).then(|| quote! { let _ = state; });
+
+    // Each import only when a handler needs it
+    let operations = [
+        &routes.list,
+        &routes.get,
+        &routes.create,
+        &routes.update,
+        &routes.delete,
+    ];
+    let (with_endpoint, without_endpoint): (Vec<_>, Vec<_>) = operations
+        .into_iter()
+        .flatten()
+        .partition(|operation| operation.endpoint.is_some());
+    let endpoint_router =
+        (!with_endpoint.is_empty()).then(|| quote! { use crate::api::EndpointRouter; });
+    let user_response = (!without_endpoint.is_empty())
+        .then(|| quote! { use crate::response::{IntoUserResponse, UserResponse}; });
+
+    Ok(quote! {
+        #vis fn #name(state: #state) -> ::axum::Router<#state> {
+            #user_response
+            #endpoint_router
+
+            #(#handlers)*
+
+            #unused_state
+            let write_routes = #write_router;
+            #read_router.merge(write_routes)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::*;
+
+    /// The expanded code without whitespace
+    fn expanded(input: TokenStream) -> String {
+        let routes = syn::parse2(input).expect("the input parses");
+        expand(routes)
+            .expect("the routes expand")
+            .to_string()
+            .split_whitespace()
+            .collect()
+    }
+
+    fn error(input: TokenStream) -> String {
+        match syn::parse2::<RestRoutes>(input) {
+            Ok(routes) => expand(routes).expect_err("an error").to_string(),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn endpoints_give_the_paths_and_the_replies() {
+        let code = expanded(quote! {
+            pub fn language_routes(AppState) for Language at "/languages" {
+                authorize: "language",
+                validate: validate_language,
+                list(ListQuery): list_languages => ListLanguages,
+                get: get_language => GetLanguage,
+                create: add_language => AddLanguage,
+                update: update_language => UpdateLanguage,
+                delete: delete_language => DeleteLanguage,
+            }
+        });
+        assert!(code.starts_with("pubfnlanguage_routes(state:AppState)->::axum::Router<AppState>"));
+        assert!(code.contains("usecrate::api::EndpointRouter;"));
+        assert!(!code.contains("UserResponse"));
+        assert!(!code.contains(".route("));
+        for (endpoint, method) in [
+            ("ListLanguages", "Get"),
+            ("GetLanguage", "Get"),
+            ("AddLanguage", "Post"),
+            ("UpdateLanguage", "Put"),
+            ("DeleteLanguage", "Delete"),
+        ] {
+            assert!(code.contains(&format!(".endpoint({},", endpoint)));
+            assert!(code.contains(&format!(
+                "<{}as::shared::api::Endpoint>::METHOD,::shared::api::Method::{}",
+                endpoint, method
+            )));
+        }
+        assert!(code.contains(
+            "crate::list_query::ListParams(query):crate::list_query::ListParams<ListQuery>"
+        ));
+        assert!(code.contains("letpage=list_languages(&state.pool,query).await?;crate::api::reply::<ListLanguages>(page)"));
+        assert!(code.contains("crate::api::Params(id):crate::api::Params<GetLanguage>"));
+        assert!(code.contains("add_language(&state.pool,&actor,payload).await?;"));
+        assert!(code.contains(
+            "crate::api::reply::<AddLanguage>(\"LanguageAddedSuccessfully\".to_string())"
+        ));
+        assert!(code.contains("delete_language(&state.pool,&actor,id).await?;"));
+        // Before both writes
+        assert_eq!(code.matches("validate_language(&payload)?;").count(), 2);
+    }
+
+    #[test]
+    fn without_endpoints_the_repository_is_called() {
+        let code = expanded(quote! {
+            fn setting_routes(AppState) for shared::Setting at "/settings/" {
+                list,
+                get(String),
+                create,
+                update,
+                delete(String),
+            }
+        });
+        assert!(code.contains("usecrate::response::{IntoUserResponse,UserResponse};"));
+        assert!(!code.contains("EndpointRouter"));
+        assert!(code.contains("letrows=<shared::Setting>::all(&*state.pool).await?;"));
+        assert!(code.contains(".route(\"/settings/\",::axum::routing::get(list_handler))"));
+        assert!(code.contains(".route(\"/settings/{id}\",::axum::routing::get(get_handler))"));
+        assert!(code.contains("::axum::extract::Path(id):::axum::extract::Path<String>"));
+        assert!(code.contains("<shared::Setting>::get(&*state.pool,id).await?"));
+        // Create and update share the collection route
+        assert!(code.contains(
+            ".route(\"/settings/\",::axum::routing::post(create_handler).put(update_handler))"
+        ));
+        assert!(code.contains("payload.add(&*state.pool).await?;"));
+        assert!(code.contains("payload.update(&*state.pool).await?;"));
+        assert!(code.contains("UserResponse::with_success(\"SettingAddedSuccessfully\")"));
+        assert!(code.contains("<shared::Setting>::delete(&*state.pool,id).await?;"));
+        assert!(code.contains("_actor:crate::audit::AuditActor"));
+    }
+
+    #[test]
+    fn the_id_is_an_i32_by_default() {
+        let code = expanded(quote! {
+            fn role_routes(AppState) for Role at "/roles" {
+                get: get_role,
+                delete: delete_role,
+            }
+        });
+        assert!(code.contains("::axum::extract::Path(id):::axum::extract::Path<i32>"));
+        assert!(code.contains("letrow=get_role(&state.pool,id).await?;"));
+        assert!(code.contains("Ok(UserResponse::with_json(row).into_response())"));
+    }
+
+    #[test]
+    fn writes_need_a_user_and_the_policies() {
+        let code = expanded(quote! {
+            fn language_routes(AppState) for Language at "/languages" {
+                authorize: "language",
+                write_action: "manage",
+                list: list_languages,
+                delete: delete_language,
+            }
+        });
+        assert!(code.contains(
+            "crate::abac::Authorize::new(&state,\"language\",\"manage\"),crate::abac::authorize"
+        ));
+        assert!(!code.contains("\"write\""));
+        // The reads are public
+        assert_eq!(code.matches("crate::user::auth_middleware").count(), 1);
+        assert!(code.contains(
+            "::axum::Router::new().route(\"/languages\",::axum::routing::get(list_handler)).merge(write_routes)"
+        ));
+
+        let code = expanded(quote! {
+            fn language_routes(AppState) for Language at "/languages" {
+                authorize: "language",
+                read_action: "view",
+                list: list_languages,
+                delete: delete_language,
+            }
+        });
+        assert!(code.contains("crate::abac::Authorize::new(&state,\"language\",\"view\")"));
+        assert!(code.contains("crate::abac::Authorize::new(&state,\"language\",\"write\")"));
+        assert_eq!(code.matches("crate::user::auth_middleware").count(), 2);
+    }
+
+    #[test]
+    fn writes_without_authorize_need_a_user_only() {
+        let code = expanded(quote! {
+            fn note_routes(AppState) for Note at "/notes" {
+                create: add_note,
+            }
+        });
+        assert!(!code.contains("crate::abac"));
+        assert_eq!(code.matches("crate::user::auth_middleware").count(), 1);
+    }
+
+    #[test]
+    fn public_reads_leave_the_state_unused() {
+        let code = expanded(quote! {
+            fn note_routes(AppState) for Note at "/notes" {
+                list: list_notes,
+            }
+        });
+        assert!(code.contains("let_=state;"));
+        assert!(code.contains("letwrite_routes=::axum::Router::new();"));
+    }
+
+    #[test]
+    fn custom_routes_are_behind_the_guards() {
+        let code = expanded(quote! {
+            fn language_routes(AppState) for Language at "/languages" {
+                authorize: "language",
+                read_action: "view",
+                read_routes: {
+                    "/languages/default" => get(default_language_handler),
+                },
+                write_routes: {
+                    "/languages/{id}/default" => put(set_default_language_handler),
+                },
+            }
+        });
+        // `let write_routes = ..; reads.merge(write_routes)`
+        let (_, routers) = code
+            .split_once("letwrite_routes=")
+            .expect("the write routes");
+        let (writes, reads) = routers.split_once(';').expect("the read routes");
+        assert!(writes
+            .contains(".route(\"/languages/{id}/default\",put(set_default_language_handler))"));
+        assert!(writes.contains("\"write\""));
+        assert!(reads.contains(".route(\"/languages/default\",get(default_language_handler))"));
+        assert!(reads.contains("\"view\""));
+    }
+
+    #[test]
+    fn invalid_routes_are_rejected() {
+        assert_eq!(
+            error(quote! {
+                fn language_routes(AppState) for Language on "/languages" {}
+            }),
+            "expected `at \"/path\"`"
+        );
+        assert!(error(quote! {
+            fn language_routes(AppState) for Language at "/languages" {
+                patch: patch_language,
+            }
+        })
+        .starts_with("expected authorize, read_action"));
+        assert_eq!(
+            error(quote! {
+                fn language_routes(AppState) for Language at "/languages" {
+                    get(i32): get_language => GetLanguage,
+                }
+            }),
+            "the endpoint gives the type of `{id}`, remove it"
+        );
+        assert_eq!(
+            error(quote! {
+                fn language_routes(AppState) for Language at "/languages" {
+                    list(ListQuery),
+                }
+            }),
+            "a list with a query needs its db function, `list(ListQuery): list_rows`"
+        );
+        assert_eq!(
+            error(quote! {
+                fn language_routes(AppState) for Language at "/languages" {
+                    read_action: "view",
+                    list: list_languages,
+                }
+            }),
+            "read_action needs `authorize: \"resource_type\"`"
+        );
+    }
+}
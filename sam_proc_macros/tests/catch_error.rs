@@ -0,0 +1,288 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use sam_error::{set_error_reporter, ErrorReport};
+use sam_proc_macros::catch_error;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    Layer, Registry,
+};
+
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    name: &'static str,
+    level: Level,
+    fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct EventRecord {
+    level: Level,
+    /// The span the event happened in
+    span: Option<&'static str>,
+    message: String,
+}
+
+#[derive(Debug, Default)]
+struct Records {
+    spans: Vec<SpanRecord>,
+    events: Vec<EventRecord>,
+}
+
+impl Records {
+    fn span(&self, name: &str) -> &SpanRecord {
+        self.spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("no span {}", name))
+    }
+}
+
+impl SpanRecord {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The arguments, without `elapsed_ms`
+    fn arguments(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .map(|(field, _)| field.as_str())
+            .filter(|field| *field != "elapsed_ms")
+            .collect()
+    }
+}
+
+struct Fields<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+/// Keeps every span and event
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Records>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _id: &span::Id, _ctx: Context<'_, S>) {
+        let mut fields = Vec::new();
+        attrs.record(&mut Fields(&mut fields));
+        self.0.lock().unwrap().spans.push(SpanRecord {
+            name: attrs.metadata().name(),
+            level: *attrs.metadata().level(),
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let name = ctx.span(id).expect("a known span").name();
+        let mut records = self.0.lock().unwrap();
+        if let Some(span) = records
+            .spans
+            .iter_mut()
+            .rev()
+            .find(|span| span.name == name)
+        {
+            values.record(&mut Fields(&mut span.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = Vec::new();
+        event.record(&mut Fields(&mut fields));
+        let message = fields
+            .into_iter()
+            .find(|(field, _)| field == "message")
+            .map(|(_, message)| message)
+            .unwrap_or_default();
+        self.0.lock().unwrap().events.push(EventRecord {
+            level: *event.metadata().level(),
+            span: ctx.event_span(event).map(|span| span.name()),
+            message,
+        });
+    }
+}
+
+/// Runs `f` with the recorder as the subscriber of this thread
+fn record(f: impl FnOnce()) -> Records {
+    let recorder = Recorder::default();
+    let subscriber = Registry::default().with(recorder.clone());
+    tracing::subscriber::with_default(subscriber, f);
+    let mut records = recorder.0.lock().unwrap();
+    std::mem::take(&mut *records)
+}
+
+/// Not `Debug`
+struct Pool;
+
+#[catch_error]
+fn find_user(id: i32, email: &str, _pool: &Pool) -> Result<String, String> {
+    if id > 0 {
+        Ok(email.to_string())
+    } else {
+        Err(format!("User {} not found", id))
+    }
+}
+
+#[catch_error(skip(password), level = "warn")]
+fn check_password(email: &str, password: &str) -> Result<bool, String> {
+    if email.is_empty() {
+        return Err("No email".to_string());
+    }
+    Ok(password.len() > 8)
+}
+
+#[catch_error(skip_all, span_level = "debug")]
+fn find_token(token: &str) -> Option<usize> {
+    token.find('.')
+}
+
+#[catch_error(persist)]
+fn save_file(name: &str) -> Result<(), String> {
+    Err(format!("Disk full while saving {}", name))
+}
+
+#[catch_error]
+fn load_file(name: &str) -> Result<(), String> {
+    Err(format!("Can't read {}", name))
+}
+
+#[catch_error(level = "info")]
+async fn fetch_user(id: i32) -> Result<i32, String> {
+    if id > 0 {
+        Ok(id)
+    } else {
+        Err("Negative id".to_string())
+    }
+}
+
+#[test]
+fn debug_arguments_are_recorded_and_the_others_are_opaque() {
+    let records = record(|| {
+        find_user(42, "a@b.c", &Pool).unwrap();
+    });
+    let span = records.span("find_user");
+    assert_eq!(span.field("id"), Some("42"));
+    assert_eq!(span.field("email"), Some("\"a@b.c\""));
+    assert_eq!(span.field("_pool"), Some("_"));
+    assert!(span.field("elapsed_ms").is_some());
+}
+
+#[test]
+fn the_span_is_info_by_default() {
+    let records = record(|| {
+        find_user(42, "a@b.c", &Pool).unwrap();
+        find_token("abc.def");
+    });
+    assert_eq!(records.span("find_user").level, Level::INFO);
+    assert_eq!(records.span("find_token").level, Level::DEBUG);
+}
+
+#[test]
+fn successes_are_not_logged() {
+    let records = record(|| {
+        find_user(42, "a@b.c", &Pool).unwrap();
+        check_password("a@b.c", "correct horse").unwrap();
+        find_token("abc.def").unwrap();
+    });
+    assert!(records.events.is_empty(), "{:?}", records.events);
+}
+
+#[test]
+fn failures_are_logged_in_the_span() {
+    let records = record(|| {
+        find_user(-1, "a@b.c", &Pool).unwrap_err();
+    });
+    let [event] = records.events.as_slice() else {
+        panic!("one event: {:?}", records.events);
+    };
+    assert_eq!(event.level, Level::ERROR);
+    assert_eq!(event.span, Some("find_user"));
+    assert!(event.message.contains("Function: find_user"));
+    assert!(event.message.contains("User -1 not found"));
+}
+
+#[test]
+fn level_sets_the_level_of_the_failure() {
+    let records = record(|| {
+        check_password("", "secret").unwrap_err();
+    });
+    assert_eq!(records.events[0].level, Level::WARN);
+    assert!(records.events[0].message.contains("No email"));
+}
+
+#[test]
+fn skipped_arguments_are_left_out() {
+    let records = record(|| {
+        check_password("a@b.c", "secret").unwrap();
+        find_token("abc").unwrap_or_default();
+    });
+    assert_eq!(records.span("check_password").arguments(), ["email"]);
+    assert!(records.span("find_token").arguments().is_empty());
+    assert!(records.span("find_token").field("elapsed_ms").is_some());
+}
+
+#[test]
+fn none_is_a_failure() {
+    let records = record(|| {
+        assert_eq!(find_token("abc"), None);
+    });
+    assert_eq!(records.events[0].level, Level::ERROR);
+    assert!(records.events[0].message.contains("returned None"));
+}
+
+#[test]
+fn async_functions_run_in_the_span() {
+    let records = record(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            fetch_user(7).await.unwrap();
+            fetch_user(-7).await.unwrap_err();
+        });
+    });
+    let spans: Vec<_> = records
+        .spans
+        .iter()
+        .filter(|span| span.name == "fetch_user")
+        .collect();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[1].field("id"), Some("-7"));
+    let [event] = records.events.as_slice() else {
+        panic!("one event: {:?}", records.events);
+    };
+    assert_eq!(event.level, Level::INFO);
+    assert_eq!(event.span, Some("fetch_user"));
+    assert!(event.message.contains("Negative id"));
+}
+
+#[test]
+fn persist_reports_the_failure() {
+    static REPORTS: Mutex<Vec<ErrorReport>> = Mutex::new(Vec::new());
+    set_error_reporter(|report| REPORTS.lock().unwrap().push(report));
+
+    save_file("notes.txt").unwrap_err();
+    load_file("notes.txt").unwrap_err();
+
+    let reports = REPORTS.lock().unwrap();
+    let [report] = reports.as_slice() else {
+        panic!("one report: {:?}", reports);
+    };
+    assert_eq!(report.message, "Disk full while saving notes.txt");
+    assert_eq!(report.module, "catch_error");
+    assert!(report.file.ends_with("catch_error.rs"));
+}
@@ -0,0 +1,6 @@
+#[test]
+fn repository() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/repository/pass_*.rs");
+    cases.compile_fail("tests/ui/repository/fail_*.rs");
+}
@@ -0,0 +1,5 @@
+#[test]
+fn rest_routes() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/rest_routes/fail_*.rs");
+}
@@ -0,0 +1,10 @@
+use sam_proc_macros::Repository;
+
+#[derive(Repository)]
+#[table = "languages"]
+pub struct Language {
+    #[repository(serial)]
+    pub id: i32,
+}
+
+fn main() {}
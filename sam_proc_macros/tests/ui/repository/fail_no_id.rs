@@ -0,0 +1,9 @@
+use sam_proc_macros::Repository;
+
+#[derive(Repository)]
+#[table = "languages"]
+pub struct Language {
+    pub code: String,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use sam_proc_macros::Repository;
+
+// `restore` comes with soft delete only
+#[derive(sqlx::FromRow, Repository)]
+#[table = "languages"]
+pub struct Language {
+    pub id: i32,
+}
+
+#[allow(dead_code)]
+async fn calls(pool: &sqlx::PgPool) {
+    let _ = Language::restore(pool, 1).await;
+}
+
+fn main() {}
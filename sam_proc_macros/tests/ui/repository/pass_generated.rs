@@ -0,0 +1,33 @@
+use sam_error::SamError;
+use sam_proc_macros::Repository;
+use sqlx::PgPool;
+
+#[derive(sqlx::FromRow, Repository)]
+#[table = "languages"]
+pub struct Language {
+    #[repository(generated)]
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+}
+
+// Every function takes a pool or a transaction
+#[allow(dead_code)]
+async fn calls(pool: &PgPool, language: Language) -> Result<(), SamError> {
+    let _: Vec<Language> = Language::list(pool, 2, 20).await?;
+    let _: Vec<Language> = Language::all(pool).await?;
+    let _: i64 = Language::count(pool).await?;
+    let _: Language = Language::get(pool, 1).await?;
+    let mut tx = pool.begin().await?;
+    let added: Language = language.add(&mut *tx).await?;
+    let _: Language = added.update(&mut *tx).await?;
+    Language::delete(&mut *tx, added.id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+fn main() {
+    assert_eq!(Language::TABLE, "languages");
+    assert_eq!(Language::COLUMNS, "id, code, name");
+    assert_eq!(Language::SOFT_DELETE, None);
+}
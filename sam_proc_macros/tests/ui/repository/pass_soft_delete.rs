@@ -0,0 +1,24 @@
+use sam_error::SamError;
+use sam_proc_macros::Repository;
+use sqlx::PgPool;
+
+#[derive(sqlx::FromRow, Repository)]
+#[table = "categories"]
+#[soft_delete = "deleted_at"]
+pub struct Category {
+    #[repository(id)]
+    pub code: String,
+    pub parent_id: Option<String>,
+}
+
+#[allow(dead_code)]
+async fn calls(pool: &PgPool) -> Result<(), SamError> {
+    Category::delete(pool, "news".to_string()).await?;
+    Category::restore(pool, "news".to_string()).await?;
+    Ok(())
+}
+
+fn main() {
+    assert_eq!(Category::COLUMNS, "code, parent_id");
+    assert_eq!(Category::SOFT_DELETE, Some("deleted_at"));
+}
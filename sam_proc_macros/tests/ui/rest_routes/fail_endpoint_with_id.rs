@@ -0,0 +1,10 @@
+use sam_proc_macros::rest_routes;
+
+// The endpoint declares the params already
+rest_routes! {
+    pub fn language_routes(AppState) for Language at "/languages" {
+        get(i32): get_language => GetLanguage,
+    }
+}
+
+fn main() {}
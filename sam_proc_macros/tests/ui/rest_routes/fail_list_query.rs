@@ -0,0 +1,9 @@
+use sam_proc_macros::rest_routes;
+
+rest_routes! {
+    pub fn language_routes(AppState) for Language at "/languages" {
+        list(ListQuery) => ListLanguages,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use sam_proc_macros::rest_routes;
+
+rest_routes! {
+    pub fn language_routes(AppState) for Language at "/languages" {
+        read_action: "view",
+        list: list_languages,
+    }
+}
+
+fn main() {}
@@ -31,6 +31,37 @@ pub async fn put_json(url: &str, payload: &impl serde::Serialize) -> Result<Resp
     }
 }
 
+/// Sends a raw body, e.g. YAML, with its content type
+pub async fn post_text(url: &str, content_type: &str, body: &str) -> Result<Response, String> {
+    let result = Request::post(url)
+        .header("Content-Type", content_type)
+        .credentials(RequestCredentials::Include)
+        .body(body);
+
+    match result {
+        Ok(req) => req
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e)),
+        Err(e) => return Err(format!("Request failed: {}", e)),
+    }
+}
+
+/// Sends a multipart form (e.g. file uploads), the browser sets the content type
+pub async fn post_form(url: &str, form: web_sys::FormData) -> Result<Response, String> {
+    let result = Request::post(url)
+        .credentials(RequestCredentials::Include)
+        .body(form);
+
+    match result {
+        Ok(req) => req
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e)),
+        Err(e) => return Err(format!("Request failed: {}", e)),
+    }
+}
+
 pub async fn fetch_data(url: &str) -> Result<Response, String> {
     Request::get(url)
         .credentials(RequestCredentials::Include)
@@ -40,6 +71,21 @@ pub async fn fetch_data(url: &str) -> Result<Response, String> {
         .map_err(|e| format!("Request failed: {}", e))
 }
 
+pub async fn delete_json(url: &str, payload: &impl serde::Serialize) -> Result<Response, String> {
+    let result = Request::delete(url)
+        .header("Content-Type", "application/json")
+        .credentials(RequestCredentials::Include)
+        .json(payload);
+
+    match result {
+        Ok(req) => req
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e)),
+        Err(e) => return Err(format!("Request failed: {}", e)),
+    }
+}
+
 pub async fn delete_entity(url: &str) -> Result<Response, String> {
     Request::delete(url)
         .credentials(RequestCredentials::Include)
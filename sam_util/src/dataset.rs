@@ -1,5 +1,5 @@
 use polars::io::json::JsonWriter;
-use polars::prelude::JsonFormat;
+use polars::prelude::{CsvWriter, JsonFormat};
 use polars::{
     frame::DataFrame,
     prelude::{AnyValue, SerWriter},
@@ -94,3 +94,14 @@ pub fn df_to_json(df: &mut DataFrame) -> Result<Value, String> {
     let json_str = String::from_utf8(buf).map_err(|e| format!("UTF-8 conversion error: {}", e))?;
     serde_json::from_str(&json_str).map_err(|e| e.to_string())
 }
+
+pub fn df_to_csv(df: &mut DataFrame) -> Result<String, String> {
+    let mut buf = Vec::new();
+
+    CsvWriter::new(&mut buf)
+        .include_header(true)
+        .finish(df)
+        .map_err(|e| format!("Polars CSV write error: {}", e))?;
+
+    String::from_utf8(buf).map_err(|e| format!("UTF-8 conversion error: {}", e))
+}
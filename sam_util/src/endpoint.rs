@@ -0,0 +1,117 @@
+use gloo_net::http::Response;
+use shared::{
+    api::{Endpoint, Method},
+    list::{ListQuery, Page},
+    user::{UserResponse, UserResponseData},
+};
+
+use crate::{delete_entity, fetch_data, post_json, put_json};
+
+/// # Calls an endpoint declared in `shared::api`
+/// The response is decoded into the endpoint's response type, the error is the message of the
+/// backend.
+///
+/// ```ignore
+/// let page = call_endpoint::<ListErrors>(BASE_URL, &(), &filter).await?;
+/// let message = call_endpoint::<SetErrorStatus>(BASE_URL, &id, &payload).await?;
+/// ```
+pub async fn call_endpoint<E: Endpoint>(
+    base_url: &str,
+    params: &E::Params,
+    request: &E::Request,
+) -> Result<E::Response, String> {
+    let url = endpoint_url::<E>(base_url, params);
+    let result = match E::METHOD {
+        Method::Get | Method::Delete => {
+            let query = serde_urlencoded::to_string(request)
+                .map_err(|e| format!("Invalid query: {}", e))?;
+            let url = if query.is_empty() {
+                url
+            } else {
+                format!("{}?{}", url, query)
+            };
+            if E::METHOD == Method::Get {
+                fetch_data(&url).await
+            } else {
+                delete_entity(&url).await
+            }
+        }
+        Method::Post => post_json(&url, request).await,
+        Method::Put => put_json(&url, request).await,
+    };
+    read_response::<E::Response>(result?).await
+}
+
+/// # The URL of an endpoint
+/// For the calls `call_endpoint` can't make, e.g. multipart uploads or download links.
+///
+/// ```ignore
+/// let url = endpoint_url::<UploadAvatar>(BASE_URL, &());
+/// let res = post_form(&url, form).await?;
+/// ```
+pub fn endpoint_url<E: Endpoint>(base_url: &str, params: &E::Params) -> String {
+    format!("{}{}", base_url, E::path(params))
+}
+
+/// # Calls a list endpoint
+/// Like `call_endpoint`, with the `ListQuery` written the way the backend's `ListParams` reads
+/// it, its filters don't fit in a flat query string.
+///
+/// ```ignore
+/// let page = call_list_endpoint::<ListLanguages>(BASE_URL, &(), &query).await?;
+/// ```
+pub async fn call_list_endpoint<E: Endpoint<Request = ListQuery>>(
+    base_url: &str,
+    params: &E::Params,
+    query: &ListQuery,
+) -> Result<E::Response, String> {
+    let url = format!(
+        "{}?{}",
+        endpoint_url::<E>(base_url, params),
+        query.to_query_string()
+    );
+    read_response::<E::Response>(fetch_data(&url).await?).await
+}
+
+/// # Every row of a list endpoint
+/// Reads the pages of `query` one after the other, following `next_cursor` to the last one,
+/// e.g. for the options of a select.
+///
+/// ```ignore
+/// let query = ListQuery::default().with_per_page(ListQuery::MAX_PER_PAGE);
+/// let languages = call_all_pages::<ListLanguages, _>(BASE_URL, &(), &query).await?;
+/// ```
+pub async fn call_all_pages<E, T>(
+    base_url: &str,
+    params: &E::Params,
+    query: &ListQuery,
+) -> Result<Vec<T>, String>
+where
+    E: Endpoint<Request = ListQuery, Response = Page<T>>,
+{
+    let mut query = query.clone();
+    let mut rows = Vec::new();
+    loop {
+        let page = call_list_endpoint::<E>(base_url, params, &query).await?;
+        rows.extend(page.items);
+        match page.next_cursor {
+            Some(cursor) => query = query.with_cursor(cursor),
+            None => return Ok(rows),
+        }
+    }
+}
+
+async fn read_response<T: serde::de::DeserializeOwned>(res: Response) -> Result<T, String> {
+    let user_res = res
+        .json::<UserResponse>()
+        .await
+        .map_err(|e| format!("Invalid response: {}", e))?;
+    if !res.ok() || !user_res.success {
+        return Err(user_res.message());
+    }
+    let value = match user_res.data {
+        UserResponseData::Json(json) => json,
+        UserResponseData::String(msg) => serde_json::Value::String(msg),
+    };
+    serde_json::from_value::<T>(value).map_err(|e| format!("Invalid response: {}", e))
+}
@@ -13,6 +13,11 @@ pub use web::to_js_array;
 mod api;
 pub use api::*;
 
+#[cfg(feature = "api")]
+mod endpoint;
+#[cfg(feature = "api")]
+pub use endpoint::*;
+
 mod browser;
 pub use browser::*;
 
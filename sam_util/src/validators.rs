@@ -1,37 +1,285 @@
-use regex::Regex;
+use std::{collections::HashSet, fmt, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
 use validator::ValidateEmail;
 
-pub fn validate_password(password: &str, password_len: usize) -> Result<(), String> {
-    if password.len() == 0 {
-        return Err("Empty not allowed".to_string());
+const COMMON_PASSWORDS: &str = include_str!("../assets/common_passwords.txt");
+
+/// ## Password rules
+///
+/// All failures are collected and returned at once by [`PasswordPolicy::validate`].
+///
+/// ```
+/// use sam_util::validators::PasswordPolicy;
+///
+/// let policy = PasswordPolicy {
+///     min_length: 10,
+///     banned_words: vec!["acme".to_string()],
+///     ..Default::default()
+/// };
+/// if let Err(errors) = policy.validate("acme2024") {
+///     // e.g. [TooShort { min: 10 }, MissingUppercase, MissingSymbol, ContainsBannedWord { word: "acme" }]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Case insensitive words the password must not contain (e.g. the site name)
+    pub banned_words: Vec<String>,
+    /// Reject passwords found in the bundled list of common passwords
+    pub reject_common: bool,
+    /// Minimum strength score from 0 (very weak) to 4 (very strong)
+    pub min_strength: u8,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            banned_words: Vec::new(),
+            reject_common: true,
+            min_strength: 2,
+        }
     }
-    if password.len() < password_len {
-        return Err(format!(
-            "Your password must be at least {} characters long",
-            password_len
-        ));
+}
+
+/// A single failed password rule.
+/// The `code` tag is stable so the frontend can map it to a localized message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum PasswordError {
+    Empty,
+    TooShort { min: usize },
+    TooLong { max: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    ContainsBannedWord { word: String },
+    Common,
+    TooWeak { score: u8, min: u8 },
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::Empty => write!(f, "Empty not allowed"),
+            PasswordError::TooShort { min } => {
+                write!(f, "Your password must be at least {} characters long", min)
+            }
+            PasswordError::TooLong { max } => {
+                write!(f, "Your password must be at most {} characters long", max)
+            }
+            PasswordError::MissingLowercase => {
+                write!(
+                    f,
+                    "Your password must contain at least one lowercase letter"
+                )
+            }
+            PasswordError::MissingUppercase => {
+                write!(
+                    f,
+                    "Your password must contain at least one uppercase letter"
+                )
+            }
+            PasswordError::MissingDigit => {
+                write!(f, "Your password must contain at least one number")
+            }
+            PasswordError::MissingSymbol => {
+                write!(f, "Your password must contain at least one symbol")
+            }
+            PasswordError::ContainsBannedWord { word } => {
+                write!(f, "Your password must not contain \"{}\"", word)
+            }
+            PasswordError::Common => write!(f, "This password is too common"),
+            PasswordError::TooWeak { .. } => write!(f, "Your password is too easy to guess"),
+        }
     }
+}
+
+impl PasswordPolicy {
+    pub fn validate(&self, password: &str) -> Result<(), Vec<PasswordError>> {
+        if password.is_empty() {
+            return Err(vec![PasswordError::Empty]);
+        }
+
+        let mut errors = Vec::new();
+        let len = password.chars().count();
+        if len < self.min_length {
+            errors.push(PasswordError::TooShort {
+                min: self.min_length,
+            });
+        }
+        if len > self.max_length {
+            errors.push(PasswordError::TooLong {
+                max: self.max_length,
+            });
+        }
+
+        let classes = CharClasses::of(password);
+        if self.require_lowercase && !classes.lowercase {
+            errors.push(PasswordError::MissingLowercase);
+        }
+        if self.require_uppercase && !classes.uppercase {
+            errors.push(PasswordError::MissingUppercase);
+        }
+        if self.require_symbol && !classes.symbol {
+            errors.push(PasswordError::MissingSymbol);
+        }
+        if self.require_digit && !classes.digit {
+            errors.push(PasswordError::MissingDigit);
+        }
 
-    let lowercase_regex = Regex::new(r"[a-z]").unwrap();
-    if !lowercase_regex.is_match(password) {
-        return Err("Your password must contain at least one lowercase letter".to_string());
+        let lowercase = password.to_lowercase();
+        for word in &self.banned_words {
+            if !word.is_empty() && lowercase.contains(&word.to_lowercase()) {
+                errors.push(PasswordError::ContainsBannedWord { word: word.clone() });
+            }
+        }
+
+        let common = is_common_password(password);
+        if self.reject_common && common {
+            errors.push(PasswordError::Common);
+        }
+
+        if self.min_strength > 0 && !common {
+            let score = password_strength(password);
+            if score < self.min_strength {
+                errors.push(PasswordError::TooWeak {
+                    score,
+                    min: self.min_strength,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
+}
 
-    let uppercase_regex = Regex::new(r"[A-Z]").unwrap();
-    if !uppercase_regex.is_match(password) {
-        return Err("Your password must contain at least one uppercase letter".to_string());
+struct CharClasses {
+    lowercase: bool,
+    uppercase: bool,
+    digit: bool,
+    symbol: bool,
+    other: bool,
+}
+
+impl CharClasses {
+    fn of(password: &str) -> Self {
+        let mut classes = CharClasses {
+            lowercase: false,
+            uppercase: false,
+            digit: false,
+            symbol: false,
+            other: false,
+        };
+        for c in password.chars() {
+            match c {
+                'a'..='z' => classes.lowercase = true,
+                'A'..='Z' => classes.uppercase = true,
+                '0'..='9' => classes.digit = true,
+                c if c.is_ascii_punctuation() || c == ' ' => classes.symbol = true,
+                _ => classes.other = true,
+            }
+        }
+        classes
     }
 
-    let symbol_regex = Regex::new(r"[!@#$%^&*(),.?:{}|<>]").unwrap();
-    if !symbol_regex.is_match(password) {
-        return Err("Your password must contain at least one symbol".to_string());
+    fn pool_size(&self) -> f64 {
+        let mut size = 0.0;
+        if self.lowercase {
+            size += 26.0;
+        }
+        if self.uppercase {
+            size += 26.0;
+        }
+        if self.digit {
+            size += 10.0;
+        }
+        if self.symbol {
+            size += 33.0;
+        }
+        if self.other {
+            size += 100.0;
+        }
+        size
     }
+}
 
-    let number_regex = Regex::new(r"[0-9]").unwrap();
-    if !number_regex.is_match(password) {
-        return Err("Your password must contain at least one number".to_string());
+/// Checks the password against the bundled list of common passwords (case insensitive)
+pub fn is_common_password(password: &str) -> bool {
+    static COMMON: OnceLock<HashSet<String>> = OnceLock::new();
+    COMMON
+        .get_or_init(|| {
+            COMMON_PASSWORDS
+                .lines()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .contains(&password.to_lowercase())
+}
+
+/// ## Estimate password strength
+/// Returns a zxcvbn-like score from 0 (too guessable) to 4 (very unguessable).
+///
+/// The estimate is based on the entropy of the used character classes,
+/// with penalties for repeated characters and keyboard/alphabet sequences.
+pub fn password_strength(password: &str) -> u8 {
+    if password.is_empty() || is_common_password(password) {
+        return 0;
+    }
+
+    let chars: Vec<char> = password.to_lowercase().chars().collect();
+
+    // Characters that repeat or continue a sequence (abc, 123, cba) add almost nothing
+    let mut effective_len = 1.0;
+    for window in chars.windows(2) {
+        let (prev, cur) = (window[0] as i64, window[1] as i64);
+        if prev == cur || (cur - prev).abs() == 1 {
+            effective_len += 0.25;
+        } else {
+            effective_len += 1.0;
+        }
+    }
+
+    let bits = effective_len * CharClasses::of(password).pool_size().log2();
+    match bits {
+        b if b < 28.0 => 0,
+        b if b < 36.0 => 1,
+        b if b < 60.0 => 2,
+        b if b < 80.0 => 3,
+        _ => 4,
     }
-    Ok(())
+}
+
+/// Validates the password length and character classes.
+/// Use [`PasswordPolicy`] to get every failure at once or for more rules.
+pub fn validate_password(password: &str, password_len: usize) -> Result<(), String> {
+    let policy = PasswordPolicy {
+        min_length: password_len,
+        max_length: usize::MAX,
+        reject_common: false,
+        min_strength: 0,
+        ..Default::default()
+    };
+    policy
+        .validate(password)
+        .map_err(|errors| errors[0].to_string())
 }
 
 pub fn validate_email(email: &str) -> Result<(), String> {
@@ -43,3 +291,179 @@ pub fn validate_email(email: &str) -> Result<(), String> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRONG: &str = "T7#kq!Vz9@Lm2$Rw";
+
+    #[test]
+    fn a_strong_password_passes() {
+        assert_eq!(PasswordPolicy::default().validate(STRONG), Ok(()));
+    }
+
+    #[test]
+    fn empty_is_the_only_error() {
+        assert_eq!(
+            PasswordPolicy::default().validate(""),
+            Err(vec![PasswordError::Empty])
+        );
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        let policy = PasswordPolicy {
+            min_length: 4,
+            max_length: 6,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            reject_common: false,
+            min_strength: 0,
+            ..Default::default()
+        };
+        assert_eq!(policy.validate("äöüß"), Ok(()));
+        assert_eq!(
+            policy.validate("äöü"),
+            Err(vec![PasswordError::TooShort { min: 4 }])
+        );
+        assert_eq!(
+            policy.validate("abcdefg"),
+            Err(vec![PasswordError::TooLong { max: 6 }])
+        );
+    }
+
+    #[test]
+    fn every_character_class_is_checked() {
+        let policy = PasswordPolicy {
+            min_length: 1,
+            min_strength: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.validate("ABC1!"),
+            Err(vec![PasswordError::MissingLowercase])
+        );
+        assert_eq!(
+            policy.validate("abc1!"),
+            Err(vec![PasswordError::MissingUppercase])
+        );
+        assert_eq!(
+            policy.validate("Abc1x"),
+            Err(vec![PasswordError::MissingSymbol])
+        );
+        assert_eq!(
+            policy.validate("Abc!x"),
+            Err(vec![PasswordError::MissingDigit])
+        );
+        let relaxed = PasswordPolicy {
+            require_uppercase: false,
+            require_symbol: false,
+            require_digit: false,
+            ..policy
+        };
+        assert_eq!(relaxed.validate("abcx"), Ok(()));
+    }
+
+    #[test]
+    fn banned_words_are_case_insensitive() {
+        let policy = PasswordPolicy {
+            banned_words: vec!["Acme".to_string(), String::new()],
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.validate("x9$ACMEqZ!v7Lw"),
+            Err(vec![PasswordError::ContainsBannedWord {
+                word: "Acme".to_string()
+            }])
+        );
+    }
+
+    #[test]
+    fn common_passwords_are_rejected() {
+        assert!(is_common_password("PassWord"));
+        assert!(!is_common_password(STRONG));
+        let policy = PasswordPolicy {
+            min_length: 1,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            ..Default::default()
+        };
+        // A common password isn't also reported as weak
+        assert_eq!(
+            policy.validate("password"),
+            Err(vec![PasswordError::Common])
+        );
+        let allowed = PasswordPolicy {
+            reject_common: false,
+            min_strength: 0,
+            ..policy
+        };
+        assert_eq!(allowed.validate("password"), Ok(()));
+    }
+
+    #[test]
+    fn strength_grows_with_length_and_classes() {
+        assert_eq!(password_strength(""), 0);
+        assert_eq!(password_strength("password"), 0);
+        assert_eq!(password_strength("abcdefgh"), 0);
+        assert!(password_strength("kqvzlmrw") < password_strength("kqvzlmrwT7#!"));
+        assert_eq!(password_strength(STRONG), 4);
+
+        let policy = PasswordPolicy {
+            min_length: 1,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            reject_common: false,
+            min_strength: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.validate("zzzzqqqq"),
+            Err(vec![PasswordError::TooWeak {
+                score: password_strength("zzzzqqqq"),
+                min: 4
+            }])
+        );
+    }
+
+    #[test]
+    fn all_failures_are_returned_at_once() {
+        let policy = PasswordPolicy {
+            min_length: 10,
+            banned_words: vec!["acme".to_string()],
+            min_strength: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.validate("acme2024"),
+            Err(vec![
+                PasswordError::TooShort { min: 10 },
+                PasswordError::MissingUppercase,
+                PasswordError::MissingSymbol,
+                PasswordError::ContainsBannedWord {
+                    word: "acme".to_string()
+                },
+                PasswordError::TooWeak {
+                    score: password_strength("acme2024"),
+                    min: 4
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn validate_password_returns_the_first_message() {
+        assert_eq!(validate_password("Ab1!efgh", 8), Ok(()));
+        assert_eq!(
+            validate_password("ab", 8),
+            Err("Your password must be at least 8 characters long".to_string())
+        );
+    }
+}